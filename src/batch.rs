@@ -0,0 +1,745 @@
+//! Running many independent queries concurrently.
+//!
+//! [`BatchRunner`] runs prompts through [`query`](crate::query()) with bounded
+//! concurrency, a shared token-bucket rate limit, retries of failed API calls
+//! per a [`RetryPolicy`], and an optional budget across all results.
+
+use futures::stream::BoxStream;
+use futures::{Stream, StreamExt};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+use crate::error::Result;
+use crate::query::query;
+use crate::retry::{RetryAttempt, RetryPolicy};
+use crate::types::{AssistantMessageError, ClaudeAgentOptions, Message, ResultMessage};
+
+/// Runs one prompt and collects its messages.
+///
+/// The default executor calls [`query`](crate::query()); a custom one can
+/// route prompts elsewhere, e.g. through a [`ClientPool`](crate::pool::ClientPool).
+pub type BatchExecutor = Arc<
+    dyn Fn(String, ClaudeAgentOptions) -> Pin<Box<dyn Future<Output = Result<Vec<Message>>> + Send>>
+        + Send
+        + Sync,
+>;
+
+/// One prompt in a batch, optionally with its own options.
+#[derive(Debug, Clone)]
+pub struct BatchItem {
+    pub prompt: String,
+    /// Options for this item; the runner's options are used when `None`.
+    pub options: Option<ClaudeAgentOptions>,
+}
+
+impl BatchItem {
+    pub fn new(prompt: impl Into<String>) -> Self {
+        Self {
+            prompt: prompt.into(),
+            options: None,
+        }
+    }
+
+    pub fn with_options(mut self, options: ClaudeAgentOptions) -> Self {
+        self.options = Some(options);
+        self
+    }
+}
+
+impl From<String> for BatchItem {
+    fn from(prompt: String) -> Self {
+        Self::new(prompt)
+    }
+}
+
+impl From<&str> for BatchItem {
+    fn from(prompt: &str) -> Self {
+        Self::new(prompt)
+    }
+}
+
+/// Token-bucket rate limit shared by all items in a batch.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    /// Requests allowed per `per`.
+    pub requests: u32,
+    pub per: Duration,
+    /// Requests that may start at once before the limit applies.
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// Allow `requests` per `per`, with a burst of the same size.
+    pub fn new(requests: u32, per: Duration) -> Self {
+        Self {
+            requests,
+            per,
+            burst: requests,
+        }
+    }
+
+    pub fn per_minute(requests: u32) -> Self {
+        Self::new(requests, Duration::from_secs(60))
+    }
+
+    pub fn with_burst(mut self, burst: u32) -> Self {
+        self.burst = burst;
+        self
+    }
+}
+
+/// Settings for a [`BatchRunner`].
+#[derive(Debug, Clone)]
+pub struct BatchConfig {
+    /// Maximum number of queries running at once.
+    pub concurrency: usize,
+    pub rate_limit: Option<RateLimit>,
+    /// When and how long to wait before rerunning an item that failed with
+    /// a retryable API error.
+    pub retry: RetryPolicy,
+    /// Stop starting new items once total cost exceeds this many USD.
+    pub budget_usd: Option<f64>,
+    /// Yield results in input order instead of as they complete.
+    pub ordered: bool,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            concurrency: 4,
+            rate_limit: None,
+            retry: RetryPolicy::default(),
+            budget_usd: None,
+            ordered: false,
+        }
+    }
+}
+
+impl BatchConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn with_rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    pub fn with_budget_usd(mut self, budget: f64) -> Self {
+        self.budget_usd = Some(budget);
+        self
+    }
+
+    pub fn with_ordered(mut self, ordered: bool) -> Self {
+        self.ordered = ordered;
+        self
+    }
+}
+
+/// Messages produced by one completed item.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchOutput {
+    pub messages: Vec<Message>,
+}
+
+impl BatchOutput {
+    /// The final result message, if the query produced one.
+    pub fn result(&self) -> Option<&ResultMessage> {
+        self.messages
+            .iter()
+            .rev()
+            .find_map(|message| match message {
+                Message::Result(result) => Some(result),
+                _ => None,
+            })
+    }
+
+    /// Total cost reported by the result messages.
+    pub fn cost_usd(&self) -> f64 {
+        cost_of(&self.messages)
+    }
+
+    /// The first API error reported by an assistant message.
+    pub fn api_error(&self) -> Option<AssistantMessageError> {
+        self.messages.iter().find_map(|message| match message {
+            Message::Assistant(assistant) => assistant.error,
+            _ => None,
+        })
+    }
+}
+
+/// What happened to one item.
+#[derive(Debug)]
+pub enum BatchOutcome {
+    /// The query ran; retries may have been exhausted, see [`BatchOutput::api_error`].
+    Completed(BatchOutput),
+    /// The query failed to run.
+    Failed(crate::error::ClaudeSDKError),
+    /// Not started because the budget was already spent.
+    Skipped,
+}
+
+/// Result for one item of a batch.
+#[derive(Debug)]
+pub struct BatchResult {
+    /// Position of the item in the input.
+    pub index: usize,
+    pub prompt: String,
+    /// Number of times the query was run.
+    pub attempts: u32,
+    pub outcome: BatchOutcome,
+}
+
+impl BatchResult {
+    pub fn output(&self) -> Option<&BatchOutput> {
+        match &self.outcome {
+            BatchOutcome::Completed(output) => Some(output),
+            _ => None,
+        }
+    }
+}
+
+/// Runs batches of independent queries.
+///
+/// ```no_run
+/// # async fn example() {
+/// use claude_agent_sdk::batch::{BatchConfig, BatchRunner, RateLimit};
+/// use claude_agent_sdk::ClaudeAgentOptions;
+/// use futures::StreamExt;
+///
+/// let runner = BatchRunner::new(
+///     ClaudeAgentOptions::builder().max_turns(1).build(),
+///     BatchConfig::new()
+///         .with_concurrency(8)
+///         .with_rate_limit(RateLimit::per_minute(60))
+///         .with_budget_usd(5.0),
+/// );
+///
+/// let prompts = futures::stream::iter(vec!["What is 2 + 2?", "Name a prime"]);
+/// let mut results = runner.run(prompts);
+/// while let Some(result) = results.next().await {
+///     println!("#{}: {:?}", result.index, result.output().and_then(|o| o.result()));
+/// }
+/// println!("spent ${:.4}", runner.spent_usd().await);
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct BatchRunner {
+    options: ClaudeAgentOptions,
+    config: BatchConfig,
+    executor: BatchExecutor,
+    state: Arc<Mutex<BatchState>>,
+}
+
+struct BatchState {
+    spent_usd: f64,
+    bucket: Option<TokenBucket>,
+}
+
+impl std::fmt::Debug for BatchRunner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BatchRunner")
+            .field("options", &self.options)
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+impl BatchRunner {
+    pub fn new(options: ClaudeAgentOptions, config: BatchConfig) -> Self {
+        let bucket = config.rate_limit.map(TokenBucket::new);
+        Self {
+            options,
+            config,
+            executor: Arc::new(|prompt, options| {
+                Box::pin(async move {
+                    let stream = query(prompt, Some(options)).await?;
+                    futures::pin_mut!(stream);
+                    let mut messages = Vec::new();
+                    while let Some(message) = stream.next().await {
+                        messages.push(message?);
+                    }
+                    Ok(messages)
+                })
+            }),
+            state: Arc::new(Mutex::new(BatchState {
+                spent_usd: 0.0,
+                bucket,
+            })),
+        }
+    }
+
+    /// Run items with a custom executor instead of [`query`](crate::query()).
+    pub fn with_executor(mut self, executor: BatchExecutor) -> Self {
+        self.executor = executor;
+        self
+    }
+
+    /// Total cost of all results seen by this runner so far.
+    pub async fn spent_usd(&self) -> f64 {
+        self.state.lock().await.spent_usd
+    }
+
+    /// Run a stream of items, yielding one result per item.
+    ///
+    /// Results arrive in input order when [`BatchConfig::ordered`] is set and
+    /// as they complete otherwise. Budget and rate limit are shared across
+    /// every run of this runner.
+    pub fn run<S, I>(&self, items: S) -> BoxStream<'static, BatchResult>
+    where
+        S: Stream<Item = I> + Send + 'static,
+        I: Into<BatchItem>,
+    {
+        let runner = self.clone();
+        let concurrency = self.config.concurrency.max(1);
+        let futures = items.enumerate().map(move |(index, item)| {
+            let runner = runner.clone();
+            let item = item.into();
+            async move { runner.run_item(index, item).await }
+        });
+
+        if self.config.ordered {
+            futures.buffered(concurrency).boxed()
+        } else {
+            futures.buffer_unordered(concurrency).boxed()
+        }
+    }
+
+    /// Run all items and collect the results in input order.
+    pub async fn run_all<I>(&self, items: impl IntoIterator<Item = I>) -> Vec<BatchResult>
+    where
+        I: Into<BatchItem>,
+    {
+        let items: Vec<BatchItem> = items.into_iter().map(Into::into).collect();
+        let mut results: Vec<BatchResult> = self.run(futures::stream::iter(items)).collect().await;
+        results.sort_by_key(|result| result.index);
+        results
+    }
+
+    async fn run_item(&self, index: usize, item: BatchItem) -> BatchResult {
+        let BatchItem { prompt, options } = item;
+        let mut options = options.unwrap_or_else(|| self.options.clone());
+        let policy = &self.config.retry;
+        let mut attempts = 0;
+
+        let outcome = loop {
+            if self.budget_exhausted().await {
+                break BatchOutcome::Skipped;
+            }
+            self.acquire_rate_token().await;
+
+            attempts += 1;
+            let messages = match (self.executor)(prompt.clone(), options.clone()).await {
+                Ok(messages) => messages,
+                Err(e) => break BatchOutcome::Failed(e),
+            };
+
+            self.state.lock().await.spent_usd += cost_of(&messages);
+
+            let output = BatchOutput { messages };
+            let error = match output.api_error() {
+                Some(error) if policy.is_retryable(error) && attempts <= policy.max_retries => {
+                    error
+                }
+                _ => break BatchOutcome::Completed(output),
+            };
+
+            // Later retries keep the fallback model, as the client does within a turn
+            let model = if policy.use_fallback_model && attempts == 1 {
+                options.fallback_model.clone()
+            } else {
+                None
+            };
+            let report = RetryAttempt {
+                attempt: attempts,
+                error,
+                delay: policy.delay(attempts),
+                model: model.clone(),
+            };
+            tracing::debug!(
+                "Retrying batch item {} after {:?} ({:?})",
+                index,
+                report.delay,
+                report.error
+            );
+            if let Some(ref callback) = policy.on_retry {
+                callback(&report);
+            }
+            if model.is_some() {
+                options.model = model;
+            }
+            tokio::time::sleep(report.delay).await;
+        };
+
+        BatchResult {
+            index,
+            prompt,
+            attempts,
+            outcome,
+        }
+    }
+
+    async fn budget_exhausted(&self) -> bool {
+        match self.config.budget_usd {
+            Some(budget) => self.state.lock().await.spent_usd > budget,
+            None => false,
+        }
+    }
+
+    async fn acquire_rate_token(&self) {
+        loop {
+            let wait = {
+                let mut state = self.state.lock().await;
+                match state.bucket.as_mut() {
+                    Some(bucket) => bucket.try_acquire(),
+                    None => return,
+                }
+            };
+            match wait {
+                None => return,
+                Some(wait) => tokio::time::sleep(wait).await,
+            }
+        }
+    }
+}
+
+fn cost_of(messages: &[Message]) -> f64 {
+    messages
+        .iter()
+        .filter_map(|message| match message {
+            Message::Result(result) => result.total_cost_usd,
+            _ => None,
+        })
+        .sum()
+}
+
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    per_second: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(limit: RateLimit) -> Self {
+        let capacity = f64::from(limit.burst.max(1));
+        Self {
+            capacity,
+            tokens: capacity,
+            per_second: f64::from(limit.requests) / limit.per.as_secs_f64().max(f64::EPSILON),
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Take a token, or return how long to wait before one is available.
+    fn try_acquire(&mut self) -> Option<Duration> {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_second).min(self.capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            None
+        } else if self.per_second <= 0.0 {
+            Some(Duration::from_secs(1))
+        } else {
+            Some(Duration::from_secs_f64(
+                (1.0 - self.tokens) / self.per_second,
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{AssistantMessage, ContentBlock};
+    use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+
+    fn result(cost: f64) -> Message {
+        let mut result = ResultMessage::new("success", 1, 1, false, 1, "session");
+        result.total_cost_usd = Some(cost);
+        Message::Result(result)
+    }
+
+    fn assistant(text: &str) -> Message {
+        Message::Assistant(AssistantMessage::new(
+            vec![ContentBlock::text(text)],
+            "claude",
+        ))
+    }
+
+    fn echo_executor(cost: f64) -> BatchExecutor {
+        Arc::new(move |prompt, _options| {
+            Box::pin(async move {
+                let delay = if prompt == "slow" { 50 } else { 1 };
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+                Ok(vec![assistant(&prompt), result(cost)])
+            })
+        })
+    }
+
+    fn texts(results: &[BatchResult]) -> Vec<String> {
+        results
+            .iter()
+            .map(|r| match r.output().map(|o| &o.messages[0]) {
+                Some(Message::Assistant(a)) => a.text(),
+                _ => String::new(),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_ordered_results() {
+        let runner = BatchRunner::new(
+            ClaudeAgentOptions::default(),
+            BatchConfig::new().with_concurrency(2).with_ordered(true),
+        )
+        .with_executor(echo_executor(0.5));
+
+        let results: Vec<BatchResult> = runner
+            .run(futures::stream::iter(vec!["slow", "a", "b"]))
+            .collect()
+            .await;
+
+        assert_eq!(texts(&results), ["slow", "a", "b"]);
+        assert_eq!(runner.spent_usd().await, 1.5);
+    }
+
+    #[tokio::test]
+    async fn test_unordered_results_arrive_as_completed() {
+        let runner = BatchRunner::new(
+            ClaudeAgentOptions::default(),
+            BatchConfig::new().with_concurrency(2),
+        )
+        .with_executor(echo_executor(0.0));
+
+        let results: Vec<BatchResult> = runner
+            .run(futures::stream::iter(vec!["slow", "a"]))
+            .collect()
+            .await;
+
+        assert_eq!(texts(&results), ["a", "slow"]);
+        assert_eq!(results[1].index, 0);
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_is_bounded() {
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let executor: BatchExecutor = {
+            let running = running.clone();
+            let peak = peak.clone();
+            Arc::new(move |_prompt, _options| {
+                let running = running.clone();
+                let peak = peak.clone();
+                Box::pin(async move {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    Ok(vec![])
+                })
+            })
+        };
+
+        let runner = BatchRunner::new(
+            ClaudeAgentOptions::default(),
+            BatchConfig::new().with_concurrency(3),
+        )
+        .with_executor(executor);
+        let results = runner.run_all(vec!["p"; 10]).await;
+
+        assert_eq!(results.len(), 10);
+        assert_eq!(peak.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retries_rate_limit_errors() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let executor: BatchExecutor = {
+            let calls = calls.clone();
+            Arc::new(move |_prompt, _options| {
+                let call = calls.fetch_add(1, Ordering::SeqCst);
+                Box::pin(async move {
+                    if call < 2 {
+                        let error = if call == 0 {
+                            AssistantMessageError::RateLimit
+                        } else {
+                            AssistantMessageError::ServerError
+                        };
+                        Ok(vec![Message::Assistant(
+                            AssistantMessage::new(vec![], "claude").with_error(error),
+                        )])
+                    } else {
+                        Ok(vec![assistant("done"), result(0.1)])
+                    }
+                })
+            })
+        };
+
+        let runner = BatchRunner::new(
+            ClaudeAgentOptions::default(),
+            BatchConfig::new().with_retry_policy(
+                RetryPolicy::new().with_initial_backoff(Duration::from_millis(1)),
+            ),
+        )
+        .with_executor(executor);
+        let results = runner.run_all(["p"]).await;
+
+        assert_eq!(results[0].attempts, 3);
+        assert_eq!(texts(&results), ["done"]);
+    }
+
+    #[tokio::test]
+    async fn test_retries_are_bounded() {
+        let executor: BatchExecutor = Arc::new(|_prompt, _options| {
+            Box::pin(async {
+                Ok(vec![Message::Assistant(
+                    AssistantMessage::new(vec![], "claude")
+                        .with_error(AssistantMessageError::RateLimit),
+                )])
+            })
+        });
+
+        let runner = BatchRunner::new(
+            ClaudeAgentOptions::default(),
+            BatchConfig::new().with_retry_policy(
+                RetryPolicy::new()
+                    .with_max_retries(1)
+                    .with_initial_backoff(Duration::from_millis(1)),
+            ),
+        )
+        .with_executor(executor);
+        let results = runner.run_all(["p"]).await;
+
+        assert_eq!(results[0].attempts, 2);
+        assert_eq!(
+            results[0].output().unwrap().api_error(),
+            Some(AssistantMessageError::RateLimit)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retry_switches_to_fallback_model() {
+        let models = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let executor: BatchExecutor = {
+            let models = models.clone();
+            Arc::new(move |_prompt, options: ClaudeAgentOptions| {
+                let mut models = models.lock().unwrap();
+                models.push(options.model.clone());
+                let error = (models.len() == 1).then_some(AssistantMessageError::ServerError);
+                Box::pin(async move {
+                    let mut message = AssistantMessage::new(vec![], "claude");
+                    message.error = error;
+                    Ok(vec![Message::Assistant(message), result(0.1)])
+                })
+            })
+        };
+        let retries = Arc::new(AtomicU32::new(0));
+        let policy = {
+            let retries = retries.clone();
+            RetryPolicy::new()
+                .with_initial_backoff(Duration::from_millis(1))
+                .with_fallback_model(true)
+                .on_retry(move |_| {
+                    retries.fetch_add(1, Ordering::SeqCst);
+                })
+        };
+
+        let options = ClaudeAgentOptions::builder()
+            .model("claude-sonnet")
+            .fallback_model("claude-haiku")
+            .build();
+        let runner = BatchRunner::new(options, BatchConfig::new().with_retry_policy(policy))
+            .with_executor(executor);
+        let results = runner.run_all(["p"]).await;
+
+        assert_eq!(results[0].attempts, 2);
+        assert_eq!(retries.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            *models.lock().unwrap(),
+            [
+                Some("claude-sonnet".to_string()),
+                Some("claude-haiku".to_string())
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_budget_stops_scheduling() {
+        let runner = BatchRunner::new(
+            ClaudeAgentOptions::default(),
+            BatchConfig::new().with_concurrency(1).with_budget_usd(1.0),
+        )
+        .with_executor(echo_executor(0.6));
+        let results = runner.run_all(["a", "b", "c", "d"]).await;
+
+        assert!(matches!(results[0].outcome, BatchOutcome::Completed(_)));
+        assert!(matches!(results[1].outcome, BatchOutcome::Completed(_)));
+        assert!(matches!(results[2].outcome, BatchOutcome::Skipped));
+        assert!(matches!(results[3].outcome, BatchOutcome::Skipped));
+        assert_eq!(results[3].attempts, 0);
+    }
+
+    #[tokio::test]
+    async fn test_executor_errors_are_reported() {
+        let executor: BatchExecutor = Arc::new(|_prompt, _options| {
+            Box::pin(async { Err(crate::ClaudeSDKError::cli_not_found(None)) })
+        });
+        let runner = BatchRunner::new(ClaudeAgentOptions::default(), BatchConfig::new())
+            .with_executor(executor);
+        let results = runner.run_all(["p"]).await;
+
+        assert!(matches!(
+            results[0].outcome,
+            BatchOutcome::Failed(crate::ClaudeSDKError::CLINotFound { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_per_item_options() {
+        let executor: BatchExecutor = Arc::new(|_prompt, options| {
+            Box::pin(async move { Ok(vec![assistant(options.model.as_deref().unwrap_or(""))]) })
+        });
+        let runner = BatchRunner::new(
+            ClaudeAgentOptions::builder().model("default").build(),
+            BatchConfig::new(),
+        )
+        .with_executor(executor);
+        let results = runner
+            .run_all(vec![
+                BatchItem::new("a"),
+                BatchItem::new("b")
+                    .with_options(ClaudeAgentOptions::builder().model("custom").build()),
+            ])
+            .await;
+
+        assert_eq!(texts(&results), ["default", "custom"]);
+    }
+
+    #[test]
+    fn test_token_bucket() {
+        let mut bucket = TokenBucket::new(RateLimit::new(10, Duration::from_secs(1)).with_burst(2));
+        assert!(bucket.try_acquire().is_none());
+        assert!(bucket.try_acquire().is_none());
+
+        let wait = bucket.try_acquire().unwrap();
+        assert!(wait <= Duration::from_millis(100));
+        assert!(wait > Duration::from_millis(50));
+    }
+}
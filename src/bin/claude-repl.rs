@@ -0,0 +1,666 @@
+//! Interactive chat REPL over [`ClaudeSDKClient`].
+//!
+//! Streams replies as they are generated, shows tool calls and their
+//! results, and asks before each tool runs. Lines starting with `/` are
+//! commands; `/help` lists them.
+//!
+//! ```text
+//! cargo run --features cli --bin claude-repl -- --transcript chat.md
+//! ```
+
+use std::collections::HashSet;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use clap::Parser;
+use futures::StreamExt;
+use serde_json::Value;
+use tokio::sync::{mpsc, oneshot};
+
+use claude_agent_sdk::types::{
+    CanUseToolFn, ContentBlock, PermissionMode, PermissionResult, UserMessage, UserMessageContent,
+};
+use claude_agent_sdk::{export, ClaudeAgentOptions, ClaudeSDKClient, Message, Result};
+
+const HELP: &str = "\
+Commands:
+  /model [name]       switch model, or reset to the default
+  /mode <mode>        default, acceptEdits, plan or bypassPermissions
+  /interrupt          stop the current response
+  /rewind [n|uuid]    list checkpoints, or restore files to one
+  /mcp                show MCP server status
+  /save [path]        write the transcript now
+  /help               show this help
+  /quit               save the transcript (with --transcript) and exit
+Other /commands are sent to the session:";
+
+/// Chat with Claude Code from the terminal.
+#[derive(Debug, Parser)]
+#[command(name = "claude-repl", version)]
+struct Args {
+    /// Model to use
+    #[arg(long)]
+    model: Option<String>,
+
+    /// Permission mode: default, acceptEdits, plan or bypassPermissions
+    #[arg(long, value_parser = parse_mode)]
+    permission_mode: Option<PermissionMode>,
+
+    /// Working directory for the session
+    #[arg(long)]
+    cwd: Option<PathBuf>,
+
+    /// System prompt
+    #[arg(long)]
+    system_prompt: Option<String>,
+
+    /// Path to the Claude Code CLI
+    #[arg(long)]
+    cli_path: Option<PathBuf>,
+
+    /// Save the transcript on exit: Markdown for `.md`, HTML for `.html`,
+    /// JSONL otherwise
+    #[arg(long, value_name = "PATH")]
+    transcript: Option<PathBuf>,
+
+    /// Print whole messages instead of streaming text as it is generated
+    #[arg(long)]
+    no_partial: bool,
+}
+
+/// A slash command typed at the prompt.
+#[derive(Debug, PartialEq)]
+enum Command {
+    Model(Option<String>),
+    Mode(PermissionMode),
+    Interrupt,
+    Rewind(Option<String>),
+    Mcp,
+    Save(Option<PathBuf>),
+    Help,
+    Quit,
+    /// A CLI slash command or custom command, sent as a prompt
+    Session {
+        name: String,
+        args: String,
+    },
+}
+
+/// Parse a slash command; `None` if the line is a prompt.
+fn parse_command(line: &str) -> Option<std::result::Result<Command, String>> {
+    let line = line.trim();
+    let rest = line.strip_prefix('/')?;
+    let (name, arg) = match rest.split_once(char::is_whitespace) {
+        Some((name, arg)) => (name, Some(arg.trim().to_string())),
+        None => (rest, None),
+    };
+    let arg = arg.filter(|arg| !arg.is_empty());
+
+    let command = match name {
+        "model" => Ok(Command::Model(arg)),
+        "mode" => match arg {
+            Some(mode) => parse_mode(&mode).map(Command::Mode),
+            None => Err("usage: /mode <default|acceptEdits|plan|bypassPermissions>".to_string()),
+        },
+        "interrupt" => Ok(Command::Interrupt),
+        "rewind" => Ok(Command::Rewind(arg)),
+        "mcp" => Ok(Command::Mcp),
+        "save" => Ok(Command::Save(arg.map(PathBuf::from))),
+        "help" => Ok(Command::Help),
+        "quit" | "exit" => Ok(Command::Quit),
+        "" => Err("empty command; try /help".to_string()),
+        other => Ok(Command::Session {
+            name: other.to_string(),
+            args: arg.unwrap_or_default(),
+        }),
+    };
+    Some(command)
+}
+
+fn parse_mode(mode: &str) -> std::result::Result<PermissionMode, String> {
+    match mode {
+        "default" => Ok(PermissionMode::Default),
+        "acceptEdits" => Ok(PermissionMode::AcceptEdits),
+        "plan" => Ok(PermissionMode::Plan),
+        "bypassPermissions" => Ok(PermissionMode::BypassPermissions),
+        other => Err(format!(
+            "unknown permission mode '{}'; expected default, acceptEdits, plan or bypassPermissions",
+            other
+        )),
+    }
+}
+
+/// Write messages to `path` in the format its extension names.
+fn save_transcript(path: &Path, messages: &[Message]) -> Result<()> {
+    let contents = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("md" | "markdown") => export::to_markdown(messages),
+        Some("html" | "htm") => export::to_html(messages),
+        _ => export::to_jsonl(messages)?,
+    };
+    std::fs::write(path, contents)?;
+    Ok(())
+}
+
+/// Tool permission prompts, answered from the input line reader.
+#[derive(Default)]
+struct Permissions {
+    pending: Mutex<Option<oneshot::Sender<String>>>,
+    always: Mutex<HashSet<String>>,
+}
+
+impl Permissions {
+    /// A `can_use_tool` callback that asks on the terminal.
+    fn callback(self: &Arc<Self>) -> CanUseToolFn {
+        let permissions = Arc::clone(self);
+        Arc::new(move |tool_name, input, _context| {
+            let permissions = Arc::clone(&permissions);
+            Box::pin(async move { permissions.ask(tool_name, input).await })
+        })
+    }
+
+    async fn ask(&self, tool_name: String, input: Value) -> PermissionResult {
+        if self.always.lock().unwrap().contains(&tool_name) {
+            return PermissionResult::allow();
+        }
+
+        let (tx, rx) = oneshot::channel();
+        *self.pending.lock().unwrap() = Some(tx);
+        print!(
+            "\n[permission] Allow {}? [y/N/a(lways)] ",
+            tool_label(&tool_name, &input)
+        );
+        let _ = std::io::stdout().flush();
+
+        match rx.await.as_deref().map(str::trim) {
+            Ok("y" | "yes") => PermissionResult::allow(),
+            Ok("a" | "always") => {
+                self.always.lock().unwrap().insert(tool_name);
+                PermissionResult::allow()
+            }
+            _ => PermissionResult::deny_with_message("The user denied this tool call"),
+        }
+    }
+
+    /// Pass `line` to a waiting prompt; false if none is waiting.
+    fn answer(&self, line: &str) -> bool {
+        match self.pending.lock().unwrap().take() {
+            Some(tx) => {
+                let _ = tx.send(line.to_string());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Deny any waiting prompt.
+    fn cancel(&self) {
+        self.pending.lock().unwrap().take();
+    }
+}
+
+/// `Name(summary)` for a tool call.
+fn tool_label(name: &str, input: &Value) -> String {
+    const KEYS: &[&str] = &[
+        "command",
+        "file_path",
+        "pattern",
+        "url",
+        "query",
+        "description",
+    ];
+    let summary = KEYS
+        .iter()
+        .find_map(|key| input.get(*key)?.as_str())
+        .map(|value| truncate(value.lines().next().unwrap_or_default(), 80));
+    match summary {
+        Some(summary) => format!("{}({})", name, summary),
+        None => name.to_string(),
+    }
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// First line of a tool result's content.
+fn result_line(content: Option<&Value>) -> String {
+    let text = match content {
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Array(blocks)) => blocks
+            .iter()
+            .filter_map(|block| block.get("text")?.as_str())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Some(other) => other.to_string(),
+        None => String::new(),
+    };
+    truncate(text.lines().next().unwrap_or_default(), 100)
+}
+
+/// Terminal rendering of the message stream.
+#[derive(Default)]
+struct View {
+    /// Text of the current assistant message was already streamed
+    streamed: bool,
+    /// The cursor is mid-line after streamed text
+    mid_line: bool,
+    /// User messages that can be rewound to, as (uuid, prompt)
+    checkpoints: Vec<(String, String)>,
+}
+
+impl View {
+    fn show(&mut self, message: &Message) {
+        match message {
+            // Subagent output is shown whole, so it does not interleave
+            Message::StreamEvent(event)
+                if event.parent_tool_use_id.is_none()
+                    && event.event["type"] == "content_block_delta" =>
+            {
+                if let Some(text) = event.event["delta"]["text"].as_str() {
+                    print!("{}", text);
+                    let _ = std::io::stdout().flush();
+                    self.streamed = true;
+                    self.mid_line = !text.ends_with('\n');
+                }
+            }
+            Message::Assistant(assistant) => {
+                for block in &assistant.content {
+                    match block {
+                        ContentBlock::Text { text } if !self.streamed => {
+                            self.end_line();
+                            println!("{}", text);
+                        }
+                        ContentBlock::ToolUse { name, input, .. } => {
+                            self.end_line();
+                            println!("[tool] {}", tool_label(name, input));
+                        }
+                        _ => {}
+                    }
+                }
+                self.streamed = false;
+            }
+            Message::User(user) => match &user.content {
+                UserMessageContent::Blocks(blocks) => {
+                    for block in blocks {
+                        if let ContentBlock::ToolResult {
+                            content, is_error, ..
+                        } = block
+                        {
+                            self.end_line();
+                            let label = if *is_error == Some(true) {
+                                "error"
+                            } else {
+                                "result"
+                            };
+                            println!("[{}] {}", label, result_line(content.as_ref()));
+                        }
+                    }
+                }
+                UserMessageContent::Text(text) => {
+                    if let Some(ref uuid) = user.uuid {
+                        self.checkpoints.push((uuid.clone(), truncate(text, 60)));
+                    }
+                }
+            },
+            Message::Result(result) => {
+                self.end_line();
+                let status = if result.is_error {
+                    format!("error: {}", result.subtype)
+                } else {
+                    "done".to_string()
+                };
+                let cost = result
+                    .total_cost_usd
+                    .map(|cost| format!(", ${:.4}", cost))
+                    .unwrap_or_default();
+                println!(
+                    "[{}] {} turn(s), {:.1}s{}",
+                    status,
+                    result.num_turns,
+                    result.duration_ms as f64 / 1000.0,
+                    cost
+                );
+            }
+            _ => {}
+        }
+    }
+
+    fn end_line(&mut self) {
+        if self.mid_line {
+            println!();
+            self.mid_line = false;
+        }
+    }
+}
+
+/// Read stdin lines on a thread so prompts can be answered mid-turn.
+fn spawn_input() -> mpsc::UnboundedReceiver<String> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+struct Repl {
+    client: ClaudeSDKClient,
+    permissions: Arc<Permissions>,
+    input: mpsc::UnboundedReceiver<String>,
+    input_open: bool,
+    view: View,
+    transcript: Vec<Message>,
+}
+
+impl Repl {
+    /// Receive until the turn's result, answering prompts and `/interrupt`.
+    async fn run_turn(&mut self) {
+        loop {
+            let interrupted = {
+                let stream = self.client.receive_messages();
+                tokio::pin!(stream);
+                loop {
+                    tokio::select! {
+                        item = stream.next() => match item {
+                            Some(Ok(message)) => {
+                                self.view.show(&message);
+                                let done = message.is_result();
+                                if !message.is_stream_event() {
+                                    self.transcript.push(message);
+                                }
+                                if done {
+                                    return;
+                                }
+                            }
+                            Some(Err(e)) => {
+                                self.view.end_line();
+                                eprintln!("error: {}", e);
+                                return;
+                            }
+                            None => {
+                                self.view.end_line();
+                                eprintln!("error: the CLI closed the conversation");
+                                return;
+                            }
+                        },
+                        line = self.input.recv(), if self.input_open => match line {
+                            Some(line) => {
+                                if self.permissions.answer(&line) {
+                                    continue;
+                                }
+                                match parse_command(&line) {
+                                    Some(Ok(Command::Interrupt)) => break true,
+                                    _ => eprintln!("(busy; /interrupt stops the response)"),
+                                }
+                            }
+                            None => {
+                                self.input_open = false;
+                                self.permissions.cancel();
+                            }
+                        },
+                    }
+                }
+            };
+
+            if interrupted {
+                self.view.end_line();
+                match self.client.interrupt().await {
+                    Ok(()) => println!("[interrupted]"),
+                    Err(e) => eprintln!("error: {}", e),
+                }
+            }
+        }
+    }
+
+    /// Run a slash command; false to exit.
+    async fn command(&mut self, command: Command, save_path: Option<&Path>) -> bool {
+        let result = match command {
+            Command::Model(model) => self.client.set_model(model).await,
+            Command::Mode(mode) => self.client.set_permission_mode(mode.as_str()).await,
+            Command::Interrupt => self.client.interrupt().await,
+            Command::Rewind(None) => {
+                if self.view.checkpoints.is_empty() {
+                    println!("no checkpoints yet");
+                }
+                for (index, (uuid, prompt)) in self.view.checkpoints.iter().enumerate() {
+                    println!("{:>3}  {}  {}", index + 1, uuid, prompt);
+                }
+                Ok(())
+            }
+            Command::Rewind(Some(target)) => {
+                let uuid = match target.parse::<usize>() {
+                    Ok(index) => match index
+                        .checked_sub(1)
+                        .and_then(|i| self.view.checkpoints.get(i))
+                    {
+                        Some((uuid, _)) => uuid.clone(),
+                        None => {
+                            eprintln!("no checkpoint {}; /rewind lists them", index);
+                            return true;
+                        }
+                    },
+                    Err(_) => target,
+                };
+                self.client.rewind_files(&uuid).await
+            }
+            Command::Mcp => self.client.get_mcp_status().await.map(|status| {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&status).unwrap_or_default()
+                );
+            }),
+            Command::Save(path) => match path.as_deref().or(save_path) {
+                Some(path) => save_transcript(path, &self.transcript)
+                    .map(|()| println!("saved {}", path.display())),
+                None => {
+                    eprintln!("usage: /save <path>");
+                    Ok(())
+                }
+            },
+            Command::Help => {
+                println!("{}", HELP);
+                for command in self.client.slash_commands() {
+                    let usage = match command.argument_hint {
+                        Some(ref hint) => format!("/{} {}", command.name, hint),
+                        None => format!("/{}", command.name),
+                    };
+                    let description = command.description.as_deref().unwrap_or_default();
+                    println!("  {:<19} {}", usage, description);
+                }
+                Ok(())
+            }
+            Command::Quit => return false,
+            Command::Session { name, args } => match self.client.send_command(&name, &args).await {
+                Ok(()) => {
+                    let line = format!("/{} {}", name, args);
+                    self.transcript
+                        .push(Message::User(UserMessage::new(line.trim_end())));
+                    self.run_turn().await;
+                    Ok(())
+                }
+                Err(e) => Err(e),
+            },
+        };
+        if let Err(e) = result {
+            eprintln!("error: {}", e);
+        }
+        true
+    }
+}
+
+#[tokio::main]
+async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    let permissions = Arc::new(Permissions::default());
+
+    let mut builder = ClaudeAgentOptions::builder()
+        .can_use_tool(permissions.callback())
+        .include_partial_messages(!args.no_partial)
+        .enable_file_checkpointing(true);
+    if let Some(model) = args.model {
+        builder = builder.model(model);
+    }
+    if let Some(mode) = args.permission_mode {
+        builder = builder.permission_mode(mode);
+    }
+    if let Some(cwd) = args.cwd {
+        builder = builder.cwd(cwd);
+    }
+    if let Some(prompt) = args.system_prompt {
+        builder = builder.system_prompt(prompt);
+    }
+    if let Some(path) = args.cli_path {
+        builder = builder.cli_path(path);
+    }
+
+    let mut client = ClaudeSDKClient::new(builder.build());
+    client.connect().await?;
+    println!("Connected. Type /help for commands, /quit to exit.");
+
+    let mut repl = Repl {
+        client,
+        permissions,
+        input: spawn_input(),
+        input_open: true,
+        view: View::default(),
+        transcript: Vec::new(),
+    };
+
+    while repl.input_open {
+        print!("> ");
+        std::io::stdout().flush()?;
+        let Some(line) = repl.input.recv().await else {
+            println!();
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match parse_command(&line) {
+            Some(Ok(command)) => {
+                if !repl.command(command, args.transcript.as_deref()).await {
+                    break;
+                }
+            }
+            Some(Err(message)) => eprintln!("{}", message),
+            None => {
+                repl.client.send_message(line.clone()).await?;
+                repl.transcript
+                    .push(Message::User(UserMessage::new(line.as_str())));
+                repl.run_turn().await;
+            }
+        }
+    }
+
+    if let Some(ref path) = args.transcript {
+        save_transcript(path, &repl.transcript)?;
+        println!("Transcript saved to {}", path.display());
+    }
+    repl.client.disconnect().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_command() {
+        assert!(parse_command("hello /model").is_none());
+        assert_eq!(parse_command("/model"), Some(Ok(Command::Model(None))));
+        assert_eq!(
+            parse_command(" /model  claude-haiku "),
+            Some(Ok(Command::Model(Some("claude-haiku".to_string()))))
+        );
+        assert_eq!(
+            parse_command("/mode acceptEdits"),
+            Some(Ok(Command::Mode(PermissionMode::AcceptEdits)))
+        );
+        assert_eq!(parse_command("/exit"), Some(Ok(Command::Quit)));
+        assert!(matches!(parse_command("/mode"), Some(Err(_))));
+        assert!(matches!(parse_command("/mode yolo"), Some(Err(_))));
+        assert_eq!(
+            parse_command("/review src/lib.rs"),
+            Some(Ok(Command::Session {
+                name: "review".to_string(),
+                args: "src/lib.rs".to_string()
+            }))
+        );
+        assert!(matches!(parse_command("/"), Some(Err(_))));
+    }
+
+    #[test]
+    fn test_save_transcript_format_follows_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let messages = vec![Message::User(UserMessage::new("Hi there"))];
+
+        let jsonl = dir.path().join("chat.jsonl");
+        save_transcript(&jsonl, &messages).unwrap();
+        let contents = std::fs::read_to_string(&jsonl).unwrap();
+        assert_eq!(export::from_jsonl(&contents).unwrap(), messages);
+
+        let markdown = dir.path().join("chat.md");
+        save_transcript(&markdown, &messages).unwrap();
+        assert!(std::fs::read_to_string(markdown)
+            .unwrap()
+            .contains("Hi there"));
+
+        let html = dir.path().join("chat.html");
+        save_transcript(&html, &messages).unwrap();
+        assert!(std::fs::read_to_string(html)
+            .unwrap()
+            .starts_with("<!DOCTYPE html>"));
+    }
+
+    #[test]
+    fn test_tool_label() {
+        assert_eq!(
+            tool_label("Bash", &json!({"command": "ls -la\npwd"})),
+            "Bash(ls -la)"
+        );
+        assert_eq!(tool_label("TodoWrite", &json!({"todos": []})), "TodoWrite");
+    }
+
+    #[tokio::test]
+    async fn test_permission_answers() {
+        let permissions = Arc::new(Permissions::default());
+        let callback = permissions.callback();
+        let ask = |permissions: &Arc<Permissions>, answer: &'static str| {
+            let decision = callback(
+                "Bash".to_string(),
+                json!({"command": "ls"}),
+                Default::default(),
+            );
+            let permissions = Arc::clone(permissions);
+            async move {
+                let decision = tokio::spawn(decision);
+                while !permissions.answer(answer) {
+                    tokio::task::yield_now().await;
+                }
+                decision.await.unwrap()
+            }
+        };
+
+        assert!(matches!(
+            ask(&permissions, "n").await,
+            PermissionResult::Deny(_)
+        ));
+        assert!(matches!(
+            ask(&permissions, "a").await,
+            PermissionResult::Allow(_)
+        ));
+        // Remembered without asking
+        let decision = callback("Bash".to_string(), json!({}), Default::default()).await;
+        assert!(matches!(decision, PermissionResult::Allow(_)));
+    }
+}
@@ -0,0 +1,331 @@
+//! Synchronous API for code without an async runtime.
+//!
+//! [`query`] and [`Client`] mirror [`crate::query()`] and
+//! [`ClaudeSDKClient`], blocking the calling thread instead of returning
+//! futures. They run on a small runtime owned by the SDK, so they work from
+//! plain `main` functions, build scripts and threads, and from inside
+//! `tokio::task::spawn_blocking`. Calling them directly from async code
+//! panics, as blocking there would stall the caller's runtime.
+//!
+//! ```no_run
+//! use claude_agent_sdk::blocking;
+//!
+//! fn main() -> claude_agent_sdk::Result<()> {
+//!     for message in blocking::query("What is 2 + 2?", None) {
+//!         if let Some(assistant) = message?.as_assistant() {
+//!             println!("{}", assistant.text());
+//!         }
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use futures::{Stream, StreamExt};
+use serde_json::Value;
+use std::pin::Pin;
+use std::sync::OnceLock;
+use tokio::runtime::Runtime;
+
+use crate::client::ClaudeSDKClient;
+use crate::error::Result;
+use crate::retry::RetryPolicy;
+use crate::transport::CliInfo;
+use crate::types::{ClaudeAgentOptions, CliVersion, Message, SlashCommand};
+
+/// Runtime shared by all blocking calls, started on first use.
+fn runtime() -> Result<&'static Runtime> {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+    if let Some(runtime) = RUNTIME.get() {
+        return Ok(runtime);
+    }
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(2)
+        .thread_name("claude-sdk-blocking")
+        .enable_all()
+        .build()?;
+    // A runtime built by a racing thread is dropped unused
+    Ok(RUNTIME.get_or_init(|| runtime))
+}
+
+/// Query Claude Code, blocking until each message arrives.
+///
+/// Errors while starting the CLI are returned as the first item.
+pub fn query(
+    prompt: impl Into<String>,
+    options: Option<ClaudeAgentOptions>,
+) -> impl Iterator<Item = Result<Message>> {
+    let prompt = prompt.into();
+    let started = runtime().and_then(|runtime| {
+        let stream = runtime.block_on(crate::query::query(prompt, options))?;
+        Ok(BlockingStream {
+            runtime,
+            stream: Box::pin(stream),
+        })
+    });
+
+    let (stream, error) = match started {
+        Ok(stream) => (Some(stream), None),
+        Err(e) => (None, Some(Err(e))),
+    };
+    error.into_iter().chain(stream.into_iter().flatten())
+}
+
+/// An async message stream driven by the blocking runtime.
+struct BlockingStream<'a> {
+    runtime: &'static Runtime,
+    stream: Pin<Box<dyn Stream<Item = Result<Message>> + 'a>>,
+}
+
+impl Iterator for BlockingStream<'_> {
+    type Item = Result<Message>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.runtime.block_on(self.stream.next())
+    }
+}
+
+/// Blocking counterpart of [`ClaudeSDKClient`].
+///
+/// ```no_run
+/// use claude_agent_sdk::blocking::Client;
+/// use claude_agent_sdk::ClaudeAgentOptions;
+///
+/// fn main() -> claude_agent_sdk::Result<()> {
+///     let mut client = Client::new(ClaudeAgentOptions::default());
+///     client.connect()?;
+///
+///     client.send_message("Hello!")?;
+///     for message in client.receive_messages() {
+///         let message = message?;
+///         if message.is_result() {
+///             break;
+///         }
+///         println!("{:?}", message);
+///     }
+///
+///     client.disconnect()
+/// }
+/// ```
+pub struct Client {
+    inner: ClaudeSDKClient,
+}
+
+impl Client {
+    /// Create a new client with the given options.
+    pub fn new(options: ClaudeAgentOptions) -> Self {
+        Self {
+            inner: ClaudeSDKClient::new(options),
+        }
+    }
+
+    /// Retry turns that fail with transient API errors.
+    pub fn with_retry_policy(self, policy: RetryPolicy) -> Self {
+        Self {
+            inner: self.inner.with_retry_policy(policy),
+        }
+    }
+
+    /// Connect to Claude in streaming mode.
+    pub fn connect(&mut self) -> Result<()> {
+        runtime()?.block_on(self.inner.connect())
+    }
+
+    /// Connect to Claude with an optional initial prompt.
+    pub fn connect_with_prompt(&mut self, prompt: Option<String>) -> Result<()> {
+        runtime()?.block_on(self.inner.connect_with_prompt(prompt))
+    }
+
+    /// Send a user message.
+    pub fn send_message(&mut self, message: impl Into<String>) -> Result<()> {
+        runtime()?.block_on(self.inner.send_message(message))
+    }
+
+    /// Send a slash command, or expand and send a custom command.
+    pub fn send_command(&mut self, name: &str, args: &str) -> Result<()> {
+        runtime()?.block_on(self.inner.send_command(name, args))
+    }
+
+    /// Send a raw JSON message.
+    pub fn send_raw(&mut self, message: Value) -> Result<()> {
+        runtime()?.block_on(self.inner.send_raw(message))
+    }
+
+    /// Iterate over incoming messages, blocking until each arrives.
+    ///
+    /// Like [`ClaudeSDKClient::receive_messages`], this does not stop at the
+    /// end of a turn; break on a result message.
+    pub fn receive_messages(&mut self) -> impl Iterator<Item = Result<Message>> + '_ {
+        let (stream, error) = match runtime() {
+            Ok(runtime) => (
+                Some(BlockingStream {
+                    runtime,
+                    stream: Box::pin(self.inner.receive_messages()),
+                }),
+                None,
+            ),
+            Err(e) => (None, Some(Err(e))),
+        };
+        error.into_iter().chain(stream.into_iter().flatten())
+    }
+
+    /// Receive messages until the result message, returning it.
+    pub fn receive_response(&mut self) -> Option<Result<Message>> {
+        match runtime() {
+            Ok(runtime) => runtime.block_on(self.inner.receive_response()),
+            Err(e) => Some(Err(e)),
+        }
+    }
+
+    /// Flush any pending control responses to the CLI.
+    pub fn flush_responses(&mut self) -> Result<()> {
+        runtime()?.block_on(self.inner.flush_responses())
+    }
+
+    /// Send interrupt signal.
+    pub fn interrupt(&mut self) -> Result<()> {
+        runtime()?.block_on(self.inner.interrupt())
+    }
+
+    /// Change permission mode during conversation.
+    pub fn set_permission_mode(&mut self, mode: &str) -> Result<()> {
+        runtime()?.block_on(self.inner.set_permission_mode(mode))
+    }
+
+    /// Change the AI model during conversation.
+    pub fn set_model(&mut self, model: Option<String>) -> Result<()> {
+        runtime()?.block_on(self.inner.set_model(model))
+    }
+
+    /// Rewind tracked files to their state at a specific user message.
+    pub fn rewind_files(&mut self, user_message_id: &str) -> Result<()> {
+        runtime()?.block_on(self.inner.rewind_files(user_message_id))
+    }
+
+    /// Get current MCP server connection status.
+    pub fn get_mcp_status(&mut self) -> Result<Value> {
+        runtime()?.block_on(self.inner.get_mcp_status())
+    }
+
+    /// Get server initialization info.
+    pub fn get_server_info(&self) -> Option<&Value> {
+        self.inner.get_server_info()
+    }
+
+    /// Slash commands the CLI offers.
+    pub fn slash_commands(&self) -> &[SlashCommand] {
+        self.inner.slash_commands()
+    }
+
+    /// Get the path and version of the connected CLI.
+    pub fn cli_info(&self) -> Option<&CliInfo> {
+        self.inner.cli_info()
+    }
+
+    /// Get the version of the connected CLI, if it could be detected.
+    pub fn cli_version(&self) -> Option<CliVersion> {
+        self.inner.cli_version()
+    }
+
+    /// Disconnect from Claude.
+    pub fn disconnect(&mut self) -> Result<()> {
+        runtime()?.block_on(self.inner.disconnect())
+    }
+
+    /// Check if the client is connected.
+    pub fn is_connected(&self) -> bool {
+        self.inner.is_connected()
+    }
+
+    /// Check if the client is connected and its CLI process is still usable.
+    pub fn is_healthy(&self) -> bool {
+        self.inner.is_healthy()
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::test_support::fake_cli;
+    use std::path::PathBuf;
+
+    /// Fake CLI answering control requests and echoing each user message.
+    const ECHO_CLI: &str = r#"while IFS= read -r line; do
+  id=$(request_id "$line")
+  if [ -n "$id" ]; then
+    control_success "$id"
+  elif is_user_message "$line"; then
+    printf '{"type":"assistant","message":{"model":"m","content":[{"type":"text","text":"echo"}]}}\n'
+    result_success
+  fi
+done
+"#;
+
+    /// Fake CLI answering a one-shot query and exiting.
+    const QUERY_CLI: &str = r#"printf '{"type":"assistant","message":{"model":"m","content":[{"type":"text","text":"4"}]}}\n'
+result_success
+"#;
+
+    fn run_conversation(cli: PathBuf) -> Vec<String> {
+        let mut client = Client::new(ClaudeAgentOptions::builder().cli_path(cli).build());
+        client.connect().unwrap();
+        let mut texts = Vec::new();
+        for prompt in ["one", "two"] {
+            client.send_message(prompt).unwrap();
+            for message in client.receive_messages() {
+                let message = message.unwrap();
+                if let Some(assistant) = message.as_assistant() {
+                    texts.push(assistant.text());
+                }
+                if message.is_result() {
+                    break;
+                }
+            }
+        }
+        client.disconnect().unwrap();
+        texts
+    }
+
+    #[test]
+    fn test_query_from_sync_code() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = ClaudeAgentOptions::builder()
+            .cli_path(fake_cli(&dir, QUERY_CLI))
+            .build();
+        let messages: Vec<Message> = query("What is 2 + 2?", Some(options))
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].as_assistant().unwrap().text(), "4");
+        assert!(messages[1].is_result());
+    }
+
+    #[test]
+    fn test_query_start_error_is_first_item() {
+        let options = ClaudeAgentOptions::builder()
+            .cli_path("/nonexistent/claude")
+            .build();
+        let items: Vec<_> = query("hi", Some(options)).collect();
+        assert_eq!(items.len(), 1);
+        assert!(items[0].is_err());
+    }
+
+    #[test]
+    fn test_client_from_sync_code() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            run_conversation(fake_cli(&dir, ECHO_CLI)),
+            vec!["echo", "echo"]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_client_inside_spawn_blocking() {
+        let dir = tempfile::tempdir().unwrap();
+        let cli = fake_cli(&dir, ECHO_CLI);
+        let texts = tokio::task::spawn_blocking(move || run_conversation(cli))
+            .await
+            .unwrap();
+        assert_eq!(texts, vec!["echo", "echo"]);
+    }
+}
@@ -4,9 +4,11 @@ use futures::{Stream, StreamExt};
 use serde_json::{json, Value};
 
 use crate::error::{ClaudeSDKError, Result};
+use crate::internal::telemetry::{names, Recorder, SessionTracer};
 use crate::internal::QueryHandler;
-use crate::transport::{SubprocessCLITransport, Transport};
-use crate::types::{ClaudeAgentOptions, Message};
+use crate::retry::{RetryAttempt, RetryPolicy};
+use crate::transport::{CliInfo, SubprocessCLITransport, Transport};
+use crate::types::{AssistantMessageError, ClaudeAgentOptions, CliVersion, Message, SlashCommand};
 
 /// Client for bidirectional, interactive conversations with Claude Code.
 ///
@@ -75,18 +77,54 @@ pub struct ClaudeSDKClient {
     options: ClaudeAgentOptions,
     query_handler: Option<QueryHandler>,
     connected: bool,
+    cli_info: Option<CliInfo>,
+    retry_policy: Option<RetryPolicy>,
+    retry: RetryState,
+    /// Whether a CLI process was started before, so the next is a restart.
+    started_before: bool,
+    /// Slash commands reported by the CLI at initialization.
+    slash_commands: Vec<SlashCommand>,
+    /// Model last requested via the options or `set_model()`.
+    model: Option<String>,
+}
+
+/// Progress of retrying the current turn.
+#[derive(Debug, Default)]
+struct RetryState {
+    /// Last user message sent, resent on retry.
+    last_user_message: Option<Value>,
+    /// Retries made for the current turn.
+    attempts: u32,
+    /// Error of a failed turn whose remaining messages are being skipped.
+    pending: Option<AssistantMessageError>,
+    /// Model to switch back to once a turn retried on the fallback model ends.
+    restore_model: Option<Option<String>>,
 }
 
 impl ClaudeSDKClient {
     /// Create a new Claude SDK client with the given options.
     pub fn new(options: ClaudeAgentOptions) -> Self {
         Self {
-            options,
             query_handler: None,
             connected: false,
+            cli_info: None,
+            retry_policy: None,
+            retry: RetryState::default(),
+            started_before: false,
+            slash_commands: Vec::new(),
+            model: options.model.clone(),
+            options,
         }
     }
 
+    /// Retry turns that fail with transient API errors.
+    ///
+    /// See [`RetryPolicy`] for how failed turns are resent.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
     /// Create a new client with default options.
     pub fn default_client() -> Self {
         Self::new(ClaudeAgentOptions::default())
@@ -107,13 +145,20 @@ impl ClaudeSDKClient {
         }
 
         // Create transport
-        let mut transport: Box<dyn Transport> = if let Some(prompt) = prompt {
-            Box::new(SubprocessCLITransport::new(prompt, self.options.clone())?)
+        let mut transport = if let Some(prompt) = prompt {
+            SubprocessCLITransport::new(prompt, self.options.clone())?
         } else {
-            Box::new(SubprocessCLITransport::streaming(self.options.clone())?)
+            SubprocessCLITransport::streaming(self.options.clone())?
         };
 
         transport.connect().await?;
+        if self.started_before {
+            Recorder::from_options(&self.options).counter(names::CLI_RESTARTS, &[], 1);
+        }
+        self.started_before = true;
+        self.cli_info = transport.cli_info().cloned();
+        let initialize_config = transport.initialize_config().clone();
+        let transport: Box<dyn Transport> = Box::new(transport);
 
         // Create query handler with callbacks from options
         let can_use_tool = self.options.can_use_tool.clone();
@@ -124,7 +169,10 @@ impl ClaudeSDKClient {
             can_use_tool,
             hooks,
             60, // initialize timeout
-        );
+        )
+        .with_can_use_tool_timeout(self.options.can_use_tool_timeout)
+        .with_initialize_config(initialize_config)
+        .with_tracer(SessionTracer::shared(&self.options));
 
         self.query_handler = Some(handler);
         self.connected = true;
@@ -132,6 +180,11 @@ impl ClaudeSDKClient {
         // Initialize if needed
         if let Some(ref mut handler) = self.query_handler {
             handler.initialize().await?;
+            self.slash_commands = handler
+                .initialization_result()
+                .and_then(|info| info.get("commands"))
+                .and_then(|commands| serde_json::from_value(commands.clone()).ok())
+                .unwrap_or_default();
         }
 
         Ok(())
@@ -154,7 +207,56 @@ impl ClaudeSDKClient {
         });
 
         let json_str = serde_json::to_string(&msg)?;
-        handler.write(&format!("{}\n", json_str)).await
+        handler.write(&format!("{}\n", json_str)).await?;
+        handler.start_turn();
+        self.retry.start_turn(msg);
+        Ok(())
+    }
+
+    /// Send a slash command, or expand and send a custom command.
+    ///
+    /// `name` may include the leading `/`. Custom commands from
+    /// [`ClaudeAgentOptions::custom_commands`] are expanded into a user
+    /// message; other names must be among [`slash_commands`](Self::slash_commands).
+    pub async fn send_command(&mut self, name: &str, args: &str) -> Result<()> {
+        let name = name.strip_prefix('/').unwrap_or(name);
+
+        let custom = self
+            .options
+            .custom_commands
+            .iter()
+            .find(|command| command.name == name);
+        if let Some(command) = custom {
+            let prompt = command.expand(args);
+            return self.send_message(prompt).await;
+        }
+
+        if !self
+            .slash_commands
+            .iter()
+            .any(|command| command.name == name)
+        {
+            return Err(ClaudeSDKError::UnknownCommand {
+                name: name.to_string(),
+                available: self
+                    .slash_commands
+                    .iter()
+                    .map(|command| command.name.clone())
+                    .chain(
+                        self.options
+                            .custom_commands
+                            .iter()
+                            .map(|command| command.name.clone()),
+                    )
+                    .collect(),
+            });
+        }
+        let args = args.trim();
+        if args.is_empty() {
+            self.send_message(format!("/{}", name)).await
+        } else {
+            self.send_message(format!("/{} {}", name, args)).await
+        }
     }
 
     /// Send a raw JSON message to Claude.
@@ -164,7 +266,12 @@ impl ClaudeSDKClient {
         })?;
 
         let json_str = serde_json::to_string(&message)?;
-        handler.write(&format!("{}\n", json_str)).await
+        handler.write(&format!("{}\n", json_str)).await?;
+        if message.get("type").and_then(|v| v.as_str()) == Some("user") {
+            handler.start_turn();
+            self.retry.start_turn(message);
+        }
+        Ok(())
     }
 
     /// Receive all messages from Claude.
@@ -173,23 +280,147 @@ impl ClaudeSDKClient {
     /// `collect()` to consume messages.
     ///
     /// This method handles bidirectional control protocol, processing
-    /// control requests from the CLI and queuing responses. Call
-    /// `flush_responses()` after consuming messages to send any
-    /// queued responses back to the CLI.
+    /// control requests from the CLI and answering them before the next
+    /// read. Call `flush_responses()` when you stop consuming to send any
+    /// responses still queued.
     pub fn receive_messages(&mut self) -> impl Stream<Item = Result<Message>> + '_ {
-        async_stream::try_stream! {
-            let handler = self.query_handler.as_mut().ok_or_else(|| {
-                ClaudeSDKError::CLIConnection("Not connected. Call connect() first.".to_string())
-            })?;
+        async_stream::stream! {
+            if self.query_handler.is_none() {
+                yield Err(ClaudeSDKError::CLIConnection(
+                    "Not connected. Call connect() first.".to_string(),
+                ));
+                return;
+            }
+
+            while let Some(result) = self.next_message().await {
+                // Malformed lines are reported and skipped
+                let recoverable =
+                    matches!(result, Ok(_) | Err(ClaudeSDKError::MessageParse { .. }));
+                yield result;
+                if !recoverable {
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Read the next message, retrying failed turns per the retry policy.
+    async fn next_message(&mut self) -> Option<Result<Message>> {
+        loop {
+            let result = {
+                let handler = self.query_handler.as_mut()?;
+                let stream = handler.receive_messages();
+                tokio::pin!(stream);
+                stream.next().await?
+            };
+            let msg = match result {
+                Ok(msg) => msg,
+                Err(e) => return Some(Err(e)),
+            };
+            if let Some(commands) = msg.as_system().and_then(|system| system.slash_commands()) {
+                self.update_slash_commands(commands);
+            }
 
-            let stream = handler.receive_messages();
-            tokio::pin!(stream);
+            let Some(policy) = self.retry_policy.clone() else {
+                return Some(Ok(msg));
+            };
 
-            while let Some(result) = stream.next().await {
-                let msg: Message = result?;
-                yield msg;
+            // Skip the rest of a failed turn, then resend once it ends
+            if let Some(error) = self.retry.pending {
+                if msg.is_result() {
+                    self.retry.pending = None;
+                    if let Err(e) = self.resend_after_error(&policy, error).await {
+                        return Some(Err(e));
+                    }
+                }
+                continue;
             }
+
+            if let Message::Assistant(ref assistant) = msg {
+                if let Some(error) = assistant.error {
+                    if policy.is_retryable(error)
+                        && self.retry.attempts < policy.max_retries
+                        && self.retry.last_user_message.is_some()
+                    {
+                        self.retry.pending = Some(error);
+                        continue;
+                    }
+                }
+            }
+
+            if msg.is_result() {
+                self.retry.attempts = 0;
+                if let Some(model) = self.retry.restore_model.take() {
+                    if let Err(e) = self.set_model(model).await {
+                        return Some(Err(e));
+                    }
+                }
+            }
+            return Some(Ok(msg));
+        }
+    }
+
+    /// Take the command list from an `init` message, keeping known descriptions.
+    fn update_slash_commands(&mut self, commands: Vec<SlashCommand>) {
+        let known = std::mem::take(&mut self.slash_commands);
+        self.slash_commands = commands
+            .into_iter()
+            .map(|command| {
+                known
+                    .iter()
+                    .find(|other| other.name == command.name)
+                    .cloned()
+                    .unwrap_or(command)
+            })
+            .collect();
+    }
+
+    /// Wait, optionally switch to the fallback model, and resend the last user message.
+    async fn resend_after_error(
+        &mut self,
+        policy: &RetryPolicy,
+        error: AssistantMessageError,
+    ) -> Result<()> {
+        self.retry.attempts += 1;
+        let attempt = self.retry.attempts;
+
+        let model = if policy.use_fallback_model && attempt == 1 {
+            self.options.fallback_model.clone()
+        } else {
+            None
+        };
+        let report = RetryAttempt {
+            attempt,
+            error,
+            delay: policy.delay(attempt),
+            model: model.clone(),
+        };
+        tracing::warn!(
+            attempt = report.attempt,
+            error = ?report.error,
+            delay_ms = report.delay.as_millis() as u64,
+            model = ?report.model,
+            "Retrying turn after API error"
+        );
+        if let Some(ref callback) = policy.on_retry {
+            callback(&report);
         }
+
+        tokio::time::sleep(report.delay).await;
+
+        let handler = self.query_handler.as_mut().ok_or_else(|| {
+            ClaudeSDKError::CLIConnection("Not connected. Call connect() first.".to_string())
+        })?;
+        if model.is_some() {
+            handler.set_model(model).await?;
+            self.retry.restore_model = Some(self.model.clone());
+        }
+        if let Some(ref message) = self.retry.last_user_message {
+            let json_str = serde_json::to_string(message)?;
+            handler.write(&format!("{}\n", json_str)).await?;
+            handler.start_turn();
+        }
+        Ok(())
     }
 
     /// Flush any pending control responses to the CLI.
@@ -215,11 +446,7 @@ impl ClaudeSDKClient {
     /// To process individual messages as they arrive, use `receive_messages()`
     /// instead and check for `is_result()` manually.
     pub async fn receive_response(&mut self) -> Option<Result<Message>> {
-        let handler = self.query_handler.as_mut()?;
-        let stream = handler.receive_messages();
-        tokio::pin!(stream);
-
-        while let Some(result) = stream.next().await {
+        while let Some(result) = self.next_message().await {
             match result {
                 Ok(msg) => {
                     if msg.is_result() {
@@ -274,7 +501,10 @@ impl ClaudeSDKClient {
             ClaudeSDKError::CLIConnection("Not connected. Call connect() first.".to_string())
         })?;
 
-        handler.set_model(model).await
+        handler.set_model(model.clone()).await?;
+        self.retry.restore_model = None;
+        self.model = model;
+        Ok(())
     }
 
     /// Rewind tracked files to their state at a specific user message.
@@ -313,6 +543,26 @@ impl ClaudeSDKClient {
             .and_then(|h| h.initialization_result())
     }
 
+    /// Slash commands the CLI offers.
+    ///
+    /// Filled in at connect from the initialization response and refreshed
+    /// from each `system/init` message. Custom commands are not included.
+    pub fn slash_commands(&self) -> &[SlashCommand] {
+        &self.slash_commands
+    }
+
+    /// Get the path and version of the connected CLI.
+    pub fn cli_info(&self) -> Option<&CliInfo> {
+        self.cli_info.as_ref()
+    }
+
+    /// Get the version of the connected CLI, if it could be detected.
+    ///
+    /// Useful for gating features on CLI capabilities.
+    pub fn cli_version(&self) -> Option<CliVersion> {
+        self.cli_info.as_ref().and_then(|info| info.version)
+    }
+
     /// Disconnect from Claude.
     pub async fn disconnect(&mut self) -> Result<()> {
         if let Some(mut handler) = self.query_handler.take() {
@@ -326,6 +576,19 @@ impl ClaudeSDKClient {
     pub fn is_connected(&self) -> bool {
         self.connected
     }
+
+    /// Check if the client is connected and its CLI process is still usable.
+    pub fn is_healthy(&self) -> bool {
+        self.connected && self.query_handler.as_ref().is_some_and(|h| h.is_ready())
+    }
+}
+
+impl RetryState {
+    fn start_turn(&mut self, message: Value) {
+        self.last_user_message = Some(message);
+        self.attempts = 0;
+        self.pending = None;
+    }
 }
 
 impl Drop for ClaudeSDKClient {
@@ -338,6 +601,8 @@ impl Drop for ClaudeSDKClient {
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[cfg(unix)]
+    use crate::test_support::fake_cli;
     use crate::types::PermissionMode;
 
     #[test]
@@ -378,6 +643,13 @@ mod tests {
         assert!(!client.is_connected());
     }
 
+    #[test]
+    fn test_client_cli_version_before_connect() {
+        let client = ClaudeSDKClient::default_client();
+        assert!(client.cli_info().is_none());
+        assert!(client.cli_version().is_none());
+    }
+
     #[test]
     fn test_client_get_server_info_before_connect() {
         let client = ClaudeSDKClient::default_client();
@@ -504,4 +776,280 @@ mod tests {
 
         client.disconnect().await.unwrap();
     }
+
+    /// Fake CLI: the first user message fails with a rate limit, later ones
+    /// succeed and echo the current model.
+    #[cfg(unix)]
+    const FLAKY_CLI: &str = r#"turns=0
+model=default
+while IFS= read -r line; do
+  id=$(request_id "$line")
+  case "$line" in
+    *'"set_model"'*) model=$(printf '%s' "$line" | sed -n 's/.*"model":"\([^"]*\)".*/\1/p') ;;
+  esac
+  if [ -n "$id" ]; then
+    control_success "$id"
+  elif is_user_message "$line"; then
+    turns=$((turns + 1))
+    if [ "$turns" -eq 1 ]; then
+      printf '{"type":"assistant","message":{"model":"%s","content":[],"error":"rate_limit"}}\n' "$model"
+      printf '{"type":"result","subtype":"error","duration_ms":1,"duration_api_ms":1,"is_error":true,"num_turns":1,"session_id":"s"}\n'
+    else
+      printf '{"type":"assistant","message":{"model":"%s","content":[{"type":"text","text":"ok %s"}]}}\n' "$model" "$turns"
+      result_success
+    fi
+  fi
+done
+"#;
+
+    /// Fake CLI reporting `compact` and `review` at initialization, then
+    /// `compact` and `context` in its init message, and echoing each prompt.
+    #[cfg(unix)]
+    const COMMANDS_CLI: &str = r#"while IFS= read -r line; do
+  id=$(request_id "$line")
+  if [ -n "$id" ]; then
+    control_success "$id" '{"commands":[{"name":"compact","description":"Compact history","argumentHint":"<focus>"},{"name":"review","description":"Review"}]}'
+  elif is_user_message "$line"; then
+    prompt=$(printf '%s' "$line" | sed -n 's/.*"content":"\([^"]*\)".*/\1/p')
+    printf '{"type":"system","subtype":"init","slash_commands":["compact","context"]}\n'
+    printf '{"type":"assistant","message":{"model":"m","content":[{"type":"text","text":"%s"}]}}\n' "$prompt"
+    result_success
+  fi
+done
+"#;
+
+    /// Fake CLI that answers `set_model` only after sending a whole turn,
+    /// so the client buffers the turn while it waits for the response.
+    #[cfg(unix)]
+    const MID_TURN_CONTROL_CLI: &str = r#"while IFS= read -r line; do
+  id=$(request_id "$line")
+  case "$line" in
+    *'"set_model"'*)
+      printf '{"type":"assistant","message":{"model":"m","content":[{"type":"text","text":"one"}]}}\n'
+      printf '{"type":"assistant","message":{"model":"m","content":[{"type":"text","text":"two"}]}}\n'
+      result_success ;;
+  esac
+  if [ -n "$id" ]; then control_success "$id"; fi
+done
+"#;
+
+    /// Fake CLI reporting whether agents arrived in the initialize request
+    /// rather than as `--agents`.
+    #[cfg(unix)]
+    const AGENTS_CLI: &str = r#"inline=false
+for arg in "$@"; do
+  if [ "$arg" = "--agents" ]; then inline=true; fi
+done
+while IFS= read -r line; do
+  id=$(request_id "$line")
+  case "$line" in
+    *'"agents":{"reviewer"'*) deferred=true ;;
+    *) deferred=false ;;
+  esac
+  if [ -n "$id" ]; then
+    control_success "$id" "{\"inline\":$inline,\"deferred\":$deferred}"
+  fi
+done
+"#;
+
+    #[cfg(unix)]
+    async fn collect_turn(client: &mut ClaudeSDKClient) -> Vec<Message> {
+        let stream = client.receive_messages();
+        tokio::pin!(stream);
+        let mut messages = Vec::new();
+        while let Some(message) = stream.next().await {
+            let message = message.unwrap();
+            let done = message.is_result();
+            messages.push(message);
+            if done {
+                break;
+            }
+        }
+        messages
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_retry_policy_resends_with_fallback_model() {
+        use std::sync::{Arc, Mutex};
+
+        let dir = tempfile::tempdir().unwrap();
+        let options = ClaudeAgentOptions::builder()
+            .cli_path(fake_cli(&dir, FLAKY_CLI))
+            .model("claude-sonnet")
+            .fallback_model("claude-haiku")
+            .build();
+        let attempts = Arc::new(Mutex::new(Vec::new()));
+        let policy = {
+            let attempts = attempts.clone();
+            RetryPolicy::new()
+                .with_initial_backoff(std::time::Duration::from_millis(1))
+                .with_fallback_model(true)
+                .on_retry(move |attempt| attempts.lock().unwrap().push(attempt.clone()))
+        };
+
+        let mut client = ClaudeSDKClient::new(options).with_retry_policy(policy);
+        client.connect().await.unwrap();
+        client.send_message("hi").await.unwrap();
+
+        let messages = collect_turn(&mut client).await;
+        assert_eq!(messages.len(), 2);
+        match &messages[0] {
+            Message::Assistant(assistant) => {
+                assert_eq!(assistant.text(), "ok 2");
+                assert_eq!(assistant.model, "claude-haiku");
+                assert!(assistant.error.is_none());
+            }
+            other => panic!("expected assistant message, got {:?}", other),
+        }
+
+        let attempts = attempts.lock().unwrap().clone();
+        assert_eq!(attempts.len(), 1);
+        assert_eq!(attempts[0].attempt, 1);
+        assert_eq!(attempts[0].error, AssistantMessageError::RateLimit);
+        assert_eq!(attempts[0].model.as_deref(), Some("claude-haiku"));
+
+        // The fallback only lasts for the retried turn
+        client.send_message("again").await.unwrap();
+        let messages = collect_turn(&mut client).await;
+        match &messages[0] {
+            Message::Assistant(assistant) => assert_eq!(assistant.model, "claude-sonnet"),
+            other => panic!("expected assistant message, got {:?}", other),
+        }
+
+        client.disconnect().await.unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_without_retry_policy_error_is_returned() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = ClaudeAgentOptions::builder()
+            .cli_path(fake_cli(&dir, FLAKY_CLI))
+            .build();
+
+        let mut client = ClaudeSDKClient::new(options);
+        client.connect().await.unwrap();
+        client.send_message("hi").await.unwrap();
+
+        let messages = collect_turn(&mut client).await;
+        match &messages[0] {
+            Message::Assistant(assistant) => {
+                assert_eq!(assistant.error, Some(AssistantMessageError::RateLimit))
+            }
+            other => panic!("expected assistant message, got {:?}", other),
+        }
+        assert!(messages[1].is_result());
+
+        client.disconnect().await.unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_slash_commands_and_send_command() {
+        use crate::types::{CustomCommand, SlashCommand};
+
+        let dir = tempfile::tempdir().unwrap();
+        let options = ClaudeAgentOptions::builder()
+            .cli_path(fake_cli(&dir, COMMANDS_CLI))
+            .custom_commands(vec![CustomCommand::new(
+                "explain",
+                "Explain code",
+                "Explain $1 briefly.",
+            )])
+            .build();
+
+        let mut client = ClaudeSDKClient::new(options);
+        assert!(client.slash_commands().is_empty());
+        client.connect().await.unwrap();
+        assert_eq!(
+            client.slash_commands()[0],
+            SlashCommand::new("compact")
+                .with_description("Compact history")
+                .with_argument_hint("<focus>")
+        );
+        assert_eq!(client.slash_commands()[1].name, "review");
+
+        client.send_command("/compact", " tests ").await.unwrap();
+        let messages = collect_turn(&mut client).await;
+        assert_eq!(messages[1].as_assistant().unwrap().text(), "/compact tests");
+
+        // The init message replaces the list, keeping known descriptions
+        let names: Vec<_> = client
+            .slash_commands()
+            .iter()
+            .map(|command| command.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["compact", "context"]);
+        assert!(client.slash_commands()[0].description.is_some());
+
+        client.send_command("explain", "lib.rs").await.unwrap();
+        let messages = collect_turn(&mut client).await;
+        assert_eq!(
+            messages[1].as_assistant().unwrap().text(),
+            "Explain lib.rs briefly."
+        );
+
+        match client.send_command("review", "").await {
+            Err(ClaudeSDKError::UnknownCommand { name, available }) => {
+                assert_eq!(name, "review");
+                assert_eq!(available, vec!["compact", "context", "explain"]);
+            }
+            other => panic!("expected unknown command error, got {:?}", other),
+        }
+
+        client.disconnect().await.unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_messages_buffered_during_control_request_are_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = ClaudeAgentOptions::builder()
+            .cli_path(fake_cli(&dir, MID_TURN_CONTROL_CLI))
+            .build();
+
+        let mut client = ClaudeSDKClient::new(options);
+        client.connect().await.unwrap();
+        client.send_message("hi").await.unwrap();
+        client.set_model(Some("m".to_string())).await.unwrap();
+
+        let messages = collect_turn(&mut client).await;
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].as_assistant().unwrap().text(), "one");
+        assert_eq!(messages[1].as_assistant().unwrap().text(), "two");
+        assert!(messages[2].is_result());
+
+        // receive_response reads past both buffered assistant messages
+        client.send_message("again").await.unwrap();
+        client.set_model(Some("m".to_string())).await.unwrap();
+        let result = client.receive_response().await.unwrap().unwrap();
+        assert!(result.is_result());
+
+        client.disconnect().await.unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_oversized_agents_are_sent_on_initialize() {
+        use crate::types::AgentDefinition;
+
+        let dir = tempfile::tempdir().unwrap();
+        let agents = std::collections::HashMap::from([(
+            "reviewer".to_string(),
+            AgentDefinition::new("Reviews code", "x".repeat(200_000)),
+        )]);
+        let options = ClaudeAgentOptions::builder()
+            .cli_path(fake_cli(&dir, AGENTS_CLI))
+            .agents(agents)
+            .build();
+
+        let mut client = ClaudeSDKClient::new(options);
+        client.connect().await.unwrap();
+        let info = client.get_server_info().unwrap();
+        assert_eq!(info["inline"], false);
+        assert_eq!(info["deferred"], true);
+
+        client.disconnect().await.unwrap();
+    }
 }
@@ -0,0 +1,532 @@
+//! Aggregating a message stream into turns and tool calls.
+//!
+//! [`Conversation`] pairs each `tool_use` block with the `tool_result` that
+//! answers it and nests messages from subagents (the `Task` tool) under the
+//! tool call that started them, using `parent_tool_use_id`.
+//!
+//! ```
+//! use claude_agent_sdk::conversation::Conversation;
+//! use claude_agent_sdk::{AssistantMessage, ContentBlock, Message, UserMessage};
+//! use serde_json::json;
+//!
+//! let mut conversation = Conversation::new();
+//! conversation.push(&Message::Assistant(AssistantMessage::new(
+//!     vec![ContentBlock::tool_use("toolu_1", "Bash", json!({"command": "ls"}))],
+//!     "claude-sonnet-4-5",
+//! )));
+//! conversation.push(&Message::User(UserMessage::new(vec![ContentBlock::tool_result(
+//!     "toolu_1",
+//!     Some(json!("README.md")),
+//!     None,
+//! )])));
+//!
+//! let call = conversation.tool_call("toolu_1").unwrap();
+//! assert_eq!(call.name, "Bash");
+//! assert_eq!(call.result, Some(json!("README.md")));
+//! ```
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::types::{
+    AssistantMessage, ContentBlock, Message, ResultMessage, UserMessage, UserMessageContent,
+};
+
+/// Name of the tool that starts subagents.
+pub const TASK_TOOL_NAME: &str = "Task";
+
+/// A conversation assembled from messages.
+///
+/// Serializes to JSON with serde, so a snapshot can be stored or sent at any
+/// point.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Conversation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    pub turns: Vec<Turn>,
+}
+
+/// A prompt and everything produced in response to it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Turn {
+    /// The prompt, if it was seen; the CLI does not echo prompts by default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<UserMessageContent>,
+    pub responses: Vec<AssistantMessage>,
+    pub tool_calls: Vec<ToolCall>,
+    /// Result message that ended the turn.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<ResultMessage>,
+    /// Milliseconds since the Unix epoch.
+    pub started_at_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at_ms: Option<u64>,
+}
+
+/// A tool use and its result.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    pub input: Value,
+    /// Content of the tool result, once it arrives.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    /// Structured result reported alongside the tool result.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_use_result: Option<Value>,
+    pub is_error: bool,
+    /// Milliseconds since the Unix epoch.
+    pub started_at_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at_ms: Option<u64>,
+    /// Conversation of the subagent this call started.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subagent: Option<Box<Subagent>>,
+}
+
+/// A subagent started by a tool call.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Subagent {
+    /// The `subagent_type` from the tool input.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subagent_type: Option<String>,
+    /// The `description` from the tool input.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub conversation: Conversation,
+}
+
+impl Conversation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a conversation from a transcript.
+    pub fn from_messages<'a>(messages: impl IntoIterator<Item = &'a Message>) -> Self {
+        let mut conversation = Self::new();
+        for message in messages {
+            conversation.push(message);
+        }
+        conversation
+    }
+
+    /// Start a turn with a prompt sent to the CLI.
+    pub fn start_turn(&mut self, prompt: impl Into<UserMessageContent>) {
+        self.start_turn_at(Some(prompt.into()), SystemTime::now());
+    }
+
+    /// Add a message received now.
+    pub fn push(&mut self, message: &Message) {
+        self.push_at(message, SystemTime::now());
+    }
+
+    /// Add a message received at `at`.
+    pub fn push_at(&mut self, message: &Message, at: SystemTime) {
+        let owner = message
+            .parent_tool_use_id()
+            .and_then(|id| self.tool_call_mut(id));
+        match owner {
+            Some(call) => call.subagent_mut().conversation.apply(message, at),
+            None => self.apply(message, at),
+        }
+    }
+
+    /// The latest turn.
+    pub fn current_turn(&self) -> Option<&Turn> {
+        self.turns.last()
+    }
+
+    /// Find a tool call by id, including calls made by subagents.
+    pub fn tool_call(&self, id: &str) -> Option<&ToolCall> {
+        self.turns
+            .iter()
+            .flat_map(|turn| &turn.tool_calls)
+            .find_map(|call| call.find(id))
+    }
+
+    /// Tool calls made directly in this conversation, in order.
+    pub fn tool_calls(&self) -> impl Iterator<Item = &ToolCall> {
+        self.turns.iter().flat_map(|turn| &turn.tool_calls)
+    }
+
+    /// Tool calls still waiting for a result, including those of subagents.
+    pub fn pending_tool_calls(&self) -> Vec<&ToolCall> {
+        let mut pending = Vec::new();
+        for call in self.tool_calls() {
+            call.collect_pending(&mut pending);
+        }
+        pending
+    }
+
+    /// Result of the latest finished turn.
+    pub fn last_result(&self) -> Option<&ResultMessage> {
+        self.turns
+            .iter()
+            .rev()
+            .find_map(|turn| turn.result.as_ref())
+    }
+
+    fn tool_call_mut(&mut self, id: &str) -> Option<&mut ToolCall> {
+        self.turns
+            .iter_mut()
+            .flat_map(|turn| &mut turn.tool_calls)
+            .find_map(|call| call.find_mut(id))
+    }
+
+    fn apply(&mut self, message: &Message, at: SystemTime) {
+        match message {
+            Message::System(system) if system.subtype == "init" => {
+                if let Some(id) = system.data.get("session_id").and_then(Value::as_str) {
+                    self.session_id = Some(id.to_string());
+                }
+                if let Some(model) = system.data.get("model").and_then(Value::as_str) {
+                    self.model = Some(model.to_string());
+                }
+            }
+            Message::User(user) => self.apply_user(user, at),
+            Message::Assistant(assistant) => {
+                let turn = self.open_turn(at);
+                for block in &assistant.content {
+                    if let ContentBlock::ToolUse { id, name, input } = block {
+                        turn.tool_calls.push(ToolCall::new(id, name, input, at));
+                    }
+                }
+                turn.responses.push(assistant.clone());
+            }
+            Message::Result(result) => {
+                if self.session_id.is_none() {
+                    self.session_id = Some(result.session_id.clone());
+                }
+                let turn = self.open_turn(at);
+                turn.result = Some(result.clone());
+                turn.finished_at_ms = Some(millis(at));
+            }
+            _ => {}
+        }
+    }
+
+    fn apply_user(&mut self, user: &UserMessage, at: SystemTime) {
+        let results: Vec<_> = match user.content {
+            UserMessageContent::Blocks(ref blocks) => blocks
+                .iter()
+                .filter_map(|block| match block {
+                    ContentBlock::ToolResult {
+                        tool_use_id,
+                        content,
+                        is_error,
+                    } => Some((tool_use_id, content, is_error)),
+                    _ => None,
+                })
+                .collect(),
+            UserMessageContent::Text(_) => Vec::new(),
+        };
+
+        if results.is_empty() {
+            // A prompt; skip the echo of one already recorded by start_turn
+            let echoed = self.turns.last().is_some_and(|turn| {
+                turn.prompt.as_ref() == Some(&user.content)
+                    && turn.responses.is_empty()
+                    && turn.result.is_none()
+            });
+            if !echoed {
+                self.start_turn_at(Some(user.content.clone()), at);
+            }
+            return;
+        }
+
+        for (id, content, is_error) in results {
+            if let Some(call) = self.tool_call_mut(id) {
+                call.result = content.clone();
+                call.is_error = is_error.unwrap_or(false);
+                call.finished_at_ms = Some(millis(at));
+                if user.tool_use_result.is_some() {
+                    call.tool_use_result = user.tool_use_result.clone();
+                }
+            }
+        }
+    }
+
+    fn start_turn_at(&mut self, prompt: Option<UserMessageContent>, at: SystemTime) {
+        self.turns.push(Turn {
+            prompt,
+            started_at_ms: millis(at),
+            ..Turn::default()
+        });
+    }
+
+    /// The unfinished latest turn, started if there is none.
+    fn open_turn(&mut self, at: SystemTime) -> &mut Turn {
+        if !matches!(self.turns.last(), Some(turn) if !turn.is_complete()) {
+            self.start_turn_at(None, at);
+        }
+        self.turns.last_mut().expect("turn was just started")
+    }
+}
+
+impl Turn {
+    /// Check if a result message ended this turn.
+    pub fn is_complete(&self) -> bool {
+        self.result.is_some()
+    }
+
+    /// Text of all responses.
+    pub fn text(&self) -> String {
+        self.responses
+            .iter()
+            .map(AssistantMessage::text)
+            .collect::<Vec<_>>()
+            .join("")
+    }
+
+    /// Time from the start of the turn to its result.
+    pub fn duration(&self) -> Option<Duration> {
+        elapsed(self.started_at_ms, self.finished_at_ms)
+    }
+}
+
+impl ToolCall {
+    fn new(id: &str, name: &str, input: &Value, at: SystemTime) -> Self {
+        let subagent = (name == TASK_TOOL_NAME).then(|| Box::new(Subagent::from_input(input)));
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            input: input.clone(),
+            result: None,
+            tool_use_result: None,
+            is_error: false,
+            started_at_ms: millis(at),
+            finished_at_ms: None,
+            subagent,
+        }
+    }
+
+    /// Check if the result has not arrived yet.
+    pub fn is_pending(&self) -> bool {
+        self.finished_at_ms.is_none()
+    }
+
+    /// Time from the tool use to its result.
+    pub fn duration(&self) -> Option<Duration> {
+        elapsed(self.started_at_ms, self.finished_at_ms)
+    }
+
+    fn find(&self, id: &str) -> Option<&ToolCall> {
+        if self.id == id {
+            return Some(self);
+        }
+        self.subagent
+            .as_ref()
+            .and_then(|subagent| subagent.conversation.tool_call(id))
+    }
+
+    fn find_mut(&mut self, id: &str) -> Option<&mut ToolCall> {
+        if self.id == id {
+            return Some(self);
+        }
+        self.subagent
+            .as_mut()
+            .and_then(|subagent| subagent.conversation.tool_call_mut(id))
+    }
+
+    fn collect_pending<'a>(&'a self, pending: &mut Vec<&'a ToolCall>) {
+        if self.is_pending() {
+            pending.push(self);
+        }
+        if let Some(ref subagent) = self.subagent {
+            pending.extend(subagent.conversation.pending_tool_calls());
+        }
+    }
+
+    fn subagent_mut(&mut self) -> &mut Subagent {
+        let input = &self.input;
+        self.subagent
+            .get_or_insert_with(|| Box::new(Subagent::from_input(input)))
+    }
+}
+
+impl Subagent {
+    fn from_input(input: &Value) -> Self {
+        let field = |key: &str| input.get(key).and_then(Value::as_str).map(str::to_string);
+        Self {
+            subagent_type: field("subagent_type"),
+            description: field("description"),
+            conversation: Conversation::new(),
+        }
+    }
+}
+
+fn millis(at: SystemTime) -> u64 {
+    at.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn elapsed(start_ms: u64, end_ms: Option<u64>) -> Option<Duration> {
+    end_ms.map(|end| Duration::from_millis(end.saturating_sub(start_ms)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn at(ms: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(ms)
+    }
+
+    fn tool_use(id: &str, name: &str, input: Value) -> Message {
+        Message::Assistant(AssistantMessage::new(
+            vec![ContentBlock::tool_use(id, name, input)],
+            "claude-sonnet-4-5",
+        ))
+    }
+
+    fn tool_result(id: &str, content: &str, is_error: bool) -> UserMessage {
+        UserMessage::new(vec![ContentBlock::tool_result(
+            id,
+            Some(json!(content)),
+            Some(is_error),
+        )])
+    }
+
+    #[test]
+    fn test_pairs_tool_use_with_result() {
+        let mut conversation = Conversation::new();
+        conversation.start_turn("list files");
+        conversation.push_at(
+            &tool_use("toolu_1", "Bash", json!({"command": "ls"})),
+            at(100),
+        );
+        assert_eq!(conversation.pending_tool_calls().len(), 1);
+
+        conversation.push_at(
+            &Message::User(tool_result("toolu_1", "boom", true)),
+            at(350),
+        );
+        conversation.push_at(
+            &Message::Result(ResultMessage::new("success", 400, 300, false, 2, "sess-1")),
+            at(400),
+        );
+
+        let call = conversation.tool_call("toolu_1").unwrap();
+        assert_eq!(call.input, json!({"command": "ls"}));
+        assert_eq!(call.result, Some(json!("boom")));
+        assert!(call.is_error);
+        assert_eq!(call.duration(), Some(Duration::from_millis(250)));
+        assert!(conversation.pending_tool_calls().is_empty());
+
+        assert_eq!(conversation.turns.len(), 1);
+        let turn = &conversation.turns[0];
+        assert_eq!(turn.prompt, Some(UserMessageContent::from("list files")));
+        assert!(turn.is_complete());
+        assert_eq!(conversation.session_id.as_deref(), Some("sess-1"));
+    }
+
+    #[test]
+    fn test_nests_subagent_messages() {
+        let mut conversation = Conversation::new();
+        conversation.push(&tool_use(
+            "task_1",
+            TASK_TOOL_NAME,
+            json!({"subagent_type": "explorer", "description": "Find tests", "prompt": "..."}),
+        ));
+        conversation.push(&Message::User(
+            UserMessage::new("Find tests").with_parent_tool_use_id("task_1"),
+        ));
+        conversation.push(&Message::Assistant(
+            AssistantMessage::new(
+                vec![ContentBlock::tool_use(
+                    "toolu_2",
+                    "Grep",
+                    json!({"pattern": "test"}),
+                )],
+                "claude-haiku-4-5",
+            )
+            .with_parent_tool_use_id("task_1"),
+        ));
+        conversation.push(&Message::User(
+            tool_result("toolu_2", "src/lib.rs", false).with_parent_tool_use_id("task_1"),
+        ));
+        conversation.push(&Message::User(tool_result("task_1", "Found 3", false)));
+
+        let task = conversation.tool_call("task_1").unwrap();
+        assert!(!task.is_pending());
+        let subagent = task.subagent.as_ref().unwrap();
+        assert_eq!(subagent.subagent_type.as_deref(), Some("explorer"));
+        assert_eq!(subagent.description.as_deref(), Some("Find tests"));
+
+        let child = &subagent.conversation;
+        assert_eq!(child.turns.len(), 1);
+        assert_eq!(
+            child.turns[0].prompt,
+            Some(UserMessageContent::from("Find tests"))
+        );
+        assert_eq!(child.tool_calls().count(), 1);
+        assert_eq!(
+            conversation.tool_call("toolu_2").unwrap().result,
+            Some(json!("src/lib.rs"))
+        );
+        // Only the Task call is made by the root conversation
+        assert_eq!(conversation.tool_calls().count(), 1);
+    }
+
+    #[test]
+    fn test_echoed_prompt_is_not_a_new_turn() {
+        let mut conversation = Conversation::new();
+        conversation.start_turn("hello");
+        conversation.push(&Message::User(UserMessage::new("hello")));
+        assert_eq!(conversation.turns.len(), 1);
+
+        conversation.push(&Message::Result(ResultMessage::new(
+            "success", 1, 1, false, 1, "s",
+        )));
+        conversation.push(&Message::User(UserMessage::new("hello")));
+        assert_eq!(conversation.turns.len(), 2);
+    }
+
+    #[test]
+    fn test_turn_started_by_assistant_without_prompt() {
+        let messages = vec![
+            Message::Assistant(AssistantMessage::new(
+                vec![ContentBlock::text("Hi")],
+                "claude-sonnet-4-5",
+            )),
+            Message::Result(ResultMessage::new("success", 1, 1, false, 1, "s")),
+            Message::Assistant(AssistantMessage::new(
+                vec![ContentBlock::text("Again")],
+                "claude-sonnet-4-5",
+            )),
+        ];
+        let conversation = Conversation::from_messages(&messages);
+        assert_eq!(conversation.turns.len(), 2);
+        assert_eq!(conversation.turns[0].text(), "Hi");
+        assert!(conversation.turns[0].prompt.is_none());
+        assert!(!conversation.turns[1].is_complete());
+        assert!(conversation.last_result().is_some());
+    }
+
+    #[test]
+    fn test_serde_roundtrip() {
+        let mut conversation = Conversation::new();
+        conversation.push(&tool_use(
+            "task_1",
+            TASK_TOOL_NAME,
+            json!({"subagent_type": "x"}),
+        ));
+        conversation.push(&Message::User(
+            tool_result("inner", "ok", false).with_parent_tool_use_id("task_1"),
+        ));
+
+        let json = serde_json::to_value(&conversation).unwrap();
+        assert_eq!(
+            json["turns"][0]["tool_calls"][0]["subagent"]["subagent_type"],
+            "x"
+        );
+        let restored: Conversation = serde_json::from_value(json).unwrap();
+        assert_eq!(restored, conversation);
+    }
+}
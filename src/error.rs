@@ -51,6 +51,20 @@ pub enum ClaudeSDKError {
     /// Raised when an invalid configuration is provided.
     #[error("Invalid configuration: {0}")]
     InvalidConfig(String),
+
+    /// Raised when sending a slash command the session does not offer.
+    #[error("Unknown slash command: /{name}")]
+    UnknownCommand {
+        name: String,
+        available: Vec<String>,
+    },
+
+    /// Raised when structured output is missing or does not match the expected type.
+    #[error("Structured output error: {message}")]
+    StructuredOutput {
+        message: String,
+        output: Option<Value>,
+    },
 }
 
 impl ClaudeSDKError {
@@ -133,6 +147,16 @@ mod tests {
         assert!(msg.contains("Permission denied"));
     }
 
+    #[test]
+    fn test_structured_output_error() {
+        let err = ClaudeSDKError::StructuredOutput {
+            message: "missing field `name`".to_string(),
+            output: Some(serde_json::json!({})),
+        };
+        assert!(err.to_string().contains("Structured output error"));
+        assert!(err.to_string().contains("missing field `name`"));
+    }
+
     #[test]
     fn test_message_parse_error() {
         let err = ClaudeSDKError::message_parse("Missing type field", None);
@@ -170,6 +194,15 @@ mod tests {
         assert!(err.to_string().contains("Missing required field"));
     }
 
+    #[test]
+    fn test_unknown_command_error() {
+        let err = ClaudeSDKError::UnknownCommand {
+            name: "deploy".to_string(),
+            available: vec!["compact".to_string()],
+        };
+        assert_eq!(err.to_string(), "Unknown slash command: /deploy");
+    }
+
     #[test]
     fn test_process_error_without_optional_fields() {
         let err = ClaudeSDKError::process_error("Command failed", None, None);
@@ -0,0 +1,638 @@
+//! Rendering conversations for sharing.
+//!
+//! [`to_markdown`] and [`to_html`] turn a sequence of messages, from a live
+//! stream or a saved transcript, into a readable document: thinking is
+//! collapsible, tool calls show their inputs and results, `Edit` and
+//! `MultiEdit` calls are shown as diffs, and each [`ResultMessage`] adds a
+//! cost and usage footer. [`to_jsonl`] and [`from_jsonl`] store the messages
+//! losslessly, one JSON object per line.
+//!
+//! ```
+//! use claude_agent_sdk::export;
+//! use claude_agent_sdk::{AssistantMessage, ContentBlock, Message, UserMessage};
+//!
+//! let messages = vec![
+//!     Message::User(UserMessage::new("What is 2 + 2?")),
+//!     Message::Assistant(AssistantMessage::new(
+//!         vec![ContentBlock::text("4")],
+//!         "claude-sonnet-4-5",
+//!     )),
+//! ];
+//!
+//! let markdown = export::to_markdown(&messages);
+//! assert!(markdown.contains("What is 2 + 2?"));
+//!
+//! let jsonl = export::to_jsonl(&messages)?;
+//! assert_eq!(export::from_jsonl(&jsonl)?, messages);
+//! # Ok::<(), claude_agent_sdk::ClaudeSDKError>(())
+//! ```
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::Write;
+
+use crate::conversation::TASK_TOOL_NAME;
+use crate::error::{ClaudeSDKError, Result};
+use crate::types::{ContentBlock, Message, ResultMessage, SystemMessage, UserMessageContent};
+
+/// Input keys shown next to a tool's name, in order of preference.
+const SUMMARY_KEYS: &[&str] = &[
+    "command",
+    "file_path",
+    "notebook_path",
+    "pattern",
+    "url",
+    "query",
+    "description",
+];
+
+/// Render messages as Markdown.
+///
+/// Thinking and tool results are wrapped in `<details>` blocks, which
+/// GitHub and most Markdown viewers render as collapsible sections.
+pub fn to_markdown<'a>(messages: impl IntoIterator<Item = &'a Message>) -> String {
+    let mut out = Markdown(String::new());
+    render(messages, &mut out);
+    out.0
+}
+
+/// Render messages as a self-contained HTML page.
+pub fn to_html<'a>(messages: impl IntoIterator<Item = &'a Message>) -> String {
+    let mut out = Html(String::new());
+    render(messages, &mut out);
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Conversation</title>\n<style>{}</style>\n</head>\n<body>\n<main>\n{}</main>\n\
+         </body>\n</html>\n",
+        HTML_STYLE, out.0
+    )
+}
+
+/// Serialize messages as JSON Lines, one message per line.
+pub fn to_jsonl<'a>(messages: impl IntoIterator<Item = &'a Message>) -> Result<String> {
+    let mut buffer = Vec::new();
+    write_jsonl(&mut buffer, messages)?;
+    // serde_json only writes valid UTF-8
+    Ok(String::from_utf8(buffer).expect("JSON output is UTF-8"))
+}
+
+/// Write messages as JSON Lines to `writer`.
+pub fn write_jsonl<'a, W: Write>(
+    mut writer: W,
+    messages: impl IntoIterator<Item = &'a Message>,
+) -> Result<()> {
+    for message in messages {
+        serde_json::to_writer(&mut writer, message)?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// Parse messages written by [`to_jsonl`] or [`write_jsonl`].
+///
+/// Blank lines are skipped.
+pub fn from_jsonl(input: &str) -> Result<Vec<Message>> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line).map_err(|e| {
+                ClaudeSDKError::message_parse(format!("line {}: {}", index + 1, e), None)
+            })
+        })
+        .collect()
+}
+
+/// A tool call with its result, as passed to a [`Renderer`].
+struct ToolView<'a> {
+    name: &'a str,
+    summary: Option<String>,
+    input: &'a Value,
+    diff: Option<String>,
+    result: Option<(String, bool)>,
+}
+
+/// Output format driven by [`render`].
+trait Renderer {
+    fn role(&mut self, role: &str);
+    fn session(&mut self, details: &[String]);
+    fn text(&mut self, text: &str);
+    fn thinking(&mut self, thinking: &str);
+    fn tool(&mut self, tool: &ToolView<'_>);
+    fn footer(&mut self, summary: &[String]);
+}
+
+fn render<'a>(messages: impl IntoIterator<Item = &'a Message>, out: &mut impl Renderer) {
+    let messages: Vec<&Message> = messages.into_iter().collect();
+
+    let mut results: HashMap<&str, (String, bool)> = HashMap::new();
+    let mut subagents: HashMap<&str, String> = HashMap::new();
+    for message in &messages {
+        match message {
+            Message::User(user) => {
+                if let UserMessageContent::Blocks(blocks) = &user.content {
+                    for block in blocks {
+                        if let ContentBlock::ToolResult {
+                            tool_use_id,
+                            content,
+                            is_error,
+                        } = block
+                        {
+                            let text = content.as_ref().map(result_text).unwrap_or_default();
+                            results.insert(tool_use_id, (text, is_error.unwrap_or(false)));
+                        }
+                    }
+                }
+            }
+            Message::Assistant(assistant) => {
+                for block in &assistant.content {
+                    if let ContentBlock::ToolUse { id, name, input } = block {
+                        if name == TASK_TOOL_NAME {
+                            let kind = input
+                                .get("subagent_type")
+                                .and_then(Value::as_str)
+                                .unwrap_or("task");
+                            subagents.insert(id, kind.to_string());
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut current_role = String::new();
+    for message in messages {
+        let role_suffix = message
+            .parent_tool_use_id()
+            .map(|id| match subagents.get(id) {
+                Some(kind) => format!(" ({})", kind),
+                None => " (subagent)".to_string(),
+            })
+            .unwrap_or_default();
+
+        match message {
+            Message::User(user) => {
+                let text = match &user.content {
+                    UserMessageContent::Text(text) => text.clone(),
+                    UserMessageContent::Blocks(blocks) => blocks
+                        .iter()
+                        .filter_map(ContentBlock::as_text)
+                        .collect::<Vec<_>>()
+                        .join("\n\n"),
+                };
+                // Tool results are shown with their tool use
+                if !text.trim().is_empty() {
+                    switch_role(out, &mut current_role, format!("User{}", role_suffix));
+                    out.text(&text);
+                }
+            }
+            Message::Assistant(assistant) => {
+                switch_role(out, &mut current_role, format!("Assistant{}", role_suffix));
+                for block in &assistant.content {
+                    match block {
+                        ContentBlock::Text { text } => out.text(text),
+                        ContentBlock::Thinking { thinking, .. } => out.thinking(thinking),
+                        ContentBlock::ToolUse { id, name, input } => out.tool(&ToolView {
+                            name,
+                            summary: tool_summary(input),
+                            input,
+                            diff: edit_diff(name, input),
+                            result: results.get(id.as_str()).cloned(),
+                        }),
+                        ContentBlock::ToolResult { .. } => {}
+                    }
+                }
+            }
+            Message::System(system) if system.subtype == "init" => {
+                out.session(&session_details(system));
+            }
+            Message::Result(result) => {
+                out.footer(&result_summary(result));
+                current_role.clear();
+            }
+            Message::System(_) | Message::StreamEvent(_) => {}
+        }
+    }
+}
+
+/// Start a new section unless `role` is already speaking.
+fn switch_role(out: &mut impl Renderer, current: &mut String, role: String) {
+    if *current != role {
+        out.role(&role);
+        *current = role;
+    }
+}
+
+fn session_details(system: &SystemMessage) -> Vec<String> {
+    ["session_id", "model", "cwd", "permissionMode"]
+        .iter()
+        .filter_map(|key| {
+            let value = system.data.get(*key)?.as_str()?;
+            let label = match *key {
+                "session_id" => "session",
+                "permissionMode" => "permission mode",
+                other => other,
+            };
+            Some(format!("{} {}", label, value))
+        })
+        .collect()
+}
+
+fn result_summary(result: &ResultMessage) -> Vec<String> {
+    let mut parts = vec![
+        result.subtype.clone(),
+        format!(
+            "{} turn{}",
+            result.num_turns,
+            if result.num_turns == 1 { "" } else { "s" }
+        ),
+        format!("{:.1}s", result.duration_ms as f64 / 1000.0),
+    ];
+    if let Some(cost) = result.total_cost_usd {
+        parts.push(format!("${:.4}", cost));
+    }
+    let usage = |key: &str| {
+        result
+            .usage
+            .as_ref()
+            .and_then(|usage| usage.get(key))
+            .and_then(Value::as_u64)
+    };
+    if let (Some(input), Some(output)) = (usage("input_tokens"), usage("output_tokens")) {
+        parts.push(format!("{} input / {} output tokens", input, output));
+    }
+    let cache_read = usage("cache_read_input_tokens").unwrap_or(0);
+    let cache_write = usage("cache_creation_input_tokens").unwrap_or(0);
+    if cache_read > 0 || cache_write > 0 {
+        parts.push(format!(
+            "cache {} read / {} written",
+            cache_read, cache_write
+        ));
+    }
+    parts
+}
+
+/// Text of a tool result's content.
+fn result_text(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(blocks) => blocks
+            .iter()
+            .map(|block| match block.get("text").and_then(Value::as_str) {
+                Some(text) => text.to_string(),
+                None => pretty(block),
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        other => pretty(other),
+    }
+}
+
+fn tool_summary(input: &Value) -> Option<String> {
+    SUMMARY_KEYS.iter().find_map(|key| {
+        let value = input.get(*key)?.as_str()?;
+        Some(value.lines().next().unwrap_or_default().to_string())
+    })
+}
+
+/// A unified-style diff for `Edit` and `MultiEdit` tool inputs.
+fn edit_diff(name: &str, input: &Value) -> Option<String> {
+    let edits: Vec<&Value> = match name {
+        "Edit" => vec![input],
+        "MultiEdit" => input.get("edits")?.as_array()?.iter().collect(),
+        _ => return None,
+    };
+    let path = input.get("file_path")?.as_str()?;
+
+    let mut diff = format!("--- {}\n+++ {}\n", path, path);
+    for edit in edits {
+        let old = edit.get("old_string")?.as_str()?;
+        let new = edit.get("new_string")?.as_str()?;
+        diff.push_str("@@\n");
+        for line in old.lines() {
+            let _ = writeln!(diff, "-{}", line);
+        }
+        for line in new.lines() {
+            let _ = writeln!(diff, "+{}", line);
+        }
+    }
+    Some(diff)
+}
+
+fn pretty(value: &Value) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string())
+}
+
+struct Markdown(String);
+
+impl Markdown {
+    fn code(&mut self, lang: &str, content: &str) {
+        // Use a fence longer than any backtick run in the content
+        let mut longest = 0;
+        let mut run = 0;
+        for c in content.chars() {
+            run = if c == '`' { run + 1 } else { 0 };
+            longest = longest.max(run);
+        }
+        let fence = "`".repeat((longest + 1).max(3));
+        let _ = writeln!(
+            self.0,
+            "{}{}\n{}\n{}\n",
+            fence,
+            lang,
+            content.trim_end_matches('\n'),
+            fence
+        );
+    }
+}
+
+impl Renderer for Markdown {
+    fn role(&mut self, role: &str) {
+        let _ = writeln!(self.0, "## {}\n", role);
+    }
+
+    fn session(&mut self, details: &[String]) {
+        let _ = writeln!(self.0, "_{}_\n", details.join(" · "));
+    }
+
+    fn text(&mut self, text: &str) {
+        let _ = writeln!(self.0, "{}\n", text.trim_end());
+    }
+
+    fn thinking(&mut self, thinking: &str) {
+        let _ = writeln!(
+            self.0,
+            "<details>\n<summary>Thinking</summary>\n\n{}\n\n</details>\n",
+            thinking.trim_end()
+        );
+    }
+
+    fn tool(&mut self, tool: &ToolView<'_>) {
+        match &tool.summary {
+            Some(summary) => {
+                let _ = writeln!(self.0, "**Tool: {}** `{}`\n", tool.name, summary);
+            }
+            None => {
+                let _ = writeln!(self.0, "**Tool: {}**\n", tool.name);
+            }
+        }
+        match &tool.diff {
+            Some(diff) => self.code("diff", diff),
+            None => self.code("json", &pretty(tool.input)),
+        }
+        if let Some((result, is_error)) = &tool.result {
+            let label = if *is_error { "Error" } else { "Result" };
+            let _ = writeln!(self.0, "<details>\n<summary>{}</summary>\n", label);
+            self.code("text", result);
+            self.0.push_str("</details>\n\n");
+        }
+    }
+
+    fn footer(&mut self, summary: &[String]) {
+        let _ = writeln!(self.0, "---\n\n**{}**\n", summary.join(" · "));
+    }
+}
+
+const HTML_STYLE: &str = "\
+body{font-family:system-ui,sans-serif;line-height:1.5;color:#1f2328;background:#fff}\
+main{max-width:60rem;margin:2rem auto;padding:0 1rem}\
+h2{font-size:1rem;border-bottom:1px solid #d0d7de;padding-bottom:.25rem;margin-top:2rem}\
+.text{white-space:pre-wrap}\
+.session,footer{color:#59636e;font-size:.9rem}\
+footer{border-top:1px solid #d0d7de;margin-top:1rem;padding-top:.5rem}\
+details{margin:.5rem 0}\
+summary{cursor:pointer;color:#59636e}\
+.tool{border:1px solid #d0d7de;border-radius:6px;padding:.5rem .75rem;margin:.75rem 0}\
+pre{background:#f6f8fa;padding:.5rem;overflow-x:auto;font-size:.85rem}\
+.error summary{color:#cf222e}\
+.add{color:#116329;background:#dafbe1}\
+.del{color:#82071e;background:#ffebe9}";
+
+struct Html(String);
+
+impl Renderer for Html {
+    fn role(&mut self, role: &str) {
+        let _ = writeln!(self.0, "<h2>{}</h2>", escape(role));
+    }
+
+    fn session(&mut self, details: &[String]) {
+        let _ = writeln!(
+            self.0,
+            "<p class=\"session\">{}</p>",
+            escape(&details.join(" · "))
+        );
+    }
+
+    fn text(&mut self, text: &str) {
+        let _ = writeln!(
+            self.0,
+            "<div class=\"text\">{}</div>",
+            escape(text.trim_end())
+        );
+    }
+
+    fn thinking(&mut self, thinking: &str) {
+        let _ = writeln!(
+            self.0,
+            "<details class=\"thinking\"><summary>Thinking</summary>\
+             <div class=\"text\">{}</div></details>",
+            escape(thinking.trim_end())
+        );
+    }
+
+    fn tool(&mut self, tool: &ToolView<'_>) {
+        let _ = write!(
+            self.0,
+            "<section class=\"tool\"><div><strong>{}</strong>",
+            escape(tool.name)
+        );
+        if let Some(summary) = &tool.summary {
+            let _ = write!(self.0, " <code>{}</code>", escape(summary));
+        }
+        self.0.push_str("</div>");
+        match &tool.diff {
+            Some(diff) => {
+                self.0.push_str("<pre class=\"diff\">");
+                for line in diff.lines() {
+                    let class = match line.as_bytes().first() {
+                        Some(b'+') if !line.starts_with("+++") => "add",
+                        Some(b'-') if !line.starts_with("---") => "del",
+                        _ => "",
+                    };
+                    let _ = writeln!(self.0, "<span class=\"{}\">{}</span>", class, escape(line));
+                }
+                self.0.push_str("</pre>");
+            }
+            None => {
+                let _ = write!(self.0, "<pre>{}</pre>", escape(&pretty(tool.input)));
+            }
+        }
+        if let Some((result, is_error)) = &tool.result {
+            let (class, label) = if *is_error {
+                ("result error", "Error")
+            } else {
+                ("result", "Result")
+            };
+            let _ = write!(
+                self.0,
+                "<details class=\"{}\"><summary>{}</summary><pre>{}</pre></details>",
+                class,
+                label,
+                escape(result.trim_end())
+            );
+        }
+        self.0.push_str("</section>\n");
+    }
+
+    fn footer(&mut self, summary: &[String]) {
+        let _ = writeln!(self.0, "<footer>{}</footer>", escape(&summary.join(" · ")));
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{AssistantMessage, StreamEvent, UserMessage};
+    use serde_json::json;
+
+    fn transcript() -> Vec<Message> {
+        let mut init = HashMap::new();
+        init.insert("session_id".to_string(), json!("sess_1"));
+        init.insert("model".to_string(), json!("claude-sonnet-4-5"));
+        vec![
+            Message::System(SystemMessage::new("init", init)),
+            Message::User(UserMessage::new("Rename foo to bar")),
+            Message::Assistant(AssistantMessage::new(
+                vec![
+                    ContentBlock::thinking("Look at lib.rs first", "sig"),
+                    ContentBlock::tool_use(
+                        "toolu_1",
+                        "Edit",
+                        json!({
+                            "file_path": "src/lib.rs",
+                            "old_string": "fn foo() {}",
+                            "new_string": "fn bar() {}"
+                        }),
+                    ),
+                ],
+                "claude-sonnet-4-5",
+            )),
+            Message::User(UserMessage::new(vec![ContentBlock::tool_result(
+                "toolu_1",
+                Some(json!([{"type": "text", "text": "Edited <lib.rs>"}])),
+                None,
+            )])),
+            Message::Assistant(AssistantMessage::new(
+                vec![ContentBlock::text("Done.")],
+                "claude-sonnet-4-5",
+            )),
+            Message::Result(
+                ResultMessage::new("success", 2500, 2000, false, 2, "sess_1")
+                    .with_cost(0.0123)
+                    .with_usage(json!({"input_tokens": 100, "output_tokens": 20})),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_markdown_rendering() {
+        let markdown = to_markdown(&transcript());
+        assert!(markdown.contains("_session sess_1 · model claude-sonnet-4-5_"));
+        assert!(markdown.contains("## User\n\nRename foo to bar"));
+        assert!(markdown.contains("<summary>Thinking</summary>\n\nLook at lib.rs first"));
+        assert!(markdown.contains("**Tool: Edit** `src/lib.rs`"));
+        assert!(markdown.contains(
+            "```diff\n--- src/lib.rs\n+++ src/lib.rs\n@@\n-fn foo() {}\n+fn bar() {}\n```"
+        ));
+        assert!(markdown.contains("<summary>Result</summary>\n\n```text\nEdited <lib.rs>\n```"));
+        assert!(markdown.contains("Done."));
+        assert!(markdown
+            .contains("**success · 2 turns · 2.5s · $0.0123 · 100 input / 20 output tokens**"));
+        // Both assistant messages share one heading
+        assert_eq!(markdown.matches("## Assistant").count(), 1);
+    }
+
+    #[test]
+    fn test_markdown_fence_outgrows_backticks() {
+        let messages = vec![Message::Assistant(AssistantMessage::new(
+            vec![ContentBlock::tool_use(
+                "toolu_1",
+                "Write",
+                json!({"file_path": "a.md", "content": "```rust\n```"}),
+            )],
+            "m",
+        ))];
+        let markdown = to_markdown(&messages);
+        assert!(markdown.contains("````json\n"));
+    }
+
+    #[test]
+    fn test_html_is_escaped_and_self_contained() {
+        let html = to_html(&transcript());
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<style>"));
+        assert!(!html.contains("<script"));
+        assert!(html.contains("Edited &lt;lib.rs&gt;"));
+        assert!(html.contains("<span class=\"del\">-fn foo() {}</span>"));
+        assert!(html.contains("<span class=\"add\">+fn bar() {}</span>"));
+        assert!(html.contains("<details class=\"thinking\">"));
+        assert!(html.contains("<footer>success · 2 turns"));
+    }
+
+    #[test]
+    fn test_subagent_role_label() {
+        let messages = vec![
+            Message::Assistant(AssistantMessage::new(
+                vec![ContentBlock::tool_use(
+                    "task_1",
+                    TASK_TOOL_NAME,
+                    json!({"subagent_type": "explorer", "description": "look"}),
+                )],
+                "m",
+            )),
+            Message::Assistant(
+                AssistantMessage::new(vec![ContentBlock::text("found it")], "m")
+                    .with_parent_tool_use_id("task_1"),
+            ),
+        ];
+        let markdown = to_markdown(&messages);
+        assert!(markdown.contains("## Assistant (explorer)\n\nfound it"));
+    }
+
+    #[test]
+    fn test_jsonl_round_trip() {
+        let mut messages = transcript();
+        messages.push(Message::StreamEvent(
+            StreamEvent::new("uuid_1", "sess_1", json!({"type": "message_start"}))
+                .with_parent_tool_use_id("toolu_1"),
+        ));
+        let jsonl = to_jsonl(&messages).unwrap();
+        assert_eq!(jsonl.lines().count(), messages.len());
+        assert!(jsonl.lines().all(|line| line.starts_with("{\"type\":")));
+        assert_eq!(from_jsonl(&format!("{}\n\n", jsonl)).unwrap(), messages);
+    }
+
+    #[test]
+    fn test_from_jsonl_reports_line() {
+        let err = from_jsonl("{\"type\":\"user\",\"content\":\"hi\"}\nnot json\n").unwrap_err();
+        assert!(err.to_string().contains("line 2"), "{}", err);
+    }
+}
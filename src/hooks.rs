@@ -0,0 +1,696 @@
+//! Local evaluation of hook matchers.
+//!
+//! [`HookRegistry`] holds the hooks configured on [`ClaudeAgentOptions`] and
+//! applies the same matcher rules as the CLI, so hook wiring can be exercised
+//! in unit tests without spawning Claude Code. The query handler uses the same
+//! registry to build the hooks configuration it sends during initialization.
+
+use serde_json::{json, Value};
+use std::any::Any;
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Duration;
+use tracing::Instrument;
+
+use crate::error::{ClaudeSDKError, Result};
+use crate::internal::telemetry::error_type;
+use crate::types::{
+    AsyncHookResultFn, BaseHookInput, ClaudeAgentOptions, HookCallbackFn, HookContext, HookEvent,
+    HookInput, HookJSONOutput, HookMatcher, PostToolUseHookInput, PreToolUseHookInput,
+};
+
+/// Timeout applied to hooks whose matcher does not set one, in seconds.
+pub const DEFAULT_HOOK_TIMEOUT_SECS: f64 = 60.0;
+
+/// Timeout applied to `can_use_tool` calls when the options do not set one, in
+/// seconds. Longer than the hook default, as the callback may ask a person.
+pub const DEFAULT_PERMISSION_TIMEOUT_SECS: f64 = 300.0;
+
+/// Registry of hook matchers keyed by event.
+#[derive(Debug, Clone, Default)]
+pub struct HookRegistry {
+    hooks: HashMap<HookEvent, Vec<HookMatcher>>,
+    // Regex of each matcher, compiled once, in the same order as `hooks`
+    regexes: HashMap<HookEvent, Vec<Option<regex::Regex>>>,
+}
+
+impl HookRegistry {
+    /// Create a registry from a hooks map.
+    pub fn new(hooks: HashMap<HookEvent, Vec<HookMatcher>>) -> Self {
+        let regexes = hooks
+            .iter()
+            .map(|(event, matchers)| (*event, matchers.iter().map(HookMatcher::compile).collect()))
+            .collect();
+        Self { hooks, regexes }
+    }
+
+    /// Create a registry from the hooks configured on the options.
+    pub fn from_options(options: &ClaudeAgentOptions) -> Self {
+        Self::new(options.hooks.clone())
+    }
+
+    /// Number of registered matchers across all events.
+    pub fn len(&self) -> usize {
+        self.hooks.values().map(Vec::len).sum()
+    }
+
+    /// Check if no hooks are registered.
+    pub fn is_empty(&self) -> bool {
+        self.hooks.values().all(|matchers| matchers.is_empty())
+    }
+
+    /// Get all matchers registered for an event.
+    pub fn matchers(&self, event: HookEvent) -> &[HookMatcher] {
+        self.hooks.get(&event).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Get the matchers for an event that apply to a match value.
+    ///
+    /// See [`HookInput::match_value`] for what the value is for each event.
+    pub fn matching(&self, event: HookEvent, value: Option<&str>) -> Vec<&HookMatcher> {
+        let regexes = self.regexes.get(&event).map(Vec::as_slice).unwrap_or(&[]);
+        self.matchers(event)
+            .iter()
+            .zip(regexes)
+            .filter(|(matcher, regex)| matcher.matches_compiled(value, regex.as_ref()))
+            .map(|(matcher, _)| matcher)
+            .collect()
+    }
+
+    /// Invoke every callback whose matcher applies to the input.
+    ///
+    /// Callbacks run in registration order, each in its own task bounded by
+    /// its matcher's `timeout` (60 seconds by default). A timeout aborts the
+    /// invocation with [`ClaudeSDKError::Timeout`]; a panicking callback with
+    /// [`ClaudeSDKError::ControlProtocol`]. Async matchers yield the immediate
+    /// `{"async": true}` reply, exactly as sent to the CLI.
+    pub async fn invoke(
+        &self,
+        input: HookInput,
+        tool_use_id: Option<String>,
+    ) -> Result<Vec<HookJSONOutput>> {
+        let event = input.event();
+        let value = input.match_value();
+        let mut outputs = Vec::new();
+
+        for matcher in self.matching(event, value.as_deref()) {
+            for hook in &matcher.hooks {
+                let output = RegisteredHook::new(matcher, hook)
+                    .dispatch(input.clone(), tool_use_id.clone())
+                    .await?;
+                outputs.push(output);
+            }
+        }
+
+        Ok(outputs)
+    }
+
+    /// Invoke PreToolUse hooks with a synthetic input for a tool call.
+    pub async fn pre_tool_use(
+        &self,
+        tool_name: impl Into<String>,
+        tool_input: Value,
+    ) -> Result<Vec<HookJSONOutput>> {
+        let input = HookInput::PreToolUse(PreToolUseHookInput {
+            base: synthetic_base(),
+            hook_event_name: HookEvent::PreToolUse.as_str().to_string(),
+            tool_name: tool_name.into(),
+            tool_input,
+        });
+        self.invoke(input, None).await
+    }
+
+    /// Invoke PostToolUse hooks with a synthetic input for a tool result.
+    pub async fn post_tool_use(
+        &self,
+        tool_name: impl Into<String>,
+        tool_input: Value,
+        tool_response: Value,
+    ) -> Result<Vec<HookJSONOutput>> {
+        let input = HookInput::PostToolUse(PostToolUseHookInput {
+            base: synthetic_base(),
+            hook_event_name: HookEvent::PostToolUse.as_str().to_string(),
+            tool_name: tool_name.into(),
+            tool_input,
+            tool_response,
+        });
+        self.invoke(input, None).await
+    }
+
+    /// Build the hooks configuration sent to the CLI on initialization.
+    ///
+    /// `register` is called once per callback with its matcher and returns
+    /// the callback ID the CLI will use to invoke it.
+    pub(crate) fn to_config(
+        &self,
+        mut register: impl FnMut(&HookMatcher, &HookCallbackFn) -> String,
+    ) -> HashMap<String, Value> {
+        let mut hooks_config = HashMap::new();
+
+        for (event, matchers) in &self.hooks {
+            if matchers.is_empty() {
+                continue;
+            }
+
+            let event_matchers: Vec<Value> = matchers
+                .iter()
+                .map(|matcher| {
+                    let callback_ids: Vec<String> = matcher
+                        .hooks
+                        .iter()
+                        .map(|hook| register(matcher, hook))
+                        .collect();
+
+                    let mut matcher_config = json!({
+                        "matcher": matcher.matcher,
+                        "hookCallbackIds": callback_ids,
+                    });
+
+                    if let Some(timeout) = matcher.timeout {
+                        matcher_config["timeout"] = json!(timeout);
+                    }
+
+                    matcher_config
+                })
+                .collect();
+
+            hooks_config.insert(event.as_str().to_string(), json!(event_matchers));
+        }
+
+        hooks_config
+    }
+}
+
+impl From<HashMap<HookEvent, Vec<HookMatcher>>> for HookRegistry {
+    fn from(hooks: HashMap<HookEvent, Vec<HookMatcher>>) -> Self {
+        Self::new(hooks)
+    }
+}
+
+/// A hook callback registered with the CLI, keyed by callback ID.
+#[derive(Clone)]
+pub(crate) struct RegisteredHook {
+    pub callback: HookCallbackFn,
+    pub timeout: Option<f64>,
+    pub is_async: bool,
+    pub async_timeout: Option<u64>,
+    pub on_async_result: Option<AsyncHookResultFn>,
+}
+
+impl RegisteredHook {
+    /// Register a callback with the settings of its matcher.
+    pub fn new(matcher: &HookMatcher, callback: &HookCallbackFn) -> Self {
+        Self {
+            callback: callback.clone(),
+            timeout: matcher.timeout,
+            is_async: matcher.is_async,
+            async_timeout: matcher.async_timeout,
+            on_async_result: matcher.on_async_result.clone(),
+        }
+    }
+
+    /// Run the callback and produce the reply for the CLI.
+    ///
+    /// Async hooks are spawned in the background and answered immediately;
+    /// their result goes to `on_async_result` when they finish.
+    pub async fn dispatch(
+        &self,
+        input: HookInput,
+        tool_use_id: Option<String>,
+    ) -> Result<HookJSONOutput> {
+        if !self.is_async {
+            return run_hook(&self.callback, input, tool_use_id, self.timeout).await;
+        }
+
+        let event = input.event();
+        let timeout = self
+            .async_timeout
+            .map(|ms| ms as f64 / 1000.0)
+            .or(self.timeout);
+        let callback = self.callback.clone();
+        let on_result = self.on_async_result.clone();
+
+        tokio::spawn(
+            async move {
+                let result = run_hook(&callback, input, tool_use_id, timeout).await;
+                match on_result {
+                    Some(on_result) => on_result(event, result),
+                    None => {
+                        if let Err(err) = result {
+                            tracing::warn!(hook_event = %event, error = %err, "Async hook failed");
+                        }
+                    }
+                }
+            }
+            .in_current_span(),
+        );
+
+        let reply_timeout = self
+            .async_timeout
+            .map(|ms| i64::try_from(ms).unwrap_or(i64::MAX));
+        Ok(HookJSONOutput::new().with_async(true, reply_timeout))
+    }
+}
+
+/// Run a hook callback in its own task, bounded by `timeout` seconds
+/// (60 seconds if unset).
+pub(crate) async fn run_hook(
+    callback: &HookCallbackFn,
+    input: HookInput,
+    tool_use_id: Option<String>,
+    timeout: Option<f64>,
+) -> Result<HookJSONOutput> {
+    let event = input.event();
+    let timeout = timeout.unwrap_or(DEFAULT_HOOK_TIMEOUT_SECS);
+    let limit = Duration::try_from_secs_f64(timeout).map_err(|_| {
+        ClaudeSDKError::ControlProtocol(format!(
+            "Invalid {} hook timeout: {} seconds",
+            event, timeout
+        ))
+    })?;
+    let callback = callback.clone();
+    let context = HookContext::new().with_tool_use_id(tool_use_id.clone());
+    let span = tracing::info_span!(
+        "execute_hook",
+        claude.hook.event = %event,
+        gen_ai.tool.call.id = tool_use_id.as_deref(),
+        claude.duration_ms = tracing::field::Empty,
+        error.type = tracing::field::Empty,
+    );
+
+    let started = std::time::Instant::now();
+    let result = run_isolated(
+        format!("{} hook", event),
+        Some(limit),
+        async move { callback(input, tool_use_id, context).await }.instrument(span.clone()),
+    )
+    .instrument(span.clone())
+    .await;
+
+    span.record("claude.duration_ms", started.elapsed().as_millis() as u64);
+    if let Err(ref error) = result {
+        span.record("error.type", error_type(error));
+    }
+    result
+}
+
+/// Run a callback future in its own task so a panic or stall cannot take
+/// down the caller.
+///
+/// Panics become [`ClaudeSDKError::ControlProtocol`] and timeouts
+/// [`ClaudeSDKError::Timeout`]; both are also reported through tracing.
+pub(crate) async fn run_isolated<T, F>(
+    name: String,
+    timeout: Option<Duration>,
+    future: F,
+) -> Result<T>
+where
+    T: Send + 'static,
+    F: Future<Output = T> + Send + 'static,
+{
+    let mut handle = tokio::spawn(future);
+
+    let joined = match timeout {
+        Some(limit) => match tokio::time::timeout(limit, &mut handle).await {
+            Ok(joined) => joined,
+            Err(_) => {
+                handle.abort();
+                tracing::warn!(
+                    callback = %name,
+                    timeout_secs = limit.as_secs_f64(),
+                    "Callback timed out"
+                );
+                return Err(ClaudeSDKError::Timeout(format!(
+                    "{} timed out after {}s",
+                    name,
+                    limit.as_secs_f64()
+                )));
+            }
+        },
+        None => handle.await,
+    };
+
+    joined.map_err(|err| {
+        let reason = if err.is_panic() {
+            panic_message(err.into_panic())
+        } else {
+            "task was cancelled".to_string()
+        };
+        tracing::error!(callback = %name, reason = %reason, "Callback panicked");
+        ClaudeSDKError::ControlProtocol(format!("{} panicked: {}", name, reason))
+    })
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+fn synthetic_base() -> BaseHookInput {
+    BaseHookInput {
+        session_id: "synthetic".to_string(),
+        transcript_path: String::new(),
+        cwd: std::env::current_dir()
+            .map(|dir| dir.display().to_string())
+            .unwrap_or_default(),
+        permission_mode: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{PreToolUseDecision, SessionStartHookInput, SessionStartSource, TypedHook};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn counting_hook(counter: Arc<AtomicUsize>) -> HookCallbackFn {
+        Arc::new(move |_input, _tool_use_id, _context| {
+            let counter = counter.clone();
+            Box::pin(async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                HookJSONOutput::new()
+            })
+        })
+    }
+
+    #[test]
+    fn test_matcher_rules() {
+        let any = HookMatcher::new();
+        assert!(any.matches(Some("Bash")));
+        assert!(any.matches(None));
+        assert!(HookMatcher::new().with_matcher("*").matches(Some("Read")));
+        assert!(HookMatcher::new().with_matcher("").matches(Some("Read")));
+
+        let exact = HookMatcher::new().with_matcher("Bash");
+        assert!(exact.matches(Some("Bash")));
+        assert!(!exact.matches(Some("BashOutput")));
+        assert!(!exact.matches(None));
+
+        let alternatives = HookMatcher::new().with_matcher("Write|Edit|MultiEdit");
+        assert!(alternatives.matches(Some("Edit")));
+        assert!(!alternatives.matches(Some("Read")));
+
+        let regex = HookMatcher::new().with_matcher("mcp__.*__write");
+        assert!(regex.matches(Some("mcp__fs__write")));
+        assert!(!regex.matches(Some("mcp__fs__read")));
+
+        let invalid = HookMatcher::new().with_matcher("mcp__(");
+        assert!(!invalid.matches(Some("mcp__(")));
+    }
+
+    #[test]
+    fn test_matcher_regex_follows_pattern_changes() {
+        let mut matcher = HookMatcher::new().with_matcher("mcp__.*__write");
+        assert!(matcher.matches(Some("mcp__fs__write")));
+        assert!(matcher.matches(Some("mcp__db__write")));
+
+        let before = HookRegistry::new(HashMap::from([(
+            HookEvent::PreToolUse,
+            vec![matcher.clone()],
+        )]));
+
+        matcher.matcher = Some("mcp__.*__read".to_string());
+        assert!(matcher.matches(Some("mcp__fs__read")));
+        assert!(!matcher.matches(Some("mcp__fs__write")));
+
+        let after = HookRegistry::new(HashMap::from([(HookEvent::PreToolUse, vec![matcher])]));
+        assert_eq!(
+            before
+                .matching(HookEvent::PreToolUse, Some("mcp__fs__write"))
+                .len(),
+            1
+        );
+        assert!(before
+            .matching(HookEvent::PreToolUse, Some("mcp__fs__read"))
+            .is_empty());
+        assert_eq!(
+            after
+                .matching(HookEvent::PreToolUse, Some("mcp__fs__read"))
+                .len(),
+            1
+        );
+        assert!(after
+            .matching(HookEvent::PreToolUse, Some("mcp__fs__write"))
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_invoke_only_matching_callbacks() {
+        let bash = Arc::new(AtomicUsize::new(0));
+        let edit = Arc::new(AtomicUsize::new(0));
+        let mut hooks = HashMap::new();
+        hooks.insert(
+            HookEvent::PreToolUse,
+            vec![
+                HookMatcher::new()
+                    .with_matcher("Bash")
+                    .with_hook(counting_hook(bash.clone())),
+                HookMatcher::new()
+                    .with_matcher("Edit|Write")
+                    .with_hook(counting_hook(edit.clone())),
+            ],
+        );
+        let registry = HookRegistry::new(hooks);
+
+        let outputs = registry
+            .pre_tool_use("Bash", json!({"command": "ls"}))
+            .await
+            .unwrap();
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(bash.load(Ordering::SeqCst), 1);
+        assert_eq!(edit.load(Ordering::SeqCst), 0);
+
+        let outputs = registry
+            .post_tool_use("Bash", json!({}), json!({}))
+            .await
+            .unwrap();
+        assert!(outputs.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_invoke_typed_hook() {
+        let options = ClaudeAgentOptions::builder()
+            .hook(TypedHook::pre_tool_use("Bash", |input, _ctx| async move {
+                if input.tool_input["command"] == "rm -rf /" {
+                    PreToolUseDecision::deny("no rm")
+                } else {
+                    PreToolUseDecision::passthrough()
+                }
+            }))
+            .build();
+        let registry = HookRegistry::from_options(&options);
+
+        let outputs = registry
+            .pre_tool_use("Bash", json!({"command": "rm -rf /"}))
+            .await
+            .unwrap();
+        let json = serde_json::to_value(&outputs[0]).unwrap();
+        assert_eq!(json["hookSpecificOutput"]["permissionDecision"], "deny");
+    }
+
+    #[tokio::test]
+    async fn test_invoke_matches_session_start_source() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let registry = HookRegistry::new(HashMap::from([(
+            HookEvent::SessionStart,
+            vec![HookMatcher::new()
+                .with_matcher("resume")
+                .with_hook(counting_hook(counter.clone()))],
+        )]));
+
+        for source in [SessionStartSource::Startup, SessionStartSource::Resume] {
+            let input = HookInput::SessionStart(SessionStartHookInput {
+                base: synthetic_base(),
+                hook_event_name: "SessionStart".to_string(),
+                source,
+            });
+            registry.invoke(input, None).await.unwrap();
+        }
+
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_invoke_matches_unknown_session_start_source() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let registry = HookRegistry::new(HashMap::from([(
+            HookEvent::SessionStart,
+            vec![HookMatcher::new()
+                .with_matcher("^remote$")
+                .with_hook(counting_hook(counter.clone()))],
+        )]));
+
+        let input: HookInput = serde_json::from_value(json!({
+            "session_id": "session-123",
+            "transcript_path": "/path",
+            "cwd": "/home",
+            "hook_event_name": "SessionStart",
+            "source": "remote"
+        }))
+        .unwrap();
+        registry.invoke(input, None).await.unwrap();
+
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_invoke_honors_timeout() {
+        let slow: HookCallbackFn = Arc::new(|_input, _tool_use_id, _context| {
+            Box::pin(async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                HookJSONOutput::new()
+            })
+        });
+        let registry = HookRegistry::new(HashMap::from([(
+            HookEvent::PreToolUse,
+            vec![HookMatcher::new().with_hook(slow).with_timeout(0.05)],
+        )]));
+
+        let result = registry.pre_tool_use("Bash", json!({})).await;
+        assert!(matches!(result, Err(ClaudeSDKError::Timeout(_))));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_hook_timeout_is_an_error() {
+        for timeout in [-1.0, f64::NAN, f64::INFINITY] {
+            let registry = HookRegistry::new(HashMap::from([(
+                HookEvent::PreToolUse,
+                vec![HookMatcher::new()
+                    .with_hook(counting_hook(Arc::new(AtomicUsize::new(0))))
+                    .with_timeout(timeout)],
+            )]));
+
+            let result = registry.pre_tool_use("Bash", json!({})).await;
+            assert!(matches!(result, Err(ClaudeSDKError::ControlProtocol(_))));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_async_hook_replies_immediately() {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let tx = Arc::new(std::sync::Mutex::new(Some(tx)));
+        let slow: HookCallbackFn = Arc::new(|_input, _tool_use_id, _context| {
+            Box::pin(async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                HookJSONOutput::new().with_decision("late")
+            })
+        });
+        let on_result: AsyncHookResultFn = Arc::new(move |event, result| {
+            if let Some(tx) = tx.lock().unwrap().take() {
+                let _ = tx.send((event, result));
+            }
+        });
+        let registry = HookRegistry::new(HashMap::from([(
+            HookEvent::PostToolUse,
+            vec![HookMatcher::new()
+                .with_hook(slow)
+                .with_async(Some(5000))
+                .with_async_result_handler(on_result)],
+        )]));
+
+        let outputs = registry
+            .post_tool_use("Bash", json!({}), json!({}))
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(&outputs[0]).unwrap(),
+            json!({"async": true, "asyncTimeout": 5000})
+        );
+
+        let (event, result) = rx.await.unwrap();
+        assert_eq!(event, HookEvent::PostToolUse);
+        assert_eq!(result.unwrap().decision, Some("late".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_async_hook_with_huge_timeout_still_reports() {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let tx = Arc::new(std::sync::Mutex::new(Some(tx)));
+        let on_result: AsyncHookResultFn = Arc::new(move |_event, result| {
+            if let Some(tx) = tx.lock().unwrap().take() {
+                let _ = tx.send(result.is_ok());
+            }
+        });
+        let registry = HookRegistry::new(HashMap::from([(
+            HookEvent::PostToolUse,
+            vec![HookMatcher::new()
+                .with_hook(counting_hook(Arc::new(AtomicUsize::new(0))))
+                .with_async(Some(u64::MAX))
+                .with_async_result_handler(on_result)],
+        )]));
+
+        let outputs = registry
+            .post_tool_use("Bash", json!({}), json!({}))
+            .await
+            .unwrap();
+        assert_eq!(outputs[0].async_timeout, Some(i64::MAX));
+        assert!(rx.await.unwrap());
+    }
+
+    #[test]
+    fn test_to_config() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let registry = HookRegistry::new(HashMap::from([
+            (
+                HookEvent::PreToolUse,
+                vec![HookMatcher::new()
+                    .with_matcher("Bash")
+                    .with_hook(counting_hook(counter.clone()))
+                    .with_hook(counting_hook(counter))
+                    .with_timeout(30.0)],
+            ),
+            (HookEvent::Stop, vec![]),
+        ]));
+
+        let mut next_id = 0;
+        let config = registry.to_config(|_matcher, _hook| {
+            next_id += 1;
+            format!("hook_{}", next_id)
+        });
+
+        assert_eq!(config.len(), 1);
+        assert_eq!(
+            config["PreToolUse"],
+            json!([{
+                "matcher": "Bash",
+                "hookCallbackIds": ["hook_1", "hook_2"],
+                "timeout": 30.0
+            }])
+        );
+    }
+
+    #[test]
+    fn test_from_options() {
+        let options = ClaudeAgentOptions::builder()
+            .add_hook(HookEvent::Stop, HookMatcher::new())
+            .build();
+        let registry = HookRegistry::from_options(&options);
+
+        assert_eq!(registry.len(), 1);
+        assert_eq!(registry.matchers(HookEvent::Stop).len(), 1);
+        assert!(registry.matchers(HookEvent::PreToolUse).is_empty());
+    }
+
+    #[test]
+    fn test_len_counts_matchers() {
+        let registry = HookRegistry::new(HashMap::from([
+            (
+                HookEvent::Stop,
+                vec![HookMatcher::new(), HookMatcher::new()],
+            ),
+            (HookEvent::PreToolUse, Vec::new()),
+        ]));
+        assert_eq!(registry.len(), 2);
+        assert!(!registry.is_empty());
+
+        let registry = HookRegistry::new(HashMap::from([(HookEvent::Stop, Vec::new())]));
+        assert_eq!(registry.len(), 0);
+        assert!(registry.is_empty());
+    }
+}
@@ -1,7 +1,12 @@
 //! Internal implementation details for Claude SDK.
 
 mod message_parser;
+#[cfg(feature = "tokio")]
 mod query_handler;
+// Sessions are only traced by the tokio driver
+#[cfg_attr(not(feature = "tokio"), allow(dead_code))]
+pub(crate) mod telemetry;
 
 pub use message_parser::parse_message;
+#[cfg(feature = "tokio")]
 pub use query_handler::QueryHandler;
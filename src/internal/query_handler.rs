@@ -1,43 +1,52 @@
-//! Query handler for bidirectional control protocol.
+//! Query handler driving the control protocol over a tokio transport.
 
 #![allow(dead_code)]
 
 use futures::Stream;
 use serde_json::{json, Value};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::{mpsc, oneshot, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Mutex};
+use tracing::Instrument;
 
 use crate::error::{ClaudeSDKError, Result};
-use crate::transport::Transport;
+use crate::hooks::{run_isolated, HookRegistry, RegisteredHook, DEFAULT_PERMISSION_TIMEOUT_SECS};
+use crate::protocol::{Event, Protocol};
+use crate::transport::{InitializeConfig, Transport};
 use crate::types::{
-    CanUseToolFn, ControlResponseVariant, HookCallbackFn, HookContext, HookEvent, HookInput,
-    HookMatcher, Message, PermissionResult, SDKControlRequest, SDKControlRequestVariant,
-    SDKControlResponse, ToolPermissionContext,
+    CanUseToolFn, HookEvent, HookInput, HookMatcher, Message, PermissionResult,
+    SDKControlRequestVariant, ToolPermissionContext,
 };
 
-use super::message_parser::parse_message;
+use super::telemetry::{SessionTracer, SharedTracer};
 
-/// Query handler that manages bidirectional control protocol on top of Transport.
+/// Query handler that drives a [`Protocol`] over a [`Transport`].
 pub struct QueryHandler {
     transport: Box<dyn Transport>,
     is_streaming_mode: bool,
     can_use_tool: Option<CanUseToolFn>,
-    hooks: HashMap<HookEvent, Vec<HookMatcher>>,
+    can_use_tool_timeout: Option<f64>,
+    hooks: HookRegistry,
 
-    // Control protocol state
-    pending_responses: Arc<Mutex<HashMap<String, oneshot::Sender<Result<Value>>>>>,
-    hook_callbacks: Arc<Mutex<HashMap<String, HookCallbackFn>>>,
-    request_counter: AtomicU64,
+    // Control protocol state; control responses queue here until flushed
+    protocol: Protocol,
+    hook_callbacks: Arc<Mutex<HashMap<String, RegisteredHook>>>,
     next_callback_id: AtomicU64,
 
     // Message channel
     message_tx: Option<mpsc::Sender<Result<Message>>>,
     message_rx: Option<mpsc::Receiver<Result<Message>>>,
 
-    // Outgoing response queue for control responses
-    outgoing_responses: Arc<Mutex<Vec<String>>>,
+    // Events read while waiting for a control response
+    buffered: VecDeque<Event>,
+
+    // Tracing spans for the session
+    tracer: SharedTracer,
+
+    // Sent with the initialize request
+    initialize_config: InitializeConfig,
 
     // State
     initialized: bool,
@@ -60,20 +69,45 @@ impl QueryHandler {
             transport,
             is_streaming_mode,
             can_use_tool,
-            hooks,
-            pending_responses: Arc::new(Mutex::new(HashMap::new())),
+            can_use_tool_timeout: None,
+            hooks: HookRegistry::new(hooks),
+            protocol: Protocol::new(),
             hook_callbacks: Arc::new(Mutex::new(HashMap::new())),
-            request_counter: AtomicU64::new(0),
             next_callback_id: AtomicU64::new(0),
             message_tx: Some(message_tx),
             message_rx: Some(message_rx),
-            outgoing_responses: Arc::new(Mutex::new(Vec::new())),
+            buffered: VecDeque::new(),
+            tracer: SessionTracer::shared(&Default::default()),
+            initialize_config: InitializeConfig::default(),
             initialized: false,
             initialization_result: None,
             initialize_timeout_secs,
         }
     }
 
+    /// Record spans with `tracer` instead of a tracer of its own.
+    pub(crate) fn with_tracer(mut self, tracer: SharedTracer) -> Self {
+        self.tracer = tracer;
+        self
+    }
+
+    /// Bound each `can_use_tool` call by `timeout` seconds instead of the default.
+    pub(crate) fn with_can_use_tool_timeout(mut self, timeout: Option<f64>) -> Self {
+        self.can_use_tool_timeout = timeout;
+        self
+    }
+
+    /// Send `config` with the initialize request.
+    pub(crate) fn with_initialize_config(mut self, config: InitializeConfig) -> Self {
+        self.initialize_config = config;
+        self
+    }
+
+    /// Open the tracing span for a new turn.
+    pub fn start_turn(&self) {
+        lock_tracer(&self.tracer).start_turn();
+    }
+
     /// Initialize control protocol if in streaming mode.
     ///
     /// This method sends an initialize request and reads messages directly from
@@ -92,118 +126,37 @@ impl QueryHandler {
             } else {
                 Some(hooks_config)
             },
+            agents: self.initialize_config.agents.clone(),
+            json_schema: self.initialize_config.json_schema.clone(),
         };
 
-        // Generate request ID
-        let request_id = format!(
-            "req_{}_{}",
-            self.request_counter.fetch_add(1, Ordering::SeqCst),
-            rand_hex()
-        );
-
-        // Build and send the control request
-        let control_request = SDKControlRequest::new(request_id.clone(), request);
-        let json_str = serde_json::to_string(&control_request)?;
-        self.transport.write(&format!("{}\n", json_str)).await?;
-
-        // Read messages until we get the control response
-        let timeout = std::time::Duration::from_secs(self.initialize_timeout_secs);
-        let deadline = std::time::Instant::now() + timeout;
-
-        loop {
-            if std::time::Instant::now() > deadline {
-                return Err(ClaudeSDKError::Timeout(format!(
-                    "Initialize request timed out after {} seconds",
-                    self.initialize_timeout_secs
-                )));
-            }
-
-            // Read next message with remaining timeout
-            let remaining = deadline.saturating_duration_since(std::time::Instant::now());
-            let msg_result =
-                tokio::time::timeout(remaining, self.transport.read_next_message()).await;
-
-            match msg_result {
-                Ok(Ok(Some(data))) => {
-                    // Check if this is a control response
-                    if let Some("control_response") = data.get("type").and_then(|v| v.as_str()) {
-                        if let Ok(response) = serde_json::from_value::<SDKControlResponse>(data) {
-                            if response.request_id() == request_id {
-                                match response.response {
-                                    ControlResponseVariant::Success { response, .. } => {
-                                        let result = response.unwrap_or(Value::Null);
-                                        self.initialized = true;
-                                        self.initialization_result = Some(result.clone());
-                                        return Ok(Some(result));
-                                    }
-                                    ControlResponseVariant::Error { error, .. } => {
-                                        return Err(ClaudeSDKError::ControlProtocol(error));
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    // Not a control response for our request - continue reading
-                    // (During init, there shouldn't be other messages, but handle gracefully)
-                }
-                Ok(Ok(None)) => {
-                    // Stream ended
-                    return Err(ClaudeSDKError::ControlProtocol(
-                        "Transport stream ended before initialize response received".to_string(),
-                    ));
-                }
-                Ok(Err(e)) => return Err(e),
-                Err(_) => {
-                    // Timeout
-                    return Err(ClaudeSDKError::Timeout(format!(
-                        "Initialize request timed out after {} seconds",
-                        self.initialize_timeout_secs
-                    )));
-                }
-            }
-        }
+        let control = lock_tracer(&self.tracer).start_control("outgoing", &request);
+        let timeout = Duration::from_secs(self.initialize_timeout_secs);
+        let result = self
+            .exchange_control_request(request, timeout)
+            .instrument(control.span.clone())
+            .await;
+        lock_tracer(&self.tracer).finish_control(&control, &result);
+
+        let result = result?;
+        self.initialized = true;
+        self.initialization_result = Some(result.clone());
+        Ok(Some(result))
     }
 
     /// Build hooks configuration for initialization.
     async fn build_hooks_config(&mut self) -> HashMap<String, Value> {
-        let mut hooks_config = HashMap::new();
-
-        for (event, matchers) in &self.hooks {
-            if matchers.is_empty() {
-                continue;
-            }
-
-            let mut event_matchers = Vec::new();
-
-            for matcher in matchers {
-                let mut callback_ids = Vec::new();
-
-                for hook in &matcher.hooks {
-                    let callback_id = format!(
-                        "hook_{}",
-                        self.next_callback_id.fetch_add(1, Ordering::SeqCst)
-                    );
-                    self.hook_callbacks
-                        .lock()
-                        .await
-                        .insert(callback_id.clone(), hook.clone());
-                    callback_ids.push(callback_id);
-                }
-
-                let mut matcher_config = json!({
-                    "matcher": matcher.matcher,
-                    "hookCallbackIds": callback_ids,
-                });
-
-                if let Some(timeout) = matcher.timeout {
-                    matcher_config["timeout"] = json!(timeout);
-                }
-
-                event_matchers.push(matcher_config);
-            }
+        let mut registered = Vec::new();
+        let hooks_config = self.hooks.to_config(|matcher, hook| {
+            let callback_id = format!(
+                "hook_{}",
+                self.next_callback_id.fetch_add(1, Ordering::SeqCst)
+            );
+            registered.push((callback_id.clone(), RegisteredHook::new(matcher, hook)));
+            callback_id
+        });
 
-            hooks_config.insert(event.as_str().to_string(), json!(event_matchers));
-        }
+        self.hook_callbacks.lock().await.extend(registered);
 
         hooks_config
     }
@@ -215,7 +168,10 @@ impl QueryHandler {
         Ok(())
     }
 
-    /// Send a control request and wait for response.
+    /// Send a control request and read until its response arrives.
+    ///
+    /// Other messages read while waiting are buffered for `receive_messages`,
+    /// and control requests from the CLI are answered immediately.
     async fn send_control_request(
         &mut self,
         request: SDKControlRequestVariant,
@@ -227,37 +183,67 @@ impl QueryHandler {
             ));
         }
 
-        let request_id = format!(
-            "req_{}_{}",
-            self.request_counter.fetch_add(1, Ordering::SeqCst),
-            rand_hex()
-        );
-
-        let (tx, rx) = oneshot::channel();
-        self.pending_responses
-            .lock()
-            .await
-            .insert(request_id.clone(), tx);
-
-        let control_request = SDKControlRequest::new(request_id.clone(), request);
-        let json_str = serde_json::to_string(&control_request)?;
+        let control = lock_tracer(&self.tracer).start_control("outgoing", &request);
+        let result = self
+            .exchange_control_request(request, Duration::from_secs(timeout_secs))
+            .instrument(control.span.clone())
+            .await;
+        lock_tracer(&self.tracer).finish_control(&control, &result);
+        result
+    }
 
-        self.transport.write(&format!("{}\n", json_str)).await?;
+    async fn exchange_control_request(
+        &mut self,
+        request: SDKControlRequestVariant,
+        timeout: Duration,
+    ) -> Result<Value> {
+        let request_id = self
+            .protocol
+            .send_request(request, Some(timeout), Instant::now());
 
-        // Wait for response with timeout
-        let result = tokio::time::timeout(std::time::Duration::from_secs(timeout_secs), rx)
-            .await
-            .map_err(|_| {
-                ClaudeSDKError::Timeout(format!(
-                    "Control request timed out after {} seconds",
-                    timeout_secs
-                ))
-            })?
-            .map_err(|_| {
-                ClaudeSDKError::ControlProtocol("Response channel closed".to_string())
-            })??;
+        loop {
+            // Sends the request, then answers to the CLI's requests
+            self.flush_responses().await?;
+
+            while let Some(event) = self.protocol.poll_event() {
+                match event {
+                    Event::ControlResponse {
+                        request_id: id,
+                        result,
+                    } if id == request_id => return result,
+                    Event::ControlRequest {
+                        request_id: id,
+                        request,
+                    } => {
+                        let result = handle_control_request_traced(
+                            &request,
+                            &self.can_use_tool,
+                            self.can_use_tool_timeout,
+                            &self.hook_callbacks,
+                            &self.tracer,
+                        )
+                        .await;
+                        self.protocol.respond(&id, result)?;
+                    }
+                    other => self.buffered.push_back(other),
+                }
+            }
 
-        Ok(result)
+            let read = self.transport.read_next_message();
+            let read = match self.protocol.next_deadline() {
+                Some(deadline) => tokio::time::timeout_at(deadline.into(), read).await,
+                None => Ok(read.await),
+            };
+            match read {
+                Err(_) => self.protocol.handle_timeout(Instant::now()),
+                Ok(Ok(Some(data))) => self.protocol.receive_value(data),
+                Ok(Ok(None)) => self.protocol.receive_eof(),
+                Ok(Err(e @ ClaudeSDKError::MessageParse { .. })) => {
+                    tracing::warn!("Skipping malformed message: {}", e);
+                }
+                Ok(Err(e)) => return Err(e),
+            }
+        }
     }
 
     /// Handle an incoming control request from CLI.
@@ -266,102 +252,14 @@ impl QueryHandler {
         _request_id: String,
         request: SDKControlRequestVariant,
     ) -> Result<Value> {
-        match request {
-            SDKControlRequestVariant::CanUseTool {
-                tool_name,
-                input,
-                permission_suggestions,
-                ..
-            } => {
-                let can_use_tool = self.can_use_tool.as_ref().ok_or_else(|| {
-                    ClaudeSDKError::ControlProtocol(
-                        "canUseTool callback is not provided".to_string(),
-                    )
-                })?;
-
-                let _suggestions = permission_suggestions.unwrap_or_default();
-                let context = ToolPermissionContext {
-                    signal: None,
-                    suggestions: Vec::new(), // TODO: Parse suggestions
-                };
-
-                let original_input = input.clone();
-                let result = can_use_tool(tool_name.clone(), input, context).await;
-
-                let response = match result {
-                    PermissionResult::Allow(allow) => {
-                        let mut resp = json!({
-                            "behavior": "allow",
-                            "updatedInput": allow.updated_input.unwrap_or(original_input),
-                        });
-                        if let Some(permissions) = allow.updated_permissions {
-                            let perm_dicts: Vec<_> = permissions
-                                .iter()
-                                .map(|p| serde_json::to_value(p.to_dict()).unwrap_or_default())
-                                .collect();
-                            resp["updatedPermissions"] = json!(perm_dicts);
-                        }
-                        resp
-                    }
-                    PermissionResult::Deny(deny) => {
-                        let mut resp = json!({
-                            "behavior": "deny",
-                            "message": deny.message,
-                        });
-                        if deny.interrupt {
-                            resp["interrupt"] = json!(true);
-                        }
-                        resp
-                    }
-                };
-
-                Ok(response)
-            }
-
-            SDKControlRequestVariant::HookCallback {
-                callback_id,
-                input,
-                tool_use_id,
-            } => {
-                let callbacks = self.hook_callbacks.lock().await;
-                let callback = callbacks.get(&callback_id).ok_or_else(|| {
-                    ClaudeSDKError::ControlProtocol(format!(
-                        "No hook callback found for ID: {}",
-                        callback_id
-                    ))
-                })?;
-
-                // Parse input into HookInput
-                let hook_input: HookInput = serde_json::from_value(input)?;
-                let context = HookContext { signal: None };
-
-                let output = callback.clone()(hook_input, tool_use_id, context).await;
-
-                // Convert to CLI format (async_ -> async, continue_ -> continue)
-                let output_value = serde_json::to_value(&output)?;
-                Ok(output_value)
-            }
-
-            SDKControlRequestVariant::McpMessage {
-                server_name,
-                message,
-            } => {
-                // TODO: Implement MCP server routing
-                Ok(json!({
-                    "jsonrpc": "2.0",
-                    "id": message.get("id"),
-                    "error": {
-                        "code": -32601,
-                        "message": format!("Server '{}' not found", server_name)
-                    }
-                }))
-            }
-
-            _ => Err(ClaudeSDKError::ControlProtocol(format!(
-                "Unsupported control request: {:?}",
-                request
-            ))),
-        }
+        handle_control_request_traced(
+            &request,
+            &self.can_use_tool,
+            self.can_use_tool_timeout,
+            &self.hook_callbacks,
+            &self.tracer,
+        )
+        .await
     }
 
     /// Send interrupt signal.
@@ -423,99 +321,87 @@ impl QueryHandler {
     /// This should be called periodically to send queued control responses
     /// back to the CLI.
     pub async fn flush_responses(&mut self) -> Result<()> {
-        let responses: Vec<String> = {
-            let mut guard = self.outgoing_responses.lock().await;
-            std::mem::take(&mut *guard)
-        };
-
-        for response in responses {
-            self.transport.write(&response).await?;
+        while let Some(line) = self.protocol.poll_transmit() {
+            self.transport.write(&line).await?;
         }
-
         Ok(())
     }
 
     /// Receive messages from the transport.
     ///
     /// This method handles bidirectional control protocol:
-    /// - Routes incoming control_response messages to pending requests
+    /// - Drops control responses, which only matter while a request waits
     /// - Handles incoming control_request messages by invoking callbacks and queuing responses
     /// - Yields regular SDK messages to the caller
     ///
-    /// Note: Control responses are queued and must be flushed with `flush_responses()`.
+    /// Queued control responses are written before each read, so the CLI is
+    /// never left waiting on a permission decision. Responses queued after
+    /// the last message the caller consumed go out with `flush_responses()`.
     pub fn receive_messages(&mut self) -> impl Stream<Item = Result<Message>> + '_ {
-        let pending_responses = self.pending_responses.clone();
-        let can_use_tool = self.can_use_tool.clone();
-        let hook_callbacks = self.hook_callbacks.clone();
-        let outgoing_responses = self.outgoing_responses.clone();
-
-        async_stream::try_stream! {
-            let mut stream = self.transport.read_messages();
-
-            while let Some(result) = futures::StreamExt::next(&mut stream).await {
-                let data: Value = result?;
-
-                let msg_type = data.get("type").and_then(|v| v.as_str());
-
-                match msg_type {
-                    Some("control_response") => {
-                        // Route control response to pending request
-                        if let Ok(response) = serde_json::from_value::<SDKControlResponse>(data.clone()) {
-                            let request_id = response.request_id().to_string();
-                            let mut pending = pending_responses.lock().await;
-
-                            if let Some(tx) = pending.remove(&request_id) {
-                                let result = match response.response {
-                                    ControlResponseVariant::Success { response, .. } => {
-                                        Ok(response.unwrap_or(Value::Null))
-                                    }
-                                    ControlResponseVariant::Error { error, .. } => {
-                                        Err(ClaudeSDKError::ControlProtocol(error))
-                                    }
-                                };
-                                let _ = tx.send(result);
+        async_stream::stream! {
+            loop {
+                // Pop one event at a time so a stream dropped after its first
+                // item leaves the rest buffered for the next one
+                while let Some(event) = self.buffered.front() {
+                    if let Event::ControlRequest { request, .. } = event {
+                        // Process the control request; the response is queued.
+                        // It stays buffered until answered, so a stream dropped
+                        // while the callback runs handles it again next time.
+                        let result = handle_control_request_traced(
+                            request,
+                            &self.can_use_tool,
+                            self.can_use_tool_timeout,
+                            &self.hook_callbacks,
+                            &self.tracer,
+                        ).await;
+                        if let Some(Event::ControlRequest { request_id, .. }) = self.buffered.pop_front() {
+                            if let Err(e) = self.protocol.respond(&request_id, result) {
+                                tracing::warn!("Failed to encode control response: {}", e);
                             }
                         }
                         continue;
                     }
-
-                    Some("control_request") => {
-                        // Handle incoming control request from CLI
-                        if let Ok(request) = serde_json::from_value::<SDKControlRequest>(data.clone()) {
-                            let request_id = request.request_id.clone();
-
-                            // Process the control request
-                            let response_result = handle_control_request_static(
-                                &request.request,
-                                &can_use_tool,
-                                &hook_callbacks,
-                            ).await;
-
-                            // Build the control response
-                            let control_response = match response_result {
-                                Ok(response_data) => SDKControlResponse::success(&request_id, Some(response_data)),
-                                Err(e) => SDKControlResponse::error(&request_id, e.to_string()),
-                            };
-
-                            // Queue response to be sent (will be flushed later)
-                            if let Ok(response_json) = serde_json::to_string(&control_response) {
-                                outgoing_responses.lock().await.push(format!("{}\n", response_json));
-                            }
+                    match self.buffered.pop_front() {
+                        None | Some(Event::ControlRequest { .. }) => {}
+                        Some(Event::Message(message)) => {
+                            lock_tracer(&self.tracer).observe(&message);
+                            yield Ok(message);
                         }
-                        continue;
-                    }
-
-                    Some("control_cancel_request") => {
-                        // Handle control cancel request - currently just acknowledge
                         // TODO: Implement proper cancellation support if needed
-                        continue;
+                        Some(Event::ControlResponse { .. } | Event::ControlCancel { .. }) => {}
+                        Some(Event::Error(e)) => {
+                            yield Err(e);
+                            return;
+                        }
                     }
+                }
+
+                if let Err(e) = self.flush_responses().await {
+                    yield Err(e);
+                    return;
+                }
 
-                    _ => {
-                        // Regular SDK message
-                        let message = parse_message(data)?;
-                        yield message;
+                // Read one line at a time; the transport is needed for writes in between
+                let next = {
+                    let mut stream = self.transport.read_messages();
+                    futures::StreamExt::next(&mut stream).await
+                };
+                match next {
+                    Some(Ok(data)) => {
+                        self.protocol.receive_value(data);
+                        while let Some(event) = self.protocol.poll_event() {
+                            self.buffered.push_back(event);
+                        }
+                    }
+                    Some(Err(e)) => {
+                        // Malformed lines are reported and skipped
+                        let recoverable = matches!(e, ClaudeSDKError::MessageParse { .. });
+                        yield Err(e);
+                        if !recoverable {
+                            return;
+                        }
                     }
+                    None => return,
                 }
             }
         }
@@ -526,17 +412,53 @@ impl QueryHandler {
         self.transport.close().await
     }
 
+    /// Check if the transport can still send and receive.
+    pub fn is_ready(&self) -> bool {
+        self.transport.is_ready()
+    }
+
     /// Get initialization result.
     pub fn initialization_result(&self) -> Option<&Value> {
         self.initialization_result.as_ref()
     }
 }
 
+/// Handle a control request inside a span recording its latency and, for
+/// permission requests, the decision.
+async fn handle_control_request_traced(
+    request: &SDKControlRequestVariant,
+    can_use_tool: &Option<CanUseToolFn>,
+    can_use_tool_timeout: Option<f64>,
+    hook_callbacks: &Arc<Mutex<HashMap<String, RegisteredHook>>>,
+    tracer: &SharedTracer,
+) -> Result<Value> {
+    let control = lock_tracer(tracer).start_control("incoming", request);
+    let result =
+        handle_control_request_static(request, can_use_tool, can_use_tool_timeout, hook_callbacks)
+            .instrument(control.span.clone())
+            .await;
+
+    let mut tracer = lock_tracer(tracer);
+    tracer.finish_control(&control, &result);
+    if let Ok(ref response) = result {
+        tracer.record_decision(&control, response);
+    }
+    result
+}
+
+/// Lock the tracer, recovering from a panic in another holder.
+fn lock_tracer(tracer: &SharedTracer) -> std::sync::MutexGuard<'_, SessionTracer> {
+    tracer
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
 /// Handle a control request (static version for use in async closures).
 async fn handle_control_request_static(
     request: &SDKControlRequestVariant,
     can_use_tool: &Option<CanUseToolFn>,
-    hook_callbacks: &Arc<Mutex<HashMap<String, HookCallbackFn>>>,
+    can_use_tool_timeout: Option<f64>,
+    hook_callbacks: &Arc<Mutex<HashMap<String, RegisteredHook>>>,
 ) -> Result<Value> {
     match request {
         SDKControlRequestVariant::CanUseTool {
@@ -554,8 +476,23 @@ async fn handle_control_request_static(
                 suggestions: Vec::new(),
             };
 
+            let timeout = can_use_tool_timeout.unwrap_or(DEFAULT_PERMISSION_TIMEOUT_SECS);
+            let limit = Duration::try_from_secs_f64(timeout).map_err(|_| {
+                ClaudeSDKError::ControlProtocol(format!(
+                    "Invalid canUseTool timeout: {} seconds",
+                    timeout
+                ))
+            })?;
+
             let original_input = input.clone();
-            let result = can_use_tool(tool_name.clone(), input.clone(), context).await;
+            let callback = can_use_tool.clone();
+            let (name, input) = (tool_name.clone(), input.clone());
+            let result = run_isolated(
+                format!("canUseTool callback for {}", tool_name),
+                Some(limit),
+                async move { callback(name, input, context).await },
+            )
+            .await?;
 
             let response = match result {
                 PermissionResult::Allow(allow) => {
@@ -592,18 +529,20 @@ async fn handle_control_request_static(
             input,
             tool_use_id,
         } => {
-            let callbacks = hook_callbacks.lock().await;
-            let callback = callbacks.get(callback_id).ok_or_else(|| {
-                ClaudeSDKError::ControlProtocol(format!(
-                    "No hook callback found for ID: {}",
-                    callback_id
-                ))
-            })?;
+            let hook = hook_callbacks
+                .lock()
+                .await
+                .get(callback_id)
+                .cloned()
+                .ok_or_else(|| {
+                    ClaudeSDKError::ControlProtocol(format!(
+                        "No hook callback found for ID: {}",
+                        callback_id
+                    ))
+                })?;
 
             let hook_input: HookInput = serde_json::from_value(input.clone())?;
-            let context = HookContext { signal: None };
-
-            let output = callback.clone()(hook_input, tool_use_id.clone(), context).await;
+            let output = hook.dispatch(hook_input, tool_use_id.clone()).await?;
             let output_value = serde_json::to_value(&output)?;
             Ok(output_value)
         }
@@ -627,15 +566,6 @@ async fn handle_control_request_static(
     }
 }
 
-/// Generate a random hex string.
-fn rand_hex() -> String {
-    use std::time::{SystemTime, UNIX_EPOCH};
-    let duration = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default();
-    format!("{:x}", duration.subsec_nanos())
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -650,6 +580,8 @@ mod tests {
         messages: Arc<Mutex<Vec<Value>>>,
         written: Arc<Mutex<Vec<String>>>,
         connected: Arc<AtomicBool>,
+        /// Answer each written control request with a success response
+        respond_to_control: bool,
     }
 
     impl MockTransport {
@@ -658,6 +590,14 @@ mod tests {
                 messages: Arc::new(Mutex::new(messages)),
                 written: Arc::new(Mutex::new(vec![])),
                 connected: Arc::new(AtomicBool::new(false)),
+                respond_to_control: false,
+            }
+        }
+
+        fn responding(messages: Vec<Value>) -> Self {
+            Self {
+                respond_to_control: true,
+                ..Self::new(messages)
             }
         }
 
@@ -675,6 +615,19 @@ mod tests {
 
         async fn write(&mut self, data: &str) -> Result<()> {
             self.written.lock().await.push(data.to_string());
+            if self.respond_to_control {
+                let sent: Value = serde_json::from_str(data.trim())?;
+                if sent["type"] == "control_request" {
+                    self.messages.lock().await.push(json!({
+                        "type": "control_response",
+                        "response": {
+                            "subtype": "success",
+                            "request_id": sent["request_id"],
+                            "response": {"ok": true}
+                        }
+                    }));
+                }
+            }
             Ok(())
         }
 
@@ -682,10 +635,25 @@ mod tests {
             &mut self,
         ) -> Pin<Box<dyn futures::Stream<Item = Result<Value>> + Send + '_>> {
             let messages = self.messages.clone();
-            Box::pin(async_stream::try_stream! {
-                let mut guard = messages.lock().await;
-                for msg in std::mem::take(&mut *guard) {
-                    yield msg;
+            Box::pin(async_stream::stream! {
+                loop {
+                    let msg = {
+                        let mut guard = messages.lock().await;
+                        if guard.is_empty() {
+                            break;
+                        }
+                        guard.remove(0)
+                    };
+                    // A bare string stands in for a malformed stdout line
+                    match msg {
+                        Value::String(line) => {
+                            yield Err(ClaudeSDKError::message_parse(
+                                "Invalid JSON from CLI stdout",
+                                Some(Value::String(line)),
+                            ))
+                        }
+                        msg => yield Ok(msg),
+                    }
                 }
             })
         }
@@ -713,24 +681,6 @@ mod tests {
         }
     }
 
-    #[test]
-    fn test_rand_hex() {
-        let hex1 = rand_hex();
-        let _hex2 = rand_hex();
-        // Should be non-empty
-        assert!(!hex1.is_empty());
-        // Might be different (depends on timing)
-    }
-
-    #[test]
-    fn test_rand_hex_is_valid_hex() {
-        let hex = rand_hex();
-        // Should only contain hex characters
-        for c in hex.chars() {
-            assert!(c.is_ascii_hexdigit());
-        }
-    }
-
     #[tokio::test]
     async fn test_query_handler_creation() {
         let transport = Box::new(MockTransport::empty());
@@ -820,6 +770,234 @@ mod tests {
         assert!(received[0].is_assistant());
     }
 
+    #[tokio::test]
+    async fn test_query_handler_receive_messages_skips_malformed_line() {
+        let messages = vec![
+            json!("{\"type\": \"assist"),
+            json!({
+                "type": "assistant",
+                "message": {
+                    "id": "msg_1",
+                    "role": "assistant",
+                    "content": [{"type": "text", "text": "Hello"}],
+                    "model": "claude-3-5-sonnet",
+                    "stop_reason": "end_turn"
+                }
+            }),
+        ];
+
+        let transport = Box::new(MockTransport::new(messages));
+        let mut handler = QueryHandler::new(transport, true, None, HashMap::new(), 60);
+
+        let stream = handler.receive_messages();
+        tokio::pin!(stream);
+
+        let first = futures::StreamExt::next(&mut stream).await.unwrap();
+        match first {
+            Err(ClaudeSDKError::MessageParse { data, .. }) => {
+                assert_eq!(data, Some(json!("{\"type\": \"assist")));
+            }
+            other => panic!("expected parse error, got {:?}", other),
+        }
+
+        let second = futures::StreamExt::next(&mut stream).await.unwrap();
+        assert!(second.unwrap().is_assistant());
+        assert!(futures::StreamExt::next(&mut stream).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_receive_messages_answers_permission_before_next_read() {
+        let can_use_tool: CanUseToolFn = Arc::new(|_tool_name, _input, _context| {
+            Box::pin(async move { PermissionResult::allow() })
+        });
+        let messages = vec![
+            json!({
+                "type": "control_request",
+                "request_id": "cli-1",
+                "request": {"subtype": "can_use_tool", "tool_name": "Bash", "input": {}}
+            }),
+            json!({
+                "type": "assistant",
+                "message": {
+                    "role": "assistant",
+                    "content": [{"type": "text", "text": "Done"}],
+                    "model": "claude-sonnet-4-5"
+                }
+            }),
+        ];
+
+        let mock = MockTransport::new(messages);
+        let written = mock.written.clone();
+        let mut handler =
+            QueryHandler::new(Box::new(mock), true, Some(can_use_tool), HashMap::new(), 60);
+
+        // The CLI waits for the decision, so it must be written before the
+        // stream reads on, without the caller flushing
+        let stream = handler.receive_messages();
+        tokio::pin!(stream);
+        let first = futures::StreamExt::next(&mut stream).await.unwrap();
+        assert!(first.unwrap().is_assistant());
+
+        let written = written.lock().await;
+        assert_eq!(written.len(), 1);
+        assert!(written[0].contains("cli-1"));
+        assert!(written[0].contains("\"behavior\":\"allow\""));
+    }
+
+    #[tokio::test]
+    async fn test_control_request_survives_dropped_stream() {
+        // The first call never finishes; later calls allow
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = calls.clone();
+        let can_use_tool: CanUseToolFn = Arc::new(move |_tool_name, _input, _context| {
+            let first = counter.fetch_add(1, Ordering::SeqCst) == 0;
+            Box::pin(async move {
+                if first {
+                    std::future::pending::<()>().await;
+                }
+                PermissionResult::allow()
+            })
+        });
+        let messages = vec![
+            json!({
+                "type": "control_request",
+                "request_id": "cli-1",
+                "request": {"subtype": "can_use_tool", "tool_name": "Bash", "input": {}}
+            }),
+            json!({
+                "type": "assistant",
+                "message": {
+                    "role": "assistant",
+                    "content": [{"type": "text", "text": "Done"}],
+                    "model": "claude-sonnet-4-5"
+                }
+            }),
+        ];
+
+        let mock = MockTransport::new(messages);
+        let written = mock.written.clone();
+        let mut handler =
+            QueryHandler::new(Box::new(mock), true, Some(can_use_tool), HashMap::new(), 60);
+
+        {
+            let stream = handler.receive_messages();
+            tokio::pin!(stream);
+            let next = futures::StreamExt::next(&mut stream);
+            assert!(tokio::time::timeout(Duration::from_millis(50), next)
+                .await
+                .is_err());
+        }
+
+        // The request dropped mid-callback is still answered by the next stream
+        let stream = handler.receive_messages();
+        tokio::pin!(stream);
+        let first = futures::StreamExt::next(&mut stream).await.unwrap();
+        assert!(first.unwrap().is_assistant());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        let written = written.lock().await;
+        assert_eq!(written.len(), 1);
+        assert!(written[0].contains("cli-1"));
+        assert!(written[0].contains("\"behavior\":\"allow\""));
+    }
+
+    #[tokio::test]
+    async fn test_hung_permission_callback_times_out() {
+        let can_use_tool: CanUseToolFn = Arc::new(|_tool_name, _input, _context| {
+            Box::pin(std::future::pending::<PermissionResult>())
+        });
+        let messages = vec![
+            json!({
+                "type": "control_request",
+                "request_id": "cli-1",
+                "request": {"subtype": "can_use_tool", "tool_name": "Bash", "input": {}}
+            }),
+            json!({
+                "type": "assistant",
+                "message": {
+                    "role": "assistant",
+                    "content": [{"type": "text", "text": "Done"}],
+                    "model": "claude-sonnet-4-5"
+                }
+            }),
+        ];
+
+        let mock = MockTransport::new(messages);
+        let written = mock.written.clone();
+        let mut handler =
+            QueryHandler::new(Box::new(mock), true, Some(can_use_tool), HashMap::new(), 60)
+                .with_can_use_tool_timeout(Some(0.01));
+
+        let stream = handler.receive_messages();
+        tokio::pin!(stream);
+        let first = futures::StreamExt::next(&mut stream).await.unwrap();
+        assert!(first.unwrap().is_assistant());
+
+        // The CLI gets an error instead of waiting on the callback forever
+        let written = written.lock().await;
+        assert_eq!(written.len(), 1);
+        assert!(written[0].contains("cli-1"));
+        assert!(written[0].contains("\"subtype\":\"error\""));
+        assert!(written[0].contains("timed out"));
+    }
+
+    #[tokio::test]
+    async fn test_control_request_reads_response_and_buffers_messages() {
+        let messages = vec![
+            json!({
+                "type": "assistant",
+                "message": {
+                    "id": "msg_1",
+                    "role": "assistant",
+                    "content": [{"type": "text", "text": "Hello"}],
+                    "model": "claude-3-5-sonnet",
+                    "stop_reason": "end_turn"
+                }
+            }),
+            json!({
+                "type": "control_request",
+                "request_id": "cli-1",
+                "request": {"subtype": "can_use_tool", "tool_name": "Bash", "input": {}}
+            }),
+        ];
+
+        let mock = MockTransport::responding(messages);
+        let written = mock.written.clone();
+        let mut handler = QueryHandler::new(Box::new(mock), true, None, HashMap::new(), 60);
+
+        let status = handler.get_mcp_status().await.unwrap();
+        assert_eq!(status, json!({"ok": true}));
+
+        // The CLI's permission request was answered while waiting
+        let written = written.lock().await;
+        assert_eq!(written.len(), 2);
+        assert!(written[1].contains("cli-1"));
+        drop(written);
+
+        let stream = handler.receive_messages();
+        tokio::pin!(stream);
+        let first = futures::StreamExt::next(&mut stream).await.unwrap();
+        assert!(first.unwrap().is_assistant());
+        assert!(futures::StreamExt::next(&mut stream).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_control_request_fails_when_stream_ends() {
+        let mut handler = QueryHandler::new(
+            Box::new(MockTransport::empty()),
+            true,
+            None,
+            HashMap::new(),
+            60,
+        );
+        match handler.set_model(Some("claude-haiku".to_string())).await {
+            Err(ClaudeSDKError::ControlProtocol(message)) => {
+                assert!(message.contains("ended before control response"))
+            }
+            other => panic!("expected protocol error, got {:?}", other),
+        }
+    }
+
     #[tokio::test]
     async fn test_query_handler_receive_control_response() {
         // Test that control responses are properly routed
@@ -860,6 +1038,48 @@ mod tests {
         assert!(received[0].is_assistant());
     }
 
+    #[tokio::test]
+    async fn test_receive_messages_traces_tool_permission() {
+        use crate::internal::telemetry::capture::capture;
+
+        let (captured, _guard) = capture();
+        let can_use_tool: CanUseToolFn = Arc::new(|_tool_name, _input, _context| {
+            Box::pin(async move { PermissionResult::deny() })
+        });
+        let messages = vec![
+            json!({
+                "type": "assistant",
+                "message": {
+                    "role": "assistant",
+                    "content": [{"type": "tool_use", "id": "toolu_1", "name": "Bash", "input": {}}],
+                    "model": "claude-sonnet-4-5"
+                }
+            }),
+            json!({
+                "type": "control_request",
+                "request_id": "req-1",
+                "request": {"subtype": "can_use_tool", "tool_name": "Bash", "input": {}}
+            }),
+        ];
+
+        let transport = Box::new(MockTransport::new(messages));
+        let mut handler =
+            QueryHandler::new(transport, true, Some(can_use_tool), HashMap::new(), 60);
+        handler.start_turn();
+        let stream = handler.receive_messages();
+        tokio::pin!(stream);
+        while futures::StreamExt::next(&mut stream).await.is_some() {}
+
+        let control = &captured.named("control_request")[0];
+        assert_eq!(control.parent, Some("chat"));
+        assert_eq!(control.fields["claude.control.subtype"], "can_use_tool");
+        assert_eq!(control.fields["claude.permission.decision"], "deny");
+        assert!(control.fields.contains_key("claude.control.latency_ms"));
+
+        let tool = &captured.named("execute_tool")[0];
+        assert_eq!(tool.fields["claude.permission.decision"], "deny");
+    }
+
     #[tokio::test]
     async fn test_query_handler_receive_multiple_message_types() {
         let messages = vec![
@@ -920,9 +1140,13 @@ mod tests {
             blocked_path: None,
         };
 
-        let result =
-            handle_control_request_static(&request, &None, &Arc::new(Mutex::new(HashMap::new())))
-                .await;
+        let result = handle_control_request_static(
+            &request,
+            &None,
+            None,
+            &Arc::new(Mutex::new(HashMap::new())),
+        )
+        .await;
 
         // Should fail because no callback is provided
         assert!(result.is_err());
@@ -935,9 +1159,13 @@ mod tests {
             message: json!({"jsonrpc": "2.0", "method": "tools/list", "id": 1}),
         };
 
-        let result =
-            handle_control_request_static(&request, &None, &Arc::new(Mutex::new(HashMap::new())))
-                .await;
+        let result = handle_control_request_static(
+            &request,
+            &None,
+            None,
+            &Arc::new(Mutex::new(HashMap::new())),
+        )
+        .await;
 
         assert!(result.is_ok());
         let response = result.unwrap();
@@ -958,9 +1186,13 @@ mod tests {
             tool_use_id: None,
         };
 
-        let result =
-            handle_control_request_static(&request, &None, &Arc::new(Mutex::new(HashMap::new())))
-                .await;
+        let result = handle_control_request_static(
+            &request,
+            &None,
+            None,
+            &Arc::new(Mutex::new(HashMap::new())),
+        )
+        .await;
 
         // Should fail because callback is not registered
         assert!(result.is_err());
@@ -1006,6 +1238,7 @@ mod tests {
         let result = handle_control_request_static(
             &request,
             &Some(can_use_tool),
+            None,
             &Arc::new(Mutex::new(HashMap::new())),
         )
         .await;
@@ -1039,6 +1272,7 @@ mod tests {
         let result = handle_control_request_static(
             &request,
             &Some(can_use_tool),
+            None,
             &Arc::new(Mutex::new(HashMap::new())),
         )
         .await;
@@ -1054,9 +1288,13 @@ mod tests {
     async fn test_handle_control_request_static_unsupported() {
         let request = SDKControlRequestVariant::Interrupt;
 
-        let result =
-            handle_control_request_static(&request, &None, &Arc::new(Mutex::new(HashMap::new())))
-                .await;
+        let result = handle_control_request_static(
+            &request,
+            &None,
+            None,
+            &Arc::new(Mutex::new(HashMap::new())),
+        )
+        .await;
 
         assert!(result.is_err());
         assert!(result
@@ -1073,12 +1311,11 @@ mod tests {
 
         let mut handler = QueryHandler::new(transport, true, None, HashMap::new(), 60);
 
-        // Manually add a response to the queue
+        // Queue a response without sending it
         handler
-            .outgoing_responses
-            .lock()
-            .await
-            .push("{\"test\":true}\n".to_string());
+            .protocol
+            .respond("req_1", Ok(json!({"test": true})))
+            .unwrap();
 
         let result = handler.flush_responses().await;
         assert!(result.is_ok());
@@ -1131,20 +1368,6 @@ mod tests {
         assert!(handler.initialization_result().is_none());
     }
 
-    #[test]
-    fn test_rand_hex_produces_different_values() {
-        // Call multiple times to ensure it works
-        let results: Vec<String> = (0..10).map(|_| rand_hex()).collect();
-
-        // All should be valid hex
-        for hex in &results {
-            assert!(!hex.is_empty());
-            for c in hex.chars() {
-                assert!(c.is_ascii_hexdigit());
-            }
-        }
-    }
-
     #[tokio::test]
     async fn test_query_handler_with_can_use_tool() {
         use std::sync::Arc;
@@ -1195,6 +1418,7 @@ mod tests {
         let result = handle_control_request_static(
             &request,
             &Some(can_use_tool),
+            None,
             &Arc::new(Mutex::new(HashMap::new())),
         )
         .await;
@@ -1233,6 +1457,7 @@ mod tests {
         let result = handle_control_request_static(
             &request,
             &Some(can_use_tool),
+            None,
             &Arc::new(Mutex::new(HashMap::new())),
         )
         .await;
@@ -1242,4 +1467,147 @@ mod tests {
         assert_eq!(response["behavior"], "allow");
         assert!(response.get("updatedPermissions").is_some());
     }
+
+    #[tokio::test]
+    async fn test_build_hooks_config_registers_new_events() {
+        use crate::types::{HookCallbackFn, HookEvent, HookJSONOutput, HookMatcher};
+
+        let hook: HookCallbackFn =
+            Arc::new(|_input, _tool_use_id, _context| Box::pin(async { HookJSONOutput::new() }));
+
+        let mut hooks = HashMap::new();
+        hooks.insert(
+            HookEvent::SessionStart,
+            vec![HookMatcher::new().with_hook(hook.clone())],
+        );
+        hooks.insert(
+            HookEvent::PermissionRequest,
+            vec![HookMatcher::new().with_matcher("Bash").with_hook(hook)],
+        );
+
+        let transport = Box::new(MockTransport::empty());
+        let mut handler = QueryHandler::new(transport, true, None, hooks, 60);
+
+        let config = handler.build_hooks_config().await;
+        assert!(config.contains_key("SessionStart"));
+        assert_eq!(config["PermissionRequest"][0]["matcher"], "Bash");
+        assert_eq!(handler.hook_callbacks.lock().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_can_use_tool_panic_becomes_error() {
+        let can_use_tool: crate::types::CanUseToolFn =
+            Arc::new(|_tool_name, _input, _context| panic!("callback exploded"));
+
+        let request = SDKControlRequestVariant::CanUseTool {
+            tool_name: "Bash".to_string(),
+            input: json!({"command": "ls"}),
+            permission_suggestions: None,
+            blocked_path: None,
+        };
+
+        let result = handle_control_request_static(
+            &request,
+            &Some(can_use_tool),
+            None,
+            &Arc::new(Mutex::new(HashMap::new())),
+        )
+        .await;
+
+        let err = result.unwrap_err();
+        assert!(matches!(err, ClaudeSDKError::ControlProtocol(_)));
+        assert!(err.to_string().contains("callback exploded"));
+    }
+
+    #[tokio::test]
+    async fn test_hook_callback_timeout_becomes_error() {
+        use crate::types::HookJSONOutput;
+
+        let slow: crate::types::HookCallbackFn = Arc::new(|_input, _tool_use_id, _context| {
+            Box::pin(async {
+                tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                HookJSONOutput::new()
+            })
+        });
+        let callbacks = Arc::new(Mutex::new(HashMap::from([(
+            "hook_0".to_string(),
+            RegisteredHook::new(&HookMatcher::new().with_timeout(0.05), &slow),
+        )])));
+
+        let request = SDKControlRequestVariant::HookCallback {
+            callback_id: "hook_0".to_string(),
+            input: json!({
+                "session_id": "s",
+                "transcript_path": "/t",
+                "cwd": "/",
+                "hook_event_name": "Stop",
+                "stop_hook_active": false
+            }),
+            tool_use_id: None,
+        };
+
+        let result = handle_control_request_static(&request, &None, None, &callbacks).await;
+        assert!(matches!(result, Err(ClaudeSDKError::Timeout(_))));
+    }
+
+    #[tokio::test]
+    async fn test_receive_messages_survives_panicking_hook() {
+        use crate::types::{HookEvent, HookMatcher};
+
+        let messages = vec![
+            json!({
+                "type": "control_request",
+                "request_id": "req_1",
+                "request": {
+                    "subtype": "hook_callback",
+                    "callback_id": "hook_0",
+                    "input": {
+                        "session_id": "s",
+                        "transcript_path": "/t",
+                        "cwd": "/",
+                        "hook_event_name": "Stop",
+                        "stop_hook_active": false
+                    }
+                }
+            }),
+            json!({
+                "type": "assistant",
+                "message": {
+                    "id": "msg_1",
+                    "role": "assistant",
+                    "content": [{"type": "text", "text": "Still here"}],
+                    "model": "claude-3-5-sonnet",
+                    "stop_reason": "end_turn"
+                }
+            }),
+        ];
+
+        let mut hooks = HashMap::new();
+        hooks.insert(
+            HookEvent::Stop,
+            vec![
+                HookMatcher::new().with_hook(Arc::new(|_input, _tool_use_id, _context| {
+                    Box::pin(async { panic!("hook exploded") })
+                })),
+            ],
+        );
+
+        let mock = MockTransport::new(messages);
+        let written = mock.written.clone();
+        let mut handler = QueryHandler::new(Box::new(mock), true, None, hooks, 60);
+        handler.build_hooks_config().await;
+
+        let received: Vec<_> = {
+            let stream = handler.receive_messages();
+            futures::pin_mut!(stream);
+            futures::StreamExt::collect::<Vec<_>>(stream).await
+        };
+        assert_eq!(received.len(), 1);
+        assert!(received[0].is_ok());
+
+        let written = written.lock().await;
+        assert_eq!(written.len(), 1);
+        assert!(written[0].contains("\"subtype\":\"error\""));
+        assert!(written[0].contains("hook exploded"));
+    }
 }
@@ -0,0 +1,707 @@
+//! Tracing spans and metrics for sessions, turns, tool uses and control
+//! requests.
+//!
+//! Field names follow the OpenTelemetry GenAI semantic conventions
+//! (`gen_ai.*`) where one exists; Claude Code specific fields use the
+//! `claude.*` prefix. Metrics are only recorded with the `metrics` feature.
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::{field::Empty, Span};
+
+use crate::error::{ClaudeSDKError, Result};
+use crate::types::{
+    ClaudeAgentOptions, ContentBlock, Message, ResultMessage, SDKControlRequestVariant,
+    UserMessageContent,
+};
+
+/// Provider reported as `gen_ai.provider.name`.
+const PROVIDER: &str = "anthropic";
+
+/// Metric names recorded by the SDK.
+pub mod names {
+    /// Completed turns. Counter.
+    pub const TURNS: &str = "claude.turns";
+    /// Tokens used, labelled by `type`: `input`, `output`, `cache_read` or
+    /// `cache_creation`. Counter.
+    pub const TOKENS: &str = "claude.tokens";
+    /// Cost of each turn in USD. Histogram.
+    pub const COST_USD: &str = "claude.cost_usd";
+    /// Tool uses, labelled by `tool`. Counter.
+    pub const TOOL_CALLS: &str = "claude.tool.calls";
+    /// Time from a tool use to its result, labelled by `tool`. Histogram.
+    pub const TOOL_DURATION_MS: &str = "claude.tool.duration_ms";
+    /// Permission decisions, labelled by `tool` and `decision`. Counter.
+    pub const PERMISSION_DECISIONS: &str = "claude.permission.decisions";
+    /// Hook callback latency, labelled by `event`. Histogram.
+    pub const HOOK_DURATION_MS: &str = "claude.hook.duration_ms";
+    /// Control request latency, labelled by `subtype`. Histogram.
+    pub const CONTROL_LATENCY_MS: &str = "claude.control.latency_ms";
+    /// Control requests that failed, labelled by `subtype`. Counter.
+    pub const CONTROL_ERRORS: &str = "claude.control.errors";
+    /// Control requests that timed out, labelled by `subtype`. Counter.
+    pub const CONTROL_TIMEOUTS: &str = "claude.control.timeouts";
+    /// CLI processes started to replace an earlier one. Counter.
+    pub const CLI_RESTARTS: &str = "claude.cli.restarts";
+}
+
+/// Forwards metrics to the sink configured on the options, if any.
+#[derive(Clone, Default)]
+pub(crate) struct Recorder {
+    #[cfg(feature = "metrics")]
+    sink: Option<Arc<dyn crate::metrics::MetricsSink>>,
+}
+
+impl std::fmt::Debug for Recorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        #[cfg(feature = "metrics")]
+        let enabled = self.sink.is_some();
+        #[cfg(not(feature = "metrics"))]
+        let enabled = false;
+        f.debug_struct("Recorder")
+            .field("enabled", &enabled)
+            .finish()
+    }
+}
+
+impl Recorder {
+    pub(crate) fn from_options(options: &ClaudeAgentOptions) -> Self {
+        #[cfg(feature = "metrics")]
+        return Self {
+            sink: options.metrics_sink.clone(),
+        };
+        #[cfg(not(feature = "metrics"))]
+        {
+            let _ = options;
+            Self {}
+        }
+    }
+
+    pub(crate) fn counter(&self, name: &str, labels: &[(&str, &str)], value: u64) {
+        #[cfg(feature = "metrics")]
+        if let Some(ref sink) = self.sink {
+            sink.increment_counter(name, labels, value);
+        }
+        #[cfg(not(feature = "metrics"))]
+        let _ = (name, labels, value);
+    }
+
+    pub(crate) fn histogram(&self, name: &str, labels: &[(&str, &str)], value: f64) {
+        #[cfg(feature = "metrics")]
+        if let Some(ref sink) = self.sink {
+            sink.record_histogram(name, labels, value);
+        }
+        #[cfg(not(feature = "metrics"))]
+        let _ = (name, labels, value);
+    }
+}
+
+/// A [`SessionTracer`] shared between a client and its query handler.
+pub(crate) type SharedTracer = Arc<Mutex<SessionTracer>>;
+
+/// Tracks the spans and metrics of one CLI session.
+///
+/// The session span lives as long as the tracer. Turn spans are opened by
+/// [`start_turn`](Self::start_turn) and closed by the turn's result message;
+/// tool spans are opened by a `tool_use` block and closed by its result.
+#[derive(Debug)]
+pub(crate) struct SessionTracer {
+    session: Span,
+    turn: Option<Span>,
+    tools: HashMap<String, ToolSpan>,
+    metrics: Recorder,
+}
+
+/// An in-flight control request.
+#[derive(Debug)]
+pub(crate) struct ControlSpan {
+    pub(crate) span: Span,
+    subtype: String,
+    tool_name: Option<String>,
+    hook_event: Option<String>,
+    started: Instant,
+}
+
+#[derive(Debug)]
+struct ToolSpan {
+    span: Span,
+    name: String,
+    started: Instant,
+    decided: bool,
+}
+
+impl SessionTracer {
+    pub(crate) fn new(options: &ClaudeAgentOptions) -> Self {
+        let session = tracing::info_span!(
+            "invoke_agent",
+            otel.kind = "client",
+            gen_ai.operation.name = "invoke_agent",
+            gen_ai.provider.name = PROVIDER,
+            gen_ai.request.model = options.model.as_deref(),
+            gen_ai.response.model = Empty,
+            gen_ai.conversation.id = Empty,
+            claude.permission_mode = options.permission_mode.map(|m| m.as_str()),
+        );
+        Self {
+            session,
+            turn: None,
+            tools: HashMap::new(),
+            metrics: Recorder::from_options(options),
+        }
+    }
+
+    pub(crate) fn shared(options: &ClaudeAgentOptions) -> SharedTracer {
+        Arc::new(Mutex::new(Self::new(options)))
+    }
+
+    /// Span that control requests and hooks are nested under.
+    pub(crate) fn current_span(&self) -> &Span {
+        self.turn.as_ref().unwrap_or(&self.session)
+    }
+
+    /// Open the span for a new turn, closing any unfinished one.
+    pub(crate) fn start_turn(&mut self) {
+        self.turn = Some(tracing::info_span!(
+            parent: &self.session,
+            "chat",
+            otel.kind = "client",
+            gen_ai.operation.name = "chat",
+            gen_ai.provider.name = PROVIDER,
+            gen_ai.response.model = Empty,
+            gen_ai.usage.input_tokens = Empty,
+            gen_ai.usage.output_tokens = Empty,
+            claude.num_turns = Empty,
+            claude.cost_usd = Empty,
+            claude.duration_ms = Empty,
+            claude.is_error = Empty,
+        ));
+    }
+
+    /// Record what a message reveals about the session.
+    pub(crate) fn observe(&mut self, message: &Message) {
+        match message {
+            Message::System(system) if system.subtype == "init" => {
+                if let Some(id) = system.data.get("session_id").and_then(Value::as_str) {
+                    self.session.record("gen_ai.conversation.id", id);
+                }
+                if let Some(model) = system.data.get("model").and_then(Value::as_str) {
+                    self.session.record("gen_ai.response.model", model);
+                }
+            }
+            Message::Assistant(assistant) => {
+                if let Some(ref turn) = self.turn {
+                    turn.record("gen_ai.response.model", assistant.model.as_str());
+                }
+                for block in &assistant.content {
+                    if let ContentBlock::ToolUse { id, name, .. } = block {
+                        self.start_tool(id, name);
+                    }
+                }
+            }
+            Message::User(user) => {
+                if let UserMessageContent::Blocks(ref blocks) = user.content {
+                    for block in blocks {
+                        if let ContentBlock::ToolResult {
+                            tool_use_id,
+                            is_error,
+                            ..
+                        } = block
+                        {
+                            self.finish_tool(tool_use_id, is_error.unwrap_or(false));
+                        }
+                    }
+                }
+            }
+            Message::Result(result) => self.finish_turn(result),
+            _ => {}
+        }
+    }
+
+    /// Open a span for a control request sent to or received from the CLI.
+    pub(crate) fn start_control(
+        &self,
+        direction: &'static str,
+        request: &SDKControlRequestVariant,
+    ) -> ControlSpan {
+        let subtype = control_subtype(request);
+        let span = tracing::info_span!(
+            parent: self.current_span(),
+            "control_request",
+            claude.control.subtype = subtype.as_str(),
+            claude.control.direction = direction,
+            claude.control.latency_ms = Empty,
+            claude.permission.decision = Empty,
+            gen_ai.tool.name = Empty,
+            error.type = Empty,
+        );
+
+        let mut tool_name = None;
+        let mut hook_event = None;
+        match request {
+            SDKControlRequestVariant::CanUseTool {
+                tool_name: name, ..
+            } => {
+                span.record("gen_ai.tool.name", name.as_str());
+                tool_name = Some(name.clone());
+            }
+            SDKControlRequestVariant::HookCallback { input, .. } => {
+                hook_event = input
+                    .get("hook_event_name")
+                    .and_then(Value::as_str)
+                    .map(str::to_string);
+            }
+            _ => {}
+        }
+
+        ControlSpan {
+            span,
+            subtype,
+            tool_name,
+            hook_event,
+            started: Instant::now(),
+        }
+    }
+
+    /// Record the latency and outcome of a finished control request.
+    pub(crate) fn finish_control<T>(&mut self, control: &ControlSpan, result: &Result<T>) {
+        let latency = control.started.elapsed();
+        let latency_ms = latency.as_secs_f64() * 1000.0;
+        let labels = [("subtype", control.subtype.as_str())];
+
+        control
+            .span
+            .record("claude.control.latency_ms", latency.as_millis() as u64);
+        self.metrics
+            .histogram(names::CONTROL_LATENCY_MS, &labels, latency_ms);
+        if let Some(ref event) = control.hook_event {
+            self.metrics
+                .histogram(names::HOOK_DURATION_MS, &[("event", event)], latency_ms);
+        }
+
+        if let Err(ref error) = result {
+            control.span.record("error.type", error_type(error));
+            self.metrics.counter(names::CONTROL_ERRORS, &labels, 1);
+            if matches!(error, ClaudeSDKError::Timeout(_)) {
+                self.metrics.counter(names::CONTROL_TIMEOUTS, &labels, 1);
+            }
+        }
+    }
+
+    /// Record the decision returned for a permission request.
+    pub(crate) fn record_decision(&mut self, control: &ControlSpan, response: &Value) {
+        let (Some(tool_name), Some(decision)) = (
+            control.tool_name.as_deref(),
+            response.get("behavior").and_then(Value::as_str),
+        ) else {
+            return;
+        };
+        control.span.record("claude.permission.decision", decision);
+        self.metrics.counter(
+            names::PERMISSION_DECISIONS,
+            &[("tool", tool_name), ("decision", decision)],
+            1,
+        );
+        self.record_permission(tool_name, decision);
+    }
+
+    /// Record a permission decision on the oldest undecided use of `tool_name`.
+    ///
+    /// Permission requests do not carry the tool use id, so they are matched
+    /// to open tool spans by name.
+    fn record_permission(&mut self, tool_name: &str, decision: &str) {
+        let pending = self
+            .tools
+            .values_mut()
+            .filter(|tool| tool.name == tool_name && !tool.decided)
+            .min_by_key(|tool| tool.started);
+        if let Some(tool) = pending {
+            tool.span.record("claude.permission.decision", decision);
+            tool.decided = true;
+        }
+    }
+
+    fn start_tool(&mut self, id: &str, name: &str) {
+        self.metrics
+            .counter(names::TOOL_CALLS, &[("tool", name)], 1);
+        let span = tracing::info_span!(
+            parent: self.current_span(),
+            "execute_tool",
+            otel.kind = "internal",
+            gen_ai.operation.name = "execute_tool",
+            gen_ai.tool.name = name,
+            gen_ai.tool.call.id = id,
+            claude.permission.decision = Empty,
+            claude.duration_ms = Empty,
+            claude.is_error = Empty,
+        );
+        self.tools.insert(
+            id.to_string(),
+            ToolSpan {
+                span,
+                name: name.to_string(),
+                started: Instant::now(),
+                decided: false,
+            },
+        );
+    }
+
+    fn finish_tool(&mut self, id: &str, is_error: bool) {
+        if let Some(tool) = self.tools.remove(id) {
+            let elapsed = tool.started.elapsed();
+            tool.span
+                .record("claude.duration_ms", elapsed.as_millis() as u64);
+            tool.span.record("claude.is_error", is_error);
+            self.metrics.histogram(
+                names::TOOL_DURATION_MS,
+                &[("tool", &tool.name)],
+                elapsed.as_secs_f64() * 1000.0,
+            );
+        }
+    }
+
+    fn finish_turn(&mut self, result: &ResultMessage) {
+        self.session
+            .record("gen_ai.conversation.id", result.session_id.as_str());
+
+        self.metrics.counter(names::TURNS, &[], 1);
+        if let Some(cost) = result.total_cost_usd {
+            self.metrics.histogram(names::COST_USD, &[], cost);
+        }
+        let usage = |key: &str| {
+            result
+                .usage
+                .as_ref()
+                .and_then(|usage| usage.get(key))
+                .and_then(Value::as_u64)
+        };
+        for (kind, key) in [
+            ("input", "input_tokens"),
+            ("output", "output_tokens"),
+            ("cache_read", "cache_read_input_tokens"),
+            ("cache_creation", "cache_creation_input_tokens"),
+        ] {
+            if let Some(tokens) = usage(key) {
+                self.metrics
+                    .counter(names::TOKENS, &[("type", kind)], tokens);
+            }
+        }
+
+        // Tools left open by an interrupted turn end with it
+        self.tools.clear();
+        let Some(turn) = self.turn.take() else {
+            return;
+        };
+        turn.record("claude.num_turns", result.num_turns);
+        turn.record("claude.duration_ms", result.duration_ms);
+        turn.record("claude.is_error", result.is_error);
+        if let Some(cost) = result.total_cost_usd {
+            turn.record("claude.cost_usd", cost);
+        }
+        if let Some(tokens) = usage("input_tokens") {
+            turn.record("gen_ai.usage.input_tokens", tokens);
+        }
+        if let Some(tokens) = usage("output_tokens") {
+            turn.record("gen_ai.usage.output_tokens", tokens);
+        }
+    }
+}
+
+/// Variant name of an error, e.g. `Timeout`.
+pub(crate) fn error_type(error: &ClaudeSDKError) -> String {
+    let debug = format!("{:?}", error);
+    debug
+        .split(|c: char| !c.is_alphanumeric())
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Wire name of a control request, e.g. `can_use_tool`.
+fn control_subtype(request: &SDKControlRequestVariant) -> String {
+    serde_json::to_value(request)
+        .ok()
+        .and_then(|value| value.get("subtype")?.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+pub(crate) mod capture {
+    //! A subscriber layer that records span fields for assertions.
+
+    use std::collections::HashMap;
+    use std::fmt::Debug;
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::Subscriber;
+    use tracing_subscriber::layer::{Context, SubscriberExt};
+    use tracing_subscriber::registry::LookupSpan;
+    use tracing_subscriber::Layer;
+
+    /// A recorded span: its name, fields and parent name.
+    #[derive(Debug, Clone, Default)]
+    pub(crate) struct CapturedSpan {
+        pub name: &'static str,
+        pub parent: Option<&'static str>,
+        pub fields: HashMap<String, String>,
+    }
+
+    #[derive(Clone, Default)]
+    pub(crate) struct Captured(Arc<Mutex<Vec<CapturedSpan>>>);
+
+    impl Captured {
+        pub(crate) fn named(&self, name: &str) -> Vec<CapturedSpan> {
+            let spans = self.0.lock().unwrap();
+            spans.iter().filter(|s| s.name == name).cloned().collect()
+        }
+    }
+
+    struct Fields<'a>(&'a mut HashMap<String, String>);
+
+    impl Visit for Fields<'_> {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.insert(field.name().to_string(), value.to_string());
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.0
+                .insert(field.name().to_string(), format!("{:?}", value));
+        }
+    }
+
+    struct Index(usize);
+
+    struct CaptureLayer(Captured);
+
+    impl<S> Layer<S> for CaptureLayer
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+            let span = ctx.span(id).expect("span exists");
+            let mut captured = CapturedSpan {
+                name: attrs.metadata().name(),
+                parent: span.parent().map(|p| p.name()),
+                fields: HashMap::new(),
+            };
+            attrs.record(&mut Fields(&mut captured.fields));
+
+            let mut spans = (self.0).0.lock().unwrap();
+            span.extensions_mut().insert(Index(spans.len()));
+            spans.push(captured);
+        }
+
+        fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+            let span = ctx.span(id).expect("span exists");
+            let extensions = span.extensions();
+            if let Some(Index(index)) = extensions.get::<Index>() {
+                let mut spans = (self.0).0.lock().unwrap();
+                values.record(&mut Fields(&mut spans[*index].fields));
+            }
+        }
+    }
+
+    /// Capture spans created while the returned guard is alive.
+    pub(crate) fn capture() -> (Captured, tracing::subscriber::DefaultGuard) {
+        let captured = Captured::default();
+        let subscriber = tracing_subscriber::registry().with(CaptureLayer(captured.clone()));
+        let guard = tracing::subscriber::set_default(subscriber);
+        (captured, guard)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::capture::capture;
+    use super::*;
+    use crate::types::{AssistantMessage, SystemMessage, UserMessage};
+    use serde_json::json;
+
+    #[test]
+    fn test_session_and_turn_spans() {
+        let (captured, _guard) = capture();
+        let options = ClaudeAgentOptions::builder()
+            .model("claude-sonnet-4-5")
+            .build();
+        let mut tracer = SessionTracer::new(&options);
+
+        let mut data = HashMap::new();
+        data.insert("session_id".to_string(), json!("sess-1"));
+        tracer.observe(&Message::System(SystemMessage::new("init", data)));
+        tracer.start_turn();
+        tracer.observe(&Message::Result(
+            ResultMessage::new("success", 1200, 1000, false, 2, "sess-1")
+                .with_cost(0.05)
+                .with_usage(json!({"input_tokens": 100, "output_tokens": 20})),
+        ));
+
+        let session = &captured.named("invoke_agent")[0];
+        assert_eq!(session.fields["gen_ai.provider.name"], "anthropic");
+        assert_eq!(session.fields["gen_ai.request.model"], "claude-sonnet-4-5");
+        assert_eq!(session.fields["gen_ai.conversation.id"], "sess-1");
+
+        let turn = &captured.named("chat")[0];
+        assert_eq!(turn.parent, Some("invoke_agent"));
+        assert_eq!(turn.fields["gen_ai.usage.input_tokens"], "100");
+        assert_eq!(turn.fields["gen_ai.usage.output_tokens"], "20");
+        assert_eq!(turn.fields["claude.num_turns"], "2");
+        assert_eq!(turn.fields["claude.cost_usd"], "0.05");
+        assert!(tracer.turn.is_none());
+    }
+
+    #[test]
+    fn test_tool_span_closed_by_tool_result() {
+        let (captured, _guard) = capture();
+        let mut tracer = SessionTracer::new(&ClaudeAgentOptions::default());
+        tracer.start_turn();
+
+        tracer.observe(&Message::Assistant(AssistantMessage::new(
+            vec![ContentBlock::tool_use(
+                "toolu_1",
+                "Bash",
+                json!({"command": "ls"}),
+            )],
+            "claude-sonnet-4-5",
+        )));
+        tracer.record_permission("Bash", "allow");
+        tracer.observe(&Message::User(UserMessage::new(vec![
+            ContentBlock::ToolResult {
+                tool_use_id: "toolu_1".to_string(),
+                content: None,
+                is_error: Some(true),
+            },
+        ])));
+
+        let tool = &captured.named("execute_tool")[0];
+        assert_eq!(tool.parent, Some("chat"));
+        assert_eq!(tool.fields["gen_ai.tool.name"], "Bash");
+        assert_eq!(tool.fields["gen_ai.tool.call.id"], "toolu_1");
+        assert_eq!(tool.fields["claude.permission.decision"], "allow");
+        assert_eq!(tool.fields["claude.is_error"], "true");
+        assert!(tool.fields.contains_key("claude.duration_ms"));
+        assert!(tracer.tools.is_empty());
+    }
+
+    #[test]
+    fn test_control_request_span_fields() {
+        let (captured, _guard) = capture();
+        let request = SDKControlRequestVariant::CanUseTool {
+            tool_name: "Write".to_string(),
+            input: json!({}),
+            permission_suggestions: None,
+            blocked_path: None,
+        };
+        let mut tracer = SessionTracer::new(&ClaudeAgentOptions::default());
+        let control = tracer.start_control("incoming", &request);
+        tracer.finish_control(&control, &Ok(()));
+        tracer.record_decision(&control, &json!({"behavior": "deny"}));
+
+        let span = &captured.named("control_request")[0];
+        assert_eq!(span.parent, Some("invoke_agent"));
+        assert_eq!(span.fields["claude.control.subtype"], "can_use_tool");
+        assert_eq!(span.fields["gen_ai.tool.name"], "Write");
+        assert_eq!(span.fields["claude.permission.decision"], "deny");
+        assert!(span.fields.contains_key("claude.control.latency_ms"));
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn test_metrics_recorded_into_sink() {
+        use crate::metrics::InMemoryMetrics;
+
+        let metrics = Arc::new(InMemoryMetrics::new());
+        let options = ClaudeAgentOptions::builder()
+            .metrics_sink(metrics.clone())
+            .build();
+        let mut tracer = SessionTracer::new(&options);
+        tracer.start_turn();
+
+        tracer.observe(&Message::Assistant(AssistantMessage::new(
+            vec![ContentBlock::tool_use("toolu_1", "Bash", json!({}))],
+            "claude-sonnet-4-5",
+        )));
+        let permission = SDKControlRequestVariant::CanUseTool {
+            tool_name: "Bash".to_string(),
+            input: json!({}),
+            permission_suggestions: None,
+            blocked_path: None,
+        };
+        let control = tracer.start_control("incoming", &permission);
+        tracer.finish_control(&control, &Ok(()));
+        tracer.record_decision(&control, &json!({"behavior": "allow"}));
+
+        let hook = SDKControlRequestVariant::HookCallback {
+            callback_id: "hook_0".to_string(),
+            input: json!({"hook_event_name": "PreToolUse"}),
+            tool_use_id: None,
+        };
+        let control = tracer.start_control("incoming", &hook);
+        tracer.finish_control::<()>(
+            &control,
+            &Err(ClaudeSDKError::Timeout("hook timed out".into())),
+        );
+
+        tracer.observe(&Message::User(UserMessage::new(vec![
+            ContentBlock::ToolResult {
+                tool_use_id: "toolu_1".to_string(),
+                content: None,
+                is_error: None,
+            },
+        ])));
+        tracer.observe(&Message::Result(
+            ResultMessage::new("success", 10, 10, false, 1, "sess-1")
+                .with_cost(0.25)
+                .with_usage(json!({
+                    "input_tokens": 100,
+                    "output_tokens": 20,
+                    "cache_read_input_tokens": 50
+                })),
+        ));
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.counter(names::TURNS, &[]), 1);
+        assert_eq!(snapshot.counter(names::TOKENS, &[("type", "input")]), 100);
+        assert_eq!(
+            snapshot.counter(names::TOKENS, &[("type", "cache_read")]),
+            50
+        );
+        assert_eq!(snapshot.counter_total(names::TOKENS), 170);
+        assert_eq!(snapshot.histogram(names::COST_USD, &[]).unwrap().sum, 0.25);
+        assert_eq!(snapshot.counter(names::TOOL_CALLS, &[("tool", "Bash")]), 1);
+        assert_eq!(
+            snapshot
+                .histogram(names::TOOL_DURATION_MS, &[("tool", "Bash")])
+                .unwrap()
+                .count,
+            1
+        );
+        assert_eq!(
+            snapshot.counter(
+                names::PERMISSION_DECISIONS,
+                &[("tool", "Bash"), ("decision", "allow")]
+            ),
+            1
+        );
+        assert!(snapshot
+            .histogram(names::HOOK_DURATION_MS, &[("event", "PreToolUse")])
+            .is_some());
+        assert_eq!(
+            snapshot.counter(names::CONTROL_TIMEOUTS, &[("subtype", "hook_callback")]),
+            1
+        );
+    }
+
+    #[test]
+    fn test_error_type_is_variant_name() {
+        assert_eq!(
+            error_type(&ClaudeSDKError::Timeout("slow".into())),
+            "Timeout"
+        );
+        assert_eq!(
+            error_type(&ClaudeSDKError::ControlProtocol("bad".into())),
+            "ControlProtocol"
+        );
+    }
+}
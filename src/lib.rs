@@ -81,45 +81,97 @@
 //! - **Control protocol**: Interrupt, change permissions, and more during conversations
 //! - **Type safety**: Strongly typed messages and configurations
 //! - **Async/await**: Built on tokio for efficient async operations
+//! - **Runtime-agnostic core**: The [`protocol`] state machine does no I/O
+//!   and works with any executor
+//!
+//! ## Feature Flags
+//!
+//! - `tokio` (default): The subprocess transport, [`ClaudeSDKClient`],
+//!   `query()`, hooks, pools and batches. Without it, the crate provides the
+//!   types, [`protocol`], [`conversation`], [`export`] and [`subagent`].
+//! - `blocking`: Synchronous wrappers in `blocking`
+//! - `cli`: The `claude-repl` interactive chat binary
+//! - `metrics`: Metrics sinks in `metrics`
+//! - `schemars`: `query_typed()` for structured output
+//! - `toml`: TOML files in [`AgentConfig`]
 //!
 //! ## Module Organization
 //!
 //! - [`error`]: Error types and result aliases
 //! - [`types`]: All type definitions (messages, configurations, etc.)
+//! - [`protocol`]: Sans-IO state machine for the CLI protocol
 //! - [`transport`]: Transport layer for CLI communication
 //! - [`query`]: One-shot query function
 //! - [`client`]: Interactive client for conversations
+//! - [`pool`]: Pool of pre-connected clients
+//! - [`batch`]: Concurrent batch queries with rate limiting and budgets
+//! - [`conversation`]: Turns and tool calls assembled from a message stream
+//! - [`subagent`]: Splitting a message stream into per-subagent streams
+//! - [`export`]: Markdown, HTML and JSONL renderings of a conversation
+//! - `blocking`: Synchronous query and client (requires the `blocking` feature)
+//! - `metrics`: Pluggable metrics sinks (requires the `metrics` feature)
 
 #![allow(missing_docs)]
 #![warn(clippy::all)]
 
+#[cfg(feature = "tokio")]
+pub mod batch;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+#[cfg(feature = "tokio")]
 pub mod client;
+pub mod conversation;
 pub mod error;
+pub mod export;
+#[cfg(feature = "tokio")]
+pub mod hooks;
 pub(crate) mod internal;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(feature = "tokio")]
+pub mod pool;
+pub mod protocol;
+#[cfg(feature = "tokio")]
 pub mod query;
+pub mod retry;
+pub mod subagent;
+#[cfg(all(test, unix, feature = "tokio"))]
+mod test_support;
+pub mod tools;
 pub mod transport;
 pub mod types;
 
 // Re-export main types at crate root for convenience
+#[cfg(feature = "tokio")]
 pub use client::ClaudeSDKClient;
 pub use error::{ClaudeSDKError, Result};
+#[cfg(feature = "tokio")]
+pub use hooks::HookRegistry;
+#[cfg(feature = "tokio")]
 pub use query::query;
+#[cfg(all(feature = "tokio", feature = "schemars"))]
+pub use query::query_typed;
+pub use retry::{RetryAttempt, RetryPolicy};
 pub use types::{
     // Config
+    AgentConfig,
     AgentDefinition,
     // Messages
     AssistantMessage,
     AssistantMessageError,
+    AsyncHookResultFn,
     CanUseToolFn,
     ClaudeAgentOptions,
     ClaudeAgentOptionsBuilder,
     // Content
     ContentBlock,
+    ContextDecision,
     // Control
     ControlResponseVariant,
     // Hooks
     HookCallbackFn,
     HookContext,
+    HookDecision,
     HookEvent,
     HookInput,
     HookJSONOutput,
@@ -137,6 +189,7 @@ pub use types::{
     // Permissions
     PermissionBehavior,
     PermissionMode,
+    PermissionRequestDecision,
     PermissionResult,
     PermissionResultAllow,
     PermissionResultDeny,
@@ -144,6 +197,9 @@ pub use types::{
     PermissionUpdate,
     PermissionUpdateDestination,
     PermissionUpdateType,
+    PostToolUseDecision,
+    PostToolUseFailureDecision,
+    PreToolUseDecision,
     ResultMessage,
     SDKControlRequest,
     SDKControlRequestVariant,
@@ -156,6 +212,7 @@ pub use types::{
     SdkPluginConfig,
     SettingSource,
     StderrCallbackFn,
+    StopDecision,
     StreamEvent,
     SystemMessage,
     SystemPrompt,
@@ -167,8 +224,10 @@ pub use types::{
     ToolUseBlock,
     Tools,
     ToolsPreset,
+    TypedHook,
     UserMessage,
     UserMessageContent,
+    UserPromptSubmitDecision,
 };
 
 // Re-export transport trait
@@ -0,0 +1,243 @@
+//! Metrics for sessions, tools and the control protocol.
+//!
+//! Set a [`MetricsSink`] with
+//! [`ClaudeAgentOptionsBuilder::metrics_sink`](crate::ClaudeAgentOptionsBuilder::metrics_sink)
+//! and the client records turns, token usage, cost, tool latencies,
+//! permission decisions, hook latencies and control request health into it.
+//! [`InMemoryMetrics`] keeps everything for inspection; [`MetricsFacade`]
+//! forwards to the [`metrics`](https://docs.rs/metrics) crate so any of its
+//! exporters can be used.
+//!
+//! ```
+//! use std::sync::Arc;
+//! use claude_agent_sdk::metrics::{names, InMemoryMetrics};
+//! use claude_agent_sdk::ClaudeAgentOptions;
+//!
+//! let metrics = Arc::new(InMemoryMetrics::new());
+//! let options = ClaudeAgentOptions::builder()
+//!     .metrics_sink(metrics.clone())
+//!     .build();
+//!
+//! // ... run a conversation with `options` ...
+//!
+//! let snapshot = metrics.snapshot();
+//! println!("turns: {}", snapshot.counter_total(names::TURNS));
+//! ```
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+pub use crate::internal::telemetry::names;
+
+/// Destination for SDK metrics.
+///
+/// Implementations must be cheap to call; they are invoked inline while
+/// messages are read.
+pub trait MetricsSink: Send + Sync {
+    /// Add `value` to a counter.
+    fn increment_counter(&self, name: &str, labels: &[(&str, &str)], value: u64);
+
+    /// Record one observation in a histogram.
+    fn record_histogram(&self, name: &str, labels: &[(&str, &str)], value: f64);
+}
+
+/// A metric name with its labels, sorted by label key.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MetricKey {
+    pub name: String,
+    pub labels: Vec<(String, String)>,
+}
+
+impl MetricKey {
+    pub fn new(name: &str, labels: &[(&str, &str)]) -> Self {
+        let mut labels: Vec<(String, String)> = labels
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        labels.sort();
+        Self {
+            name: name.to_string(),
+            labels,
+        }
+    }
+}
+
+/// Summary of a histogram's observations.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HistogramSummary {
+    pub count: u64,
+    pub sum: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl HistogramSummary {
+    fn observe(&mut self, value: f64) {
+        self.count += 1;
+        self.sum += value;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    /// Mean of the observations.
+    pub fn mean(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.sum / self.count as f64
+        }
+    }
+}
+
+/// Point-in-time copy of the metrics held by [`InMemoryMetrics`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MetricsSnapshot {
+    pub counters: BTreeMap<MetricKey, u64>,
+    pub histograms: BTreeMap<MetricKey, HistogramSummary>,
+}
+
+impl MetricsSnapshot {
+    /// Value of the counter with exactly these labels.
+    pub fn counter(&self, name: &str, labels: &[(&str, &str)]) -> u64 {
+        self.counters
+            .get(&MetricKey::new(name, labels))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Sum of a counter across all label values.
+    pub fn counter_total(&self, name: &str) -> u64 {
+        self.counters
+            .iter()
+            .filter(|(key, _)| key.name == name)
+            .map(|(_, value)| value)
+            .sum()
+    }
+
+    /// Summary of the histogram with exactly these labels.
+    pub fn histogram(&self, name: &str, labels: &[(&str, &str)]) -> Option<HistogramSummary> {
+        self.histograms.get(&MetricKey::new(name, labels)).copied()
+    }
+}
+
+/// A sink that keeps metrics in memory.
+#[derive(Debug, Default)]
+pub struct InMemoryMetrics {
+    state: Mutex<MetricsSnapshot>,
+}
+
+impl InMemoryMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Copy the current metrics.
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        self.lock().clone()
+    }
+
+    /// Clear all metrics.
+    pub fn reset(&self) {
+        *self.lock() = MetricsSnapshot::default();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MetricsSnapshot> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl MetricsSink for InMemoryMetrics {
+    fn increment_counter(&self, name: &str, labels: &[(&str, &str)], value: u64) {
+        *self
+            .lock()
+            .counters
+            .entry(MetricKey::new(name, labels))
+            .or_insert(0) += value;
+    }
+
+    fn record_histogram(&self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.lock()
+            .histograms
+            .entry(MetricKey::new(name, labels))
+            .or_insert(HistogramSummary {
+                count: 0,
+                sum: 0.0,
+                min: f64::INFINITY,
+                max: f64::NEG_INFINITY,
+            })
+            .observe(value);
+    }
+}
+
+/// A sink that forwards to the global recorder of the `metrics` crate.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MetricsFacade;
+
+impl MetricsSink for MetricsFacade {
+    fn increment_counter(&self, name: &str, labels: &[(&str, &str)], value: u64) {
+        ::metrics::counter!(name.to_string(), facade_labels(labels)).increment(value);
+    }
+
+    fn record_histogram(&self, name: &str, labels: &[(&str, &str)], value: f64) {
+        ::metrics::histogram!(name.to_string(), facade_labels(labels)).record(value);
+    }
+}
+
+fn facade_labels(labels: &[(&str, &str)]) -> Vec<::metrics::Label> {
+    labels
+        .iter()
+        .map(|(k, v)| ::metrics::Label::new(k.to_string(), v.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_memory_counters_by_label() {
+        let metrics = InMemoryMetrics::new();
+        metrics.increment_counter(names::TOOL_CALLS, &[("tool", "Bash")], 1);
+        metrics.increment_counter(names::TOOL_CALLS, &[("tool", "Bash")], 2);
+        metrics.increment_counter(names::TOOL_CALLS, &[("tool", "Read")], 1);
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.counter(names::TOOL_CALLS, &[("tool", "Bash")]), 3);
+        assert_eq!(snapshot.counter_total(names::TOOL_CALLS), 4);
+        assert_eq!(snapshot.counter(names::TURNS, &[]), 0);
+
+        metrics.reset();
+        assert_eq!(metrics.snapshot(), MetricsSnapshot::default());
+    }
+
+    #[test]
+    fn test_label_order_does_not_matter() {
+        let metrics = InMemoryMetrics::new();
+        let labels = [("tool", "Bash"), ("decision", "allow")];
+        metrics.increment_counter(names::PERMISSION_DECISIONS, &labels, 1);
+        let snapshot = metrics.snapshot();
+        assert_eq!(
+            snapshot.counter(
+                names::PERMISSION_DECISIONS,
+                &[("decision", "allow"), ("tool", "Bash")]
+            ),
+            1
+        );
+    }
+
+    #[test]
+    fn test_histogram_summary() {
+        let metrics = InMemoryMetrics::new();
+        for value in [10.0, 30.0, 20.0] {
+            metrics.record_histogram(names::HOOK_DURATION_MS, &[("event", "PreToolUse")], value);
+        }
+        let summary = metrics
+            .snapshot()
+            .histogram(names::HOOK_DURATION_MS, &[("event", "PreToolUse")])
+            .unwrap();
+        assert_eq!(summary.count, 3);
+        assert_eq!(summary.min, 10.0);
+        assert_eq!(summary.max, 30.0);
+        assert_eq!(summary.mean(), 20.0);
+    }
+}
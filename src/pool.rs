@@ -0,0 +1,510 @@
+//! Pool of pre-connected clients.
+//!
+//! Starting the CLI and completing the initialize handshake takes a noticeable
+//! amount of time. [`ClientPool`] keeps clients connected in streaming mode so
+//! a conversation can start as soon as one is checked out.
+
+use std::collections::VecDeque;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::client::ClaudeSDKClient;
+use crate::error::{ClaudeSDKError, Result};
+use crate::internal::telemetry::{names, Recorder};
+use crate::types::ClaudeAgentOptions;
+
+/// Settings for a [`ClientPool`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolConfig {
+    /// Maximum number of clients, idle or checked out. Must be at least 1.
+    pub size: usize,
+    /// Clients older than this are disconnected instead of being reused.
+    pub max_lifetime: Option<Duration>,
+    /// Return clients to the pool after use instead of respawning them.
+    ///
+    /// A recycled client keeps its conversation history, so leave this off
+    /// when conversations must be isolated.
+    pub recycle: bool,
+    /// Respawn a recycled client after this many checkouts.
+    pub max_uses: Option<usize>,
+    /// How long [`ClientPool::checkout`] waits for a free slot.
+    pub checkout_timeout: Option<Duration>,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            size: 4,
+            max_lifetime: None,
+            recycle: false,
+            max_uses: None,
+            checkout_timeout: None,
+        }
+    }
+}
+
+impl PoolConfig {
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_max_lifetime(mut self, lifetime: Duration) -> Self {
+        self.max_lifetime = Some(lifetime);
+        self
+    }
+
+    pub fn with_recycle(mut self, recycle: bool) -> Self {
+        self.recycle = recycle;
+        self
+    }
+
+    pub fn with_max_uses(mut self, uses: usize) -> Self {
+        self.max_uses = Some(uses);
+        self
+    }
+
+    pub fn with_checkout_timeout(mut self, timeout: Duration) -> Self {
+        self.checkout_timeout = Some(timeout);
+        self
+    }
+}
+
+/// A fixed-size pool of connected [`ClaudeSDKClient`]s sharing one set of options.
+///
+/// Checked-out clients return to the pool when dropped. Depending on
+/// [`PoolConfig::recycle`] they are either reused as-is or disconnected and
+/// replaced by a fresh client in the background. Clients that are unhealthy,
+/// older than `max_lifetime` or past `max_uses` are always replaced.
+///
+/// ```no_run
+/// # async fn example() -> claude_agent_sdk::Result<()> {
+/// use claude_agent_sdk::pool::{ClientPool, PoolConfig};
+/// use claude_agent_sdk::ClaudeAgentOptions;
+///
+/// let pool = ClientPool::connect(ClaudeAgentOptions::default(), PoolConfig::new(2)).await?;
+///
+/// let mut client = pool.checkout().await?;
+/// client.send_message("Hello").await?;
+/// while let Some(message) = client.receive_response().await {
+///     if message?.is_result() {
+///         break;
+///     }
+/// }
+/// drop(client); // respawned in the background
+///
+/// pool.close().await;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ClientPool {
+    inner: Arc<PoolInner>,
+}
+
+struct PoolInner {
+    options: ClaudeAgentOptions,
+    config: PoolConfig,
+    idle: Mutex<VecDeque<PoolEntry>>,
+    slots: Arc<Semaphore>,
+    closed: AtomicBool,
+}
+
+struct PoolEntry {
+    client: ClaudeSDKClient,
+    created_at: Instant,
+    uses: usize,
+}
+
+impl std::fmt::Debug for ClientPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClientPool")
+            .field("config", &self.inner.config)
+            .field("idle", &self.idle_count())
+            .field("closed", &self.inner.closed.load(Ordering::SeqCst))
+            .finish()
+    }
+}
+
+impl ClientPool {
+    /// Create a pool and connect `config.size` clients up front.
+    pub async fn connect(options: ClaudeAgentOptions, config: PoolConfig) -> Result<Self> {
+        let pool = Self::new(options, config)?;
+        let size = pool.inner.config.size;
+        let clients =
+            futures::future::try_join_all((0..size).map(|_| pool.inner.spawn_entry(false))).await?;
+        pool.inner.lock_idle().extend(clients);
+        Ok(pool)
+    }
+
+    /// Create an empty pool; clients are connected on first checkout.
+    ///
+    /// Fails with [`ClaudeSDKError::InvalidConfig`] if `config.size` is 0,
+    /// since checkout could never succeed.
+    pub fn new(options: ClaudeAgentOptions, config: PoolConfig) -> Result<Self> {
+        if config.size == 0 {
+            return Err(ClaudeSDKError::InvalidConfig(
+                "Client pool size must be at least 1".to_string(),
+            ));
+        }
+        let slots = Arc::new(Semaphore::new(config.size));
+        Ok(Self {
+            inner: Arc::new(PoolInner {
+                options,
+                config,
+                idle: Mutex::new(VecDeque::new()),
+                slots,
+                closed: AtomicBool::new(false),
+            }),
+        })
+    }
+
+    /// Check out a connected client, waiting for a free slot if all are in use.
+    ///
+    /// Idle clients that are unhealthy or expired are replaced with a newly
+    /// connected one.
+    pub async fn checkout(&self) -> Result<PooledClient> {
+        if self.inner.closed.load(Ordering::SeqCst) {
+            return Err(ClaudeSDKError::CLIConnection(
+                "Client pool is closed".to_string(),
+            ));
+        }
+
+        let acquire = self.inner.slots.clone().acquire_owned();
+        let permit = match self.inner.config.checkout_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, acquire).await.map_err(|_| {
+                ClaudeSDKError::Timeout(format!("No pooled client available after {:?}", timeout))
+            })?,
+            None => acquire.await,
+        }
+        .map_err(|_| ClaudeSDKError::CLIConnection("Client pool is closed".to_string()))?;
+
+        let mut retired = false;
+        let mut entry = loop {
+            let candidate = self.inner.lock_idle().pop_front();
+            match candidate {
+                Some(entry) if self.inner.is_reusable(&entry) => break entry,
+                Some(entry) => {
+                    retire(entry);
+                    retired = true;
+                }
+                None => break self.inner.spawn_entry(retired).await?,
+            }
+        };
+        entry.uses += 1;
+
+        Ok(PooledClient {
+            entry: Some(entry),
+            pool: self.inner.clone(),
+            permit: Some(permit),
+            discard: false,
+        })
+    }
+
+    /// Number of connected clients waiting to be checked out.
+    pub fn idle_count(&self) -> usize {
+        self.inner.lock_idle().len()
+    }
+
+    /// Number of clients that can be checked out without waiting.
+    pub fn available(&self) -> usize {
+        self.inner.slots.available_permits()
+    }
+
+    /// Disconnect idle clients that are unhealthy or past their lifetime.
+    ///
+    /// Expired clients are otherwise only noticed on checkout.
+    pub async fn evict_expired(&self) {
+        let expired = {
+            let mut idle = self.inner.lock_idle();
+            let (keep, expired): (VecDeque<_>, VecDeque<_>) = idle
+                .drain(..)
+                .partition(|entry| self.inner.is_reusable(entry));
+            *idle = keep;
+            expired
+        };
+        for mut entry in expired {
+            let _ = entry.client.disconnect().await;
+        }
+    }
+
+    /// Disconnect all idle clients and stop handing out new ones.
+    ///
+    /// Clients still checked out are disconnected when returned.
+    pub async fn close(&self) {
+        self.inner.closed.store(true, Ordering::SeqCst);
+        self.inner.slots.close();
+        let idle: Vec<PoolEntry> = self.inner.lock_idle().drain(..).collect();
+        for mut entry in idle {
+            let _ = entry.client.disconnect().await;
+        }
+    }
+}
+
+impl PoolInner {
+    fn lock_idle(&self) -> std::sync::MutexGuard<'_, VecDeque<PoolEntry>> {
+        self.idle.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Connect a new client; `replacing` marks it as a restart of a retired one.
+    async fn spawn_entry(&self, replacing: bool) -> Result<PoolEntry> {
+        let mut client = ClaudeSDKClient::new(self.options.clone());
+        client.connect().await?;
+        if replacing {
+            Recorder::from_options(&self.options).counter(names::CLI_RESTARTS, &[], 1);
+        }
+        Ok(PoolEntry {
+            client,
+            created_at: Instant::now(),
+            uses: 0,
+        })
+    }
+
+    fn is_reusable(&self, entry: &PoolEntry) -> bool {
+        let expired = self
+            .config
+            .max_lifetime
+            .is_some_and(|lifetime| entry.created_at.elapsed() >= lifetime);
+        let worn_out = self.config.max_uses.is_some_and(|max| entry.uses >= max);
+        entry.client.is_healthy() && !expired && !worn_out
+    }
+
+    /// Put a returned client back, or replace it with a fresh one.
+    fn release(self: &Arc<Self>, entry: PoolEntry, discard: bool, permit: OwnedSemaphorePermit) {
+        let closed = self.closed.load(Ordering::SeqCst);
+        if !closed && !discard && self.config.recycle && self.is_reusable(&entry) {
+            self.lock_idle().push_back(entry);
+            return;
+        }
+
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let pool = self.clone();
+        handle.spawn(async move {
+            let mut entry = entry;
+            let _ = entry.client.disconnect().await;
+            if closed {
+                return;
+            }
+            match pool.spawn_entry(true).await {
+                Ok(fresh) if !pool.closed.load(Ordering::SeqCst) => {
+                    pool.lock_idle().push_back(fresh)
+                }
+                Ok(mut fresh) => {
+                    let _ = fresh.client.disconnect().await;
+                }
+                Err(e) => tracing::warn!("Failed to respawn pooled client: {}", e),
+            }
+            drop(permit);
+        });
+    }
+}
+
+/// Disconnect a client in the background.
+fn retire(mut entry: PoolEntry) {
+    if let Ok(handle) = tokio::runtime::Handle::try_current() {
+        handle.spawn(async move {
+            let _ = entry.client.disconnect().await;
+        });
+    }
+}
+
+/// A client checked out of a [`ClientPool`], returned when dropped.
+pub struct PooledClient {
+    entry: Option<PoolEntry>,
+    pool: Arc<PoolInner>,
+    permit: Option<OwnedSemaphorePermit>,
+    discard: bool,
+}
+
+impl PooledClient {
+    /// Replace this client with a fresh one when it is returned, even if
+    /// recycling is enabled. Use after errors that may leave it in a bad state.
+    pub fn discard(&mut self) {
+        self.discard = true;
+    }
+
+    /// How many times this client has been checked out, including now.
+    pub fn uses(&self) -> usize {
+        self.entry().uses
+    }
+
+    /// How long ago this client was connected.
+    pub fn age(&self) -> Duration {
+        self.entry().created_at.elapsed()
+    }
+
+    fn entry(&self) -> &PoolEntry {
+        self.entry.as_ref().expect("pooled client already returned")
+    }
+}
+
+impl std::fmt::Debug for PooledClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PooledClient")
+            .field("uses", &self.uses())
+            .field("age", &self.age())
+            .field("discard", &self.discard)
+            .finish()
+    }
+}
+
+impl Deref for PooledClient {
+    type Target = ClaudeSDKClient;
+
+    fn deref(&self) -> &ClaudeSDKClient {
+        &self.entry().client
+    }
+}
+
+impl DerefMut for PooledClient {
+    fn deref_mut(&mut self) -> &mut ClaudeSDKClient {
+        &mut self
+            .entry
+            .as_mut()
+            .expect("pooled client already returned")
+            .client
+    }
+}
+
+impl Drop for PooledClient {
+    fn drop(&mut self) {
+        if let (Some(entry), Some(permit)) = (self.entry.take(), self.permit.take()) {
+            self.pool.release(entry, self.discard, permit);
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::test_support::{fake_cli, CONTROL_ONLY};
+    use std::path::PathBuf;
+
+    fn options(cli: PathBuf) -> ClaudeAgentOptions {
+        ClaudeAgentOptions::builder().cli_path(cli).build()
+    }
+
+    async fn wait_for_idle(pool: &ClientPool, count: usize) {
+        for _ in 0..200 {
+            if pool.idle_count() == count && pool.available() == pool.inner.config.size {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("pool never reached {} idle clients", count);
+    }
+
+    #[tokio::test]
+    async fn test_connect_prewarms_clients() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = ClientPool::connect(options(fake_cli(&dir, CONTROL_ONLY)), PoolConfig::new(2))
+            .await
+            .unwrap();
+
+        assert_eq!(pool.idle_count(), 2);
+        let client = pool.checkout().await.unwrap();
+        assert!(client.is_healthy());
+        assert_eq!(client.uses(), 1);
+        assert_eq!(pool.idle_count(), 1);
+
+        drop(client);
+        pool.close().await;
+        assert_eq!(pool.idle_count(), 0);
+        assert!(pool.checkout().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_returned_client_is_respawned() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = ClientPool::connect(options(fake_cli(&dir, CONTROL_ONLY)), PoolConfig::new(1))
+            .await
+            .unwrap();
+
+        let client = pool.checkout().await.unwrap();
+        let first = client.cli_info().unwrap().path.clone();
+        drop(client);
+        wait_for_idle(&pool, 1).await;
+
+        let client = pool.checkout().await.unwrap();
+        assert_eq!(client.uses(), 1);
+        assert_eq!(client.cli_info().unwrap().path, first);
+        drop(client);
+        pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_recycle_until_max_uses() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = PoolConfig::new(1).with_recycle(true).with_max_uses(2);
+        let pool = ClientPool::connect(options(fake_cli(&dir, CONTROL_ONLY)), config)
+            .await
+            .unwrap();
+
+        assert_eq!(pool.checkout().await.unwrap().uses(), 1);
+        assert_eq!(pool.checkout().await.unwrap().uses(), 2);
+        wait_for_idle(&pool, 1).await;
+        assert_eq!(pool.checkout().await.unwrap().uses(), 1);
+
+        let mut client = pool.checkout().await.unwrap();
+        client.discard();
+        drop(client);
+        wait_for_idle(&pool, 1).await;
+        assert_eq!(pool.checkout().await.unwrap().uses(), 1);
+        pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_expired_idle_client_is_replaced() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = PoolConfig::new(1).with_max_lifetime(Duration::from_millis(50));
+        let pool = ClientPool::connect(options(fake_cli(&dir, CONTROL_ONLY)), config)
+            .await
+            .unwrap();
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        pool.evict_expired().await;
+        assert_eq!(pool.idle_count(), 0);
+
+        let client = pool.checkout().await.unwrap();
+        assert!(client.age() < Duration::from_millis(50));
+        drop(client);
+        pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn test_zero_size_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = options(fake_cli(&dir, CONTROL_ONLY));
+        assert!(matches!(
+            ClientPool::new(options.clone(), PoolConfig::new(0)),
+            Err(ClaudeSDKError::InvalidConfig(_))
+        ));
+        assert!(matches!(
+            ClientPool::connect(options, PoolConfig::new(0)).await,
+            Err(ClaudeSDKError::InvalidConfig(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_checkout_timeout() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = PoolConfig::new(1).with_checkout_timeout(Duration::from_millis(20));
+        let pool = ClientPool::connect(options(fake_cli(&dir, CONTROL_ONLY)), config)
+            .await
+            .unwrap();
+
+        let _held = pool.checkout().await.unwrap();
+        match pool.checkout().await {
+            Err(ClaudeSDKError::Timeout(_)) => {}
+            other => panic!("expected timeout, got {:?}", other),
+        }
+    }
+}
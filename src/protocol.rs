@@ -0,0 +1,522 @@
+//! Sans-IO core of the CLI protocol.
+//!
+//! [`Protocol`] tracks a session's control protocol without doing any I/O.
+//! Feed it what the CLI writes to stdout with [`Protocol::receive_line`],
+//! write what [`Protocol::poll_transmit`] returns to the CLI's stdin, and
+//! act on the [`Event`]s from [`Protocol::poll_event`]. It never blocks,
+//! spawns or sleeps, so any executor or a hand-written event loop can drive
+//! it; with the `tokio` feature, `ClaudeSDKClient` is such a driver.
+//!
+//! ```
+//! use claude_agent_sdk::protocol::{Event, Protocol};
+//! use claude_agent_sdk::SDKControlRequestVariant;
+//! use std::time::{Duration, Instant};
+//!
+//! let mut protocol = Protocol::new();
+//! let id = protocol.send_request(
+//!     SDKControlRequestVariant::McpStatus,
+//!     Some(Duration::from_secs(60)),
+//!     Instant::now(),
+//! );
+//!
+//! // Write this line to the CLI's stdin...
+//! let line = protocol.poll_transmit().unwrap();
+//! assert!(line.contains("mcp_status"));
+//!
+//! // ...and pass back what it prints.
+//! protocol.receive_line(&format!(
+//!     r#"{{"type":"control_response","response":{{"subtype":"success","request_id":"{}","response":{{"servers":[]}}}}}}"#,
+//!     id
+//! ));
+//! match protocol.poll_event() {
+//!     Some(Event::ControlResponse { request_id, result }) => {
+//!         assert_eq!(request_id, id);
+//!         assert!(result.is_ok());
+//!     }
+//!     other => panic!("unexpected event: {:?}", other),
+//! }
+//! ```
+
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::error::{ClaudeSDKError, Result};
+use crate::internal::parse_message;
+use crate::types::{
+    ControlResponseVariant, Message, SDKControlRequest, SDKControlRequestVariant,
+    SDKControlResponse,
+};
+
+/// Something the CLI said, decoded by [`Protocol`].
+#[derive(Debug)]
+pub enum Event {
+    /// An SDK message.
+    Message(Message),
+    /// A request from the CLI; answer it with [`Protocol::respond`].
+    ControlRequest {
+        request_id: String,
+        request: SDKControlRequestVariant,
+    },
+    /// The outcome of a request sent with [`Protocol::send_request`],
+    /// including timeouts and the CLI exiting first.
+    ControlResponse {
+        request_id: String,
+        result: Result<Value>,
+    },
+    /// The CLI withdrew one of its requests.
+    ControlCancel { request_id: String },
+    /// Output that could not be decoded.
+    Error(ClaudeSDKError),
+}
+
+/// A request awaiting its response.
+#[derive(Debug)]
+struct Pending {
+    initialize: bool,
+    deadline: Option<(Instant, Duration)>,
+}
+
+impl Pending {
+    /// "Initialize" or "Control", for error messages.
+    fn label(&self) -> &'static str {
+        if self.initialize {
+            "Initialize"
+        } else {
+            "Control"
+        }
+    }
+}
+
+/// State machine for the CLI's stream-JSON protocol.
+#[derive(Debug, Default)]
+pub struct Protocol {
+    next_request: u64,
+    pending: HashMap<String, Pending>,
+    transmit: VecDeque<String>,
+    events: VecDeque<Event>,
+}
+
+impl Protocol {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a control request, returning its request id.
+    ///
+    /// Its response arrives as [`Event::ControlResponse`]. With a `timeout`,
+    /// the request fails once [`handle_timeout`](Self::handle_timeout) is
+    /// called past `now + timeout`.
+    pub fn send_request(
+        &mut self,
+        request: SDKControlRequestVariant,
+        timeout: Option<Duration>,
+        now: Instant,
+    ) -> String {
+        let request_id = format!("req_{}_{}", self.next_request, rand_hex());
+        self.next_request += 1;
+
+        let pending = Pending {
+            initialize: matches!(request, SDKControlRequestVariant::Initialize { .. }),
+            deadline: timeout.map(|timeout| (now + timeout, timeout)),
+        };
+        let line = serde_json::to_string(&SDKControlRequest::new(request_id.clone(), request))
+            .expect("control requests serialize to JSON");
+        self.transmit.push_back(format!("{}\n", line));
+        self.pending.insert(request_id.clone(), pending);
+        request_id
+    }
+
+    /// Queue a message for the CLI, such as a user message.
+    pub fn send_message(&mut self, message: &Value) {
+        self.transmit.push_back(format!("{}\n", message));
+    }
+
+    /// Queue the answer to an [`Event::ControlRequest`].
+    pub fn respond(&mut self, request_id: &str, result: Result<Value>) -> Result<()> {
+        let response = match result {
+            Ok(data) => SDKControlResponse::success(request_id, Some(data)),
+            Err(e) => SDKControlResponse::error(request_id, e.to_string()),
+        };
+        let line = serde_json::to_string(&response)?;
+        self.transmit.push_back(format!("{}\n", line));
+        Ok(())
+    }
+
+    /// Decode one line of CLI output. Blank lines are ignored.
+    pub fn receive_line(&mut self, line: &str) {
+        let line = line.trim();
+        if line.is_empty() {
+            return;
+        }
+        match serde_json::from_str(line) {
+            Ok(data) => self.receive_value(data),
+            Err(e) => self
+                .events
+                .push_back(Event::Error(ClaudeSDKError::message_parse(
+                    format!("Invalid JSON from CLI stdout: {}", e),
+                    Some(Value::String(line.to_string())),
+                ))),
+        }
+    }
+
+    /// Decode one JSON value of CLI output.
+    pub fn receive_value(&mut self, data: Value) {
+        match data.get("type").and_then(Value::as_str) {
+            Some("control_response") => {
+                let response = match serde_json::from_value::<SDKControlResponse>(data.clone()) {
+                    Ok(response) => response,
+                    Err(e) => return self.receive_malformed_response(data, e),
+                };
+                let request_id = response.request_id().to_string();
+                // Responses to requests this side did not send are dropped
+                if self.pending.remove(&request_id).is_none() {
+                    return;
+                }
+                let result = match response.response {
+                    ControlResponseVariant::Success { response, .. } => {
+                        Ok(response.unwrap_or(Value::Null))
+                    }
+                    ControlResponseVariant::Error { error, .. } => {
+                        Err(ClaudeSDKError::ControlProtocol(error))
+                    }
+                };
+                self.events
+                    .push_back(Event::ControlResponse { request_id, result });
+            }
+            Some("control_request") => {
+                match serde_json::from_value::<SDKControlRequest>(data.clone()) {
+                    Ok(request) => self.events.push_back(Event::ControlRequest {
+                        request_id: request.request_id,
+                        request: request.request,
+                    }),
+                    Err(e) => self.receive_malformed_request(data, e),
+                }
+            }
+            Some("control_cancel_request") => {
+                if let Some(request_id) = data.get("request_id").and_then(Value::as_str) {
+                    self.events.push_back(Event::ControlCancel {
+                        request_id: request_id.to_string(),
+                    });
+                }
+            }
+            _ => self.events.push_back(match parse_message(data) {
+                Ok(message) => Event::Message(message),
+                Err(e) => Event::Error(e),
+            }),
+        }
+    }
+
+    /// Fail the pending request a malformed control response names, or
+    /// report the response as an error event when it names none.
+    fn receive_malformed_response(&mut self, data: Value, error: serde_json::Error) {
+        let request_id = data
+            .pointer("/response/request_id")
+            .and_then(Value::as_str)
+            .filter(|id| self.pending.contains_key(*id))
+            .map(str::to_string);
+        let error = ClaudeSDKError::message_parse(
+            format!("Malformed control response: {}", error),
+            Some(data),
+        );
+        match request_id {
+            Some(request_id) => {
+                self.pending.remove(&request_id);
+                self.events.push_back(Event::ControlResponse {
+                    request_id,
+                    result: Err(error),
+                });
+            }
+            None => self.events.push_back(Event::Error(error)),
+        }
+    }
+
+    /// Answer a malformed control request with an error so the CLI is not
+    /// left waiting, or report it as an error event when it has no ID.
+    fn receive_malformed_request(&mut self, data: Value, error: serde_json::Error) {
+        let message = format!("Malformed control request: {}", error);
+        let request_id = data.get("request_id").and_then(Value::as_str);
+        let queued = request_id.map(|request_id| {
+            self.respond(
+                request_id,
+                Err(ClaudeSDKError::ControlProtocol(message.clone())),
+            )
+        });
+        if !matches!(queued, Some(Ok(()))) {
+            self.events
+                .push_back(Event::Error(ClaudeSDKError::message_parse(
+                    message,
+                    Some(data),
+                )));
+        }
+    }
+
+    /// Note that the CLI's output ended, failing all pending requests.
+    pub fn receive_eof(&mut self) {
+        for (request_id, pending) in self.pending.drain() {
+            let error = ClaudeSDKError::ControlProtocol(format!(
+                "Transport stream ended before {} response received",
+                pending.label().to_lowercase()
+            ));
+            self.events.push_back(Event::ControlResponse {
+                request_id,
+                result: Err(error),
+            });
+        }
+    }
+
+    /// Earliest deadline of a pending request.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.pending
+            .values()
+            .filter_map(|pending| pending.deadline.map(|(at, _)| at))
+            .min()
+    }
+
+    /// Fail pending requests whose deadline is at or before `now`.
+    pub fn handle_timeout(&mut self, now: Instant) {
+        let expired: Vec<String> = self
+            .pending
+            .iter()
+            .filter(|(_, pending)| matches!(pending.deadline, Some((at, _)) if at <= now))
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        for request_id in expired {
+            let pending = self.pending.remove(&request_id).expect("expired request");
+            let timeout = pending.deadline.map(|(_, timeout)| timeout.as_secs());
+            let error = ClaudeSDKError::Timeout(format!(
+                "{} request timed out after {} seconds",
+                pending.label(),
+                timeout.unwrap_or_default()
+            ));
+            self.events.push_back(Event::ControlResponse {
+                request_id,
+                result: Err(error),
+            });
+        }
+    }
+
+    /// Whether a request is still awaiting its response.
+    pub fn is_pending(&self, request_id: &str) -> bool {
+        self.pending.contains_key(request_id)
+    }
+
+    /// Next line to write to the CLI, including its trailing newline.
+    pub fn poll_transmit(&mut self) -> Option<String> {
+        self.transmit.pop_front()
+    }
+
+    /// Next decoded event.
+    pub fn poll_event(&mut self) -> Option<Event> {
+        self.events.pop_front()
+    }
+}
+
+/// Generate a random hex string.
+fn rand_hex() -> String {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    let duration = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    format!("{:x}", duration.subsec_nanos())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn response_line(request_id: &str, subtype: &str) -> String {
+        json!({
+            "type": "control_response",
+            "response": {"subtype": subtype, "request_id": request_id, "response": {"ok": true}, "error": "nope"}
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_rand_hex_is_valid_hex() {
+        for hex in (0..10).map(|_| rand_hex()) {
+            assert!(!hex.is_empty());
+            assert!(hex.chars().all(|c| c.is_ascii_hexdigit()));
+        }
+    }
+
+    #[test]
+    fn test_request_round_trip() {
+        let mut protocol = Protocol::new();
+        let id = protocol.send_request(SDKControlRequestVariant::Interrupt, None, Instant::now());
+        let sent: Value = serde_json::from_str(&protocol.poll_transmit().unwrap()).unwrap();
+        assert_eq!(sent["request_id"], id);
+        assert_eq!(sent["request"]["subtype"], "interrupt");
+        assert!(protocol.is_pending(&id));
+
+        protocol.receive_line(&response_line(&id, "success"));
+        match protocol.poll_event() {
+            Some(Event::ControlResponse { request_id, result }) => {
+                assert_eq!(request_id, id);
+                assert_eq!(result.unwrap(), json!({"ok": true}));
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+        assert!(!protocol.is_pending(&id));
+    }
+
+    #[test]
+    fn test_error_response_and_unknown_ids() {
+        let mut protocol = Protocol::new();
+        let id = protocol.send_request(SDKControlRequestVariant::McpStatus, None, Instant::now());
+        protocol.receive_line(&response_line("someone_else", "success"));
+        protocol.receive_line(&response_line(&id, "error"));
+
+        match protocol.poll_event() {
+            Some(Event::ControlResponse {
+                result: Err(ClaudeSDKError::ControlProtocol(message)),
+                ..
+            }) => assert_eq!(message, "nope"),
+            other => panic!("unexpected event: {:?}", other),
+        }
+        assert!(protocol.poll_event().is_none());
+    }
+
+    #[test]
+    fn test_malformed_response_fails_its_request() {
+        let mut protocol = Protocol::new();
+        let id = protocol.send_request(SDKControlRequestVariant::McpStatus, None, Instant::now());
+        protocol.receive_line(&format!(
+            r#"{{"type":"control_response","response":{{"subtype":"bogus","request_id":"{}"}}}}"#,
+            id
+        ));
+        protocol.receive_line(r#"{"type":"control_response","response":{"subtype":"bogus"}}"#);
+
+        match protocol.poll_event() {
+            Some(Event::ControlResponse {
+                request_id,
+                result: Err(ClaudeSDKError::MessageParse { .. }),
+            }) => assert_eq!(request_id, id),
+            other => panic!("unexpected event: {:?}", other),
+        }
+        assert!(!protocol.is_pending(&id));
+        assert!(matches!(
+            protocol.poll_event(),
+            Some(Event::Error(ClaudeSDKError::MessageParse { .. }))
+        ));
+        assert!(protocol.poll_event().is_none());
+    }
+
+    #[test]
+    fn test_malformed_request_is_answered_with_an_error() {
+        let mut protocol = Protocol::new();
+        protocol.receive_line(
+            r#"{"type":"control_request","request_id":"cli-1","request":{"subtype":"bogus"}}"#,
+        );
+        protocol.receive_line(r#"{"type":"control_request","request":{"subtype":"bogus"}}"#);
+
+        let sent: Value = serde_json::from_str(&protocol.poll_transmit().unwrap()).unwrap();
+        assert_eq!(sent["response"]["subtype"], "error");
+        assert_eq!(sent["response"]["request_id"], "cli-1");
+        assert!(sent["response"]["error"]
+            .as_str()
+            .unwrap()
+            .contains("Malformed control request"));
+        assert!(protocol.poll_transmit().is_none());
+        assert!(matches!(
+            protocol.poll_event(),
+            Some(Event::Error(ClaudeSDKError::MessageParse { .. }))
+        ));
+        assert!(protocol.poll_event().is_none());
+    }
+
+    #[test]
+    fn test_incoming_requests_and_messages() {
+        let mut protocol = Protocol::new();
+        protocol.receive_line(
+            r#"{"type":"control_request","request_id":"cli-1","request":{"subtype":"can_use_tool","tool_name":"Bash","input":{}}}"#,
+        );
+        protocol.receive_line("   ");
+        protocol.receive_line(r#"{"type":"control_cancel_request","request_id":"cli-1"}"#);
+        protocol.receive_line(
+            r#"{"type":"assistant","message":{"model":"m","content":[{"type":"text","text":"hi"}]}}"#,
+        );
+        protocol.receive_line("{not json");
+
+        assert!(matches!(
+            protocol.poll_event(),
+            Some(Event::ControlRequest { request_id, request: SDKControlRequestVariant::CanUseTool { .. } })
+                if request_id == "cli-1"
+        ));
+        assert!(matches!(
+            protocol.poll_event(),
+            Some(Event::ControlCancel { request_id }) if request_id == "cli-1"
+        ));
+        assert!(matches!(
+            protocol.poll_event(),
+            Some(Event::Message(Message::Assistant(_)))
+        ));
+        assert!(matches!(
+            protocol.poll_event(),
+            Some(Event::Error(ClaudeSDKError::MessageParse { .. }))
+        ));
+
+        protocol
+            .respond("cli-1", Ok(json!({"behavior": "allow"})))
+            .unwrap();
+        let sent: Value = serde_json::from_str(&protocol.poll_transmit().unwrap()).unwrap();
+        assert_eq!(sent["type"], "control_response");
+        assert_eq!(sent["response"]["request_id"], "cli-1");
+        assert_eq!(sent["response"]["response"]["behavior"], "allow");
+    }
+
+    #[test]
+    fn test_timeouts_and_eof() {
+        let start = Instant::now();
+        let mut protocol = Protocol::new();
+        let init = protocol.send_request(
+            SDKControlRequestVariant::Initialize {
+                hooks: None,
+                agents: None,
+                json_schema: None,
+            },
+            Some(Duration::from_secs(10)),
+            start,
+        );
+        let model = protocol.send_request(
+            SDKControlRequestVariant::SetModel { model: None },
+            Some(Duration::from_secs(60)),
+            start,
+        );
+        assert_eq!(
+            protocol.next_deadline(),
+            Some(start + Duration::from_secs(10))
+        );
+
+        protocol.handle_timeout(start + Duration::from_secs(5));
+        assert!(protocol.poll_event().is_none());
+
+        protocol.handle_timeout(start + Duration::from_secs(10));
+        match protocol.poll_event() {
+            Some(Event::ControlResponse {
+                request_id,
+                result: Err(ClaudeSDKError::Timeout(message)),
+            }) => {
+                assert_eq!(request_id, init);
+                assert_eq!(message, "Initialize request timed out after 10 seconds");
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        protocol.receive_eof();
+        match protocol.poll_event() {
+            Some(Event::ControlResponse {
+                request_id,
+                result: Err(ClaudeSDKError::ControlProtocol(message)),
+            }) => {
+                assert_eq!(request_id, model);
+                assert!(message.contains("ended before control response"));
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+        assert_eq!(protocol.next_deadline(), None);
+    }
+}
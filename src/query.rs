@@ -2,8 +2,9 @@
 
 use futures::{Stream, StreamExt};
 
-use crate::error::Result;
+use crate::error::{ClaudeSDKError, Result};
 use crate::internal::parse_message;
+use crate::internal::telemetry::SessionTracer;
 use crate::transport::{SubprocessCLITransport, Transport};
 use crate::types::{ClaudeAgentOptions, Message};
 
@@ -69,26 +70,99 @@ pub async fn query(
 ) -> Result<impl Stream<Item = Result<Message>>> {
     let options = options.unwrap_or_default();
     let prompt = prompt.into();
+    let mut tracer = SessionTracer::new(&options);
 
     // Create transport
     let mut transport = SubprocessCLITransport::new(prompt, options)?;
     transport.connect().await?;
 
     // Create message stream
-    let stream = async_stream::try_stream! {
+    let stream = async_stream::stream! {
         let msg_stream = transport.read_messages();
         tokio::pin!(msg_stream);
+        tracer.start_turn();
 
         while let Some(result) = msg_stream.next().await {
-            let data = result?;
-            let message = parse_message(data)?;
-            yield message;
+            match result.and_then(parse_message) {
+                Ok(message) => {
+                    tracer.observe(&message);
+                    yield Ok(message);
+                }
+                Err(e) => {
+                    // Malformed lines are reported and skipped
+                    let recoverable = matches!(e, ClaudeSDKError::MessageParse { .. });
+                    yield Err(e);
+                    if !recoverable {
+                        return;
+                    }
+                }
+            }
         }
     };
 
     Ok(stream)
 }
 
+/// Query Claude Code for structured output deserialized into `T`.
+///
+/// The JSON schema of `T` is sent as the output format (unless `options`
+/// already sets one), and the `structured_output` of the final result is
+/// deserialized into `T`. Runs without structured output, or whose output
+/// does not match `T`, fail with [`ClaudeSDKError::StructuredOutput`].
+///
+/// # Example
+///
+/// ```no_run
+/// use claude_agent_sdk::query_typed;
+/// use schemars::JsonSchema;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize, JsonSchema)]
+/// struct Capital {
+///     country: String,
+///     city: String,
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let capital: Capital = query_typed("What is the capital of France?", None).await?;
+///     println!("{}: {}", capital.country, capital.city);
+///     Ok(())
+/// }
+/// ```
+#[cfg(feature = "schemars")]
+pub async fn query_typed<T>(
+    prompt: impl Into<String>,
+    options: Option<ClaudeAgentOptions>,
+) -> Result<T>
+where
+    T: serde::de::DeserializeOwned + schemars::JsonSchema,
+{
+    let mut options = options.unwrap_or_default();
+    if options.output_format.is_none() {
+        options.output_format = Some(crate::types::json_schema_format::<T>());
+    }
+
+    let stream = query(prompt, Some(options)).await?;
+    tokio::pin!(stream);
+
+    while let Some(message) = stream.next().await {
+        match message {
+            Ok(Message::Result(result)) => return result.structured_output_as(),
+            Ok(_) => {}
+            Err(e @ ClaudeSDKError::MessageParse { .. }) => {
+                tracing::warn!("Skipping malformed message: {}", e);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    Err(ClaudeSDKError::StructuredOutput {
+        message: "stream ended without a result message".to_string(),
+        output: None,
+    })
+}
+
 /// Query Claude Code with a custom transport.
 ///
 /// This variant allows you to provide your own transport implementation,
@@ -106,18 +180,31 @@ pub async fn query(
 pub async fn query_with_transport<T: Transport + 'static>(
     _prompt: impl Into<String>,
     mut transport: T,
-    _options: Option<ClaudeAgentOptions>,
+    options: Option<ClaudeAgentOptions>,
 ) -> Result<impl Stream<Item = Result<Message>>> {
+    let mut tracer = SessionTracer::new(&options.unwrap_or_default());
     transport.connect().await?;
 
-    let stream = async_stream::try_stream! {
+    let stream = async_stream::stream! {
         let msg_stream = transport.read_messages();
         tokio::pin!(msg_stream);
+        tracer.start_turn();
 
         while let Some(result) = msg_stream.next().await {
-            let data = result?;
-            let message = parse_message(data)?;
-            yield message;
+            match result.and_then(parse_message) {
+                Ok(message) => {
+                    tracer.observe(&message);
+                    yield Ok(message);
+                }
+                Err(e) => {
+                    // Malformed lines are reported and skipped
+                    let recoverable = matches!(e, ClaudeSDKError::MessageParse { .. });
+                    yield Err(e);
+                    if !recoverable {
+                        return;
+                    }
+                }
+            }
         }
     };
 
@@ -127,7 +214,6 @@ pub async fn query_with_transport<T: Transport + 'static>(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::error::ClaudeSDKError;
     use async_trait::async_trait;
     use serde_json::json;
     use std::pin::Pin;
@@ -474,4 +560,84 @@ mod tests {
 
         assert!(found_result);
     }
+
+    /// Fake CLI answering with structured output only when the schema it was
+    /// given describes a `Capital`.
+    #[cfg(all(unix, feature = "schemars"))]
+    const STRUCTURED_CLI: &str = r#"schema=
+while [ $# -gt 0 ]; do
+  if [ "$1" = "--json-schema" ]; then schema=$2; fi
+  shift
+done
+output=null
+if printf '%s' "$schema" | grep -q '"city"' && printf '%s' "$schema" | grep -q '"country"'; then
+  output='{"country":"France","city":"Paris"}'
+fi
+printf '{"type":"result","subtype":"success","duration_ms":1,"duration_api_ms":1,"is_error":false,"num_turns":1,"session_id":"s","structured_output":%s}\n' "$output"
+"#;
+
+    #[cfg(all(unix, feature = "schemars"))]
+    #[tokio::test]
+    async fn test_query_typed_sends_schema_and_parses_output() {
+        use crate::test_support::fake_cli;
+
+        #[derive(Debug, PartialEq, serde::Deserialize, schemars::JsonSchema)]
+        struct Capital {
+            country: String,
+            city: String,
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let options = ClaudeAgentOptions::builder()
+            .cli_path(fake_cli(&dir, STRUCTURED_CLI))
+            .build();
+        let capital: Capital = query_typed("Capital of France?", Some(options))
+            .await
+            .unwrap();
+        assert_eq!(
+            capital,
+            Capital {
+                country: "France".to_string(),
+                city: "Paris".to_string(),
+            }
+        );
+
+        // A format set on the options is sent instead of the derived schema
+        let options = ClaudeAgentOptions::builder()
+            .cli_path(fake_cli(&dir, STRUCTURED_CLI))
+            .output_format(json!({"type": "json_schema", "schema": {"type": "object"}}))
+            .build();
+        let err = query_typed::<Capital>("Capital of France?", Some(options))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ClaudeSDKError::StructuredOutput { .. }));
+    }
+
+    /// Fake CLI printing one malformed line between two messages.
+    #[cfg(unix)]
+    const MALFORMED_LINE_CLI: &str = r#"printf '{"type":"system","subtype":"init"}\n'
+printf '{"type":"assistant",\n'
+result_success
+"#;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_query_reports_malformed_line_and_continues() {
+        use crate::test_support::fake_cli;
+
+        let dir = tempfile::tempdir().unwrap();
+        let options = ClaudeAgentOptions::builder()
+            .cli_path(fake_cli(&dir, MALFORMED_LINE_CLI))
+            .build();
+        let stream = query("hi", Some(options)).await.unwrap();
+        let results: Vec<_> = stream.collect().await;
+
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok());
+        assert!(matches!(
+            results[1],
+            Err(ClaudeSDKError::MessageParse { .. })
+        ));
+        assert!(results[2].as_ref().unwrap().is_result());
+    }
 }
@@ -0,0 +1,211 @@
+//! Retrying turns that fail with transient API errors.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::types::AssistantMessageError;
+
+/// Called before each retry.
+pub type RetryCallback = Arc<dyn Fn(&RetryAttempt) + Send + Sync>;
+
+/// Details of a retry about to happen.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryAttempt {
+    /// Retry number, starting at 1.
+    pub attempt: u32,
+    /// Error reported by the failed turn.
+    pub error: AssistantMessageError,
+    /// Delay before the message is resent.
+    pub delay: Duration,
+    /// Model switched to for this attempt, if any.
+    pub model: Option<String>,
+}
+
+/// How [`ClaudeSDKClient`](crate::ClaudeSDKClient) recovers from transient API errors.
+///
+/// When an assistant message reports a retryable error, the client holds back
+/// that message and the rest of the failed turn, waits with exponential
+/// backoff and jitter, and resends the last user message. Messages the turn
+/// produced before the error, such as stream events, have already been
+/// yielded. With [`with_fallback_model`] it first switches to
+/// `fallback_model` from the options, and switches back once the retried
+/// turn ends.
+///
+/// [`with_fallback_model`]: RetryPolicy::with_fallback_model
+///
+/// ```
+/// use std::time::Duration;
+/// use claude_agent_sdk::{AssistantMessageError, RetryPolicy};
+///
+/// let policy = RetryPolicy::new()
+///     .with_max_retries(5)
+///     .with_initial_backoff(Duration::from_millis(500))
+///     .with_fallback_model(true)
+///     .on_retry(|attempt| eprintln!("retry {}: {:?}", attempt.attempt, attempt.error));
+///
+/// assert!(policy.is_retryable(AssistantMessageError::RateLimit));
+/// assert!(!policy.is_retryable(AssistantMessageError::BillingError));
+/// ```
+#[derive(Clone)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+    /// Fraction of each delay that is randomized, from 0.0 to 1.0.
+    pub jitter: f64,
+    /// Errors that trigger a retry.
+    pub retry_on: Vec<AssistantMessageError>,
+    /// Switch to the options' `fallback_model` before the first retry.
+    pub use_fallback_model: bool,
+    pub on_retry: Option<RetryCallback>,
+}
+
+impl std::fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_retries", &self.max_retries)
+            .field("initial_backoff", &self.initial_backoff)
+            .field("max_backoff", &self.max_backoff)
+            .field("multiplier", &self.multiplier)
+            .field("jitter", &self.jitter)
+            .field("retry_on", &self.retry_on)
+            .field("use_fallback_model", &self.use_fallback_model)
+            .field("on_retry", &self.on_retry.as_ref().map(|_| "<callback>"))
+            .finish()
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            multiplier: 2.0,
+            jitter: 0.2,
+            retry_on: vec![
+                AssistantMessageError::RateLimit,
+                AssistantMessageError::ServerError,
+            ],
+            use_fallback_model: false,
+            on_retry: None,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_retries(mut self, retries: u32) -> Self {
+        self.max_retries = retries;
+        self
+    }
+
+    pub fn with_initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    pub fn with_max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_retry_on(mut self, errors: Vec<AssistantMessageError>) -> Self {
+        self.retry_on = errors;
+        self
+    }
+
+    pub fn with_fallback_model(mut self, enable: bool) -> Self {
+        self.use_fallback_model = enable;
+        self
+    }
+
+    pub fn on_retry<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&RetryAttempt) + Send + Sync + 'static,
+    {
+        self.on_retry = Some(Arc::new(callback));
+        self
+    }
+
+    /// Check if the error is one this policy retries.
+    pub fn is_retryable(&self, error: AssistantMessageError) -> bool {
+        self.retry_on.contains(&error)
+    }
+
+    /// Delay before retry number `attempt`, starting at 1.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let base = self.initial_backoff.as_secs_f64() * self.multiplier.powi(exponent);
+        let capped = base.min(self.max_backoff.as_secs_f64());
+        let jittered = capped * (1.0 - self.jitter * random_unit());
+        Duration::try_from_secs_f64(jittered.max(0.0)).unwrap_or(self.max_backoff)
+    }
+}
+
+/// A random number in `[0, 1)`.
+fn random_unit() -> f64 {
+    let bits = RandomState::new().build_hasher().finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_retryable_errors() {
+        let policy = RetryPolicy::default();
+        assert!(policy.is_retryable(AssistantMessageError::RateLimit));
+        assert!(policy.is_retryable(AssistantMessageError::ServerError));
+        assert!(!policy.is_retryable(AssistantMessageError::BillingError));
+        assert!(!policy.is_retryable(AssistantMessageError::AuthenticationFailed));
+    }
+
+    #[test]
+    fn test_delay_backs_off_exponentially() {
+        let policy = RetryPolicy::new()
+            .with_initial_backoff(Duration::from_millis(100))
+            .with_max_backoff(Duration::from_millis(350))
+            .with_jitter(0.0);
+        assert_eq!(policy.delay(1), Duration::from_millis(100));
+        assert_eq!(policy.delay(2), Duration::from_millis(200));
+        assert_eq!(policy.delay(3), Duration::from_millis(350));
+        assert_eq!(policy.delay(30), Duration::from_millis(350));
+    }
+
+    #[test]
+    fn test_delay_without_cap_does_not_panic() {
+        let policy = RetryPolicy::new()
+            .with_max_backoff(Duration::MAX)
+            .with_jitter(0.0);
+        assert_eq!(policy.delay(u32::MAX), Duration::MAX);
+    }
+
+    #[test]
+    fn test_delay_jitter_stays_in_range() {
+        let policy = RetryPolicy::new()
+            .with_initial_backoff(Duration::from_millis(100))
+            .with_jitter(0.5);
+        for _ in 0..50 {
+            let delay = policy.delay(1);
+            assert!(delay > Duration::from_millis(50) && delay <= Duration::from_millis(100));
+        }
+    }
+}
@@ -0,0 +1,304 @@
+//! Splitting a message stream by subagent.
+//!
+//! Messages from `Task` subagents arrive interleaved with the main
+//! conversation and are only told apart by `parent_tool_use_id`.
+//! [`demux_subagents`] routes them into one stream per subagent.
+//!
+//! ```no_run
+//! use claude_agent_sdk::subagent::{demux_subagents, DemuxItem};
+//! use claude_agent_sdk::query;
+//! use futures::StreamExt;
+//!
+//! # async fn example() -> claude_agent_sdk::Result<()> {
+//! let messages = query("Review this repository", None).await?;
+//! let root = demux_subagents(messages);
+//! futures::pin_mut!(root);
+//!
+//! while let Some(item) = root.next().await {
+//!     match item? {
+//!         DemuxItem::Message(message) => println!("main: {:?}", message),
+//!         DemuxItem::Subagent(mut child) => {
+//!             let label = child.subagent_type.clone().unwrap_or_default();
+//!             tokio::spawn(async move {
+//!                 while let Some(item) = child.next().await {
+//!                     println!("{}: {:?}", label, item);
+//!                 }
+//!             });
+//!         }
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use futures::channel::mpsc;
+use futures::{Stream, StreamExt};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::conversation::TASK_TOOL_NAME;
+use crate::error::Result;
+use crate::types::{ContentBlock, Message, UserMessageContent};
+
+/// An item of a demultiplexed stream.
+#[derive(Debug)]
+pub enum DemuxItem {
+    /// A message belonging to this stream.
+    Message(Message),
+    /// A subagent started by a `Task` tool use in this stream.
+    Subagent(SubagentStream),
+}
+
+/// Messages of one subagent.
+///
+/// Yielded right after the message containing the `Task` tool use, and ends
+/// once the tool result for that use arrives. Subagents started by this
+/// subagent appear as nested [`DemuxItem::Subagent`] items.
+///
+/// Child streams are fed while the root stream is polled; poll the root
+/// stream for children to make progress.
+#[derive(Debug)]
+pub struct SubagentStream {
+    /// Id of the `Task` tool use that started the subagent.
+    pub tool_use_id: String,
+    /// The `subagent_type` from the tool input.
+    pub subagent_type: Option<String>,
+    /// The `description` from the tool input.
+    pub description: Option<String>,
+    receiver: mpsc::UnboundedReceiver<DemuxItem>,
+}
+
+impl Stream for SubagentStream {
+    type Item = DemuxItem;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_next_unpin(cx)
+    }
+}
+
+/// Split `messages` into a root stream and per-subagent child streams.
+///
+/// Messages whose `parent_tool_use_id` names a known `Task` tool use go to
+/// that subagent's stream; everything else, including errors and the final
+/// `Task` tool result, stays in the returned root stream.
+pub fn demux_subagents<S>(messages: S) -> impl Stream<Item = Result<DemuxItem>>
+where
+    S: Stream<Item = Result<Message>>,
+{
+    async_stream::stream! {
+        let mut children: HashMap<String, mpsc::UnboundedSender<DemuxItem>> = HashMap::new();
+        futures::pin_mut!(messages);
+
+        while let Some(result) = messages.next().await {
+            let message = match result {
+                Ok(message) => message,
+                Err(e) => {
+                    yield Err(e);
+                    continue;
+                }
+            };
+
+            let started = started_subagents(&message);
+            let finished = finished_tool_uses(&message);
+            let owner = message
+                .parent_tool_use_id()
+                .and_then(|id| children.get(id))
+                .cloned();
+
+            let mut items = vec![DemuxItem::Message(message)];
+            for (id, input) in started {
+                let (sender, receiver) = mpsc::unbounded();
+                let field = |key: &str| input.get(key).and_then(Value::as_str).map(str::to_string);
+                items.push(DemuxItem::Subagent(SubagentStream {
+                    tool_use_id: id.clone(),
+                    subagent_type: field("subagent_type"),
+                    description: field("description"),
+                    receiver,
+                }));
+                children.insert(id, sender);
+            }
+
+            match owner {
+                Some(sender) => {
+                    for item in items {
+                        // A dropped child stream just stops receiving
+                        let _ = sender.unbounded_send(item);
+                    }
+                }
+                None => {
+                    for item in items {
+                        yield Ok(item);
+                    }
+                }
+            }
+
+            // Dropping the sender ends the child stream
+            for id in finished {
+                children.remove(&id);
+            }
+        }
+    }
+}
+
+/// `Task` tool uses in a message, with their inputs.
+fn started_subagents(message: &Message) -> Vec<(String, Value)> {
+    let Message::Assistant(assistant) = message else {
+        return Vec::new();
+    };
+    assistant
+        .content
+        .iter()
+        .filter_map(|block| match block {
+            ContentBlock::ToolUse { id, name, input } if name == TASK_TOOL_NAME => {
+                Some((id.clone(), input.clone()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Ids of the tool uses answered by a message.
+fn finished_tool_uses(message: &Message) -> Vec<String> {
+    let Message::User(user) = message else {
+        return Vec::new();
+    };
+    let UserMessageContent::Blocks(ref blocks) = user.content else {
+        return Vec::new();
+    };
+    blocks
+        .iter()
+        .filter_map(|block| match block {
+            ContentBlock::ToolResult { tool_use_id, .. } => Some(tool_use_id.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{AssistantMessage, ResultMessage, UserMessage};
+    use serde_json::json;
+
+    fn task(id: &str, subagent_type: &str) -> AssistantMessage {
+        AssistantMessage::new(
+            vec![ContentBlock::tool_use(
+                id,
+                TASK_TOOL_NAME,
+                json!({"subagent_type": subagent_type, "description": "work"}),
+            )],
+            "claude-sonnet-4-5",
+        )
+    }
+
+    fn text(text: &str) -> AssistantMessage {
+        AssistantMessage::new(vec![ContentBlock::text(text)], "claude-sonnet-4-5")
+    }
+
+    fn result_for(id: &str) -> UserMessage {
+        UserMessage::new(vec![ContentBlock::tool_result(
+            id,
+            Some(json!("done")),
+            None,
+        )])
+    }
+
+    async fn collect_texts(stream: &mut SubagentStream) -> Vec<String> {
+        let mut texts = Vec::new();
+        while let Some(item) = stream.next().await {
+            if let DemuxItem::Message(Message::Assistant(msg)) = item {
+                texts.push(msg.text());
+            }
+        }
+        texts
+    }
+
+    #[tokio::test]
+    async fn test_routes_interleaved_subagents() {
+        let messages = vec![
+            Message::Assistant(task("task_a", "explorer")),
+            Message::Assistant(task("task_b", "reviewer")),
+            Message::Assistant(text("a1").with_parent_tool_use_id("task_a")),
+            Message::Assistant(text("b1").with_parent_tool_use_id("task_b")),
+            Message::Assistant(text("a2").with_parent_tool_use_id("task_a")),
+            Message::User(result_for("task_a")),
+            Message::Assistant(text("late").with_parent_tool_use_id("task_a")),
+            Message::User(result_for("task_b")),
+            Message::Assistant(text("root")),
+            Message::Result(ResultMessage::new("success", 1, 1, false, 1, "s")),
+        ];
+
+        let root = demux_subagents(futures::stream::iter(messages.into_iter().map(Ok)));
+        let items: Vec<DemuxItem> = root.map(|item| item.unwrap()).collect().await;
+
+        let mut children: Vec<SubagentStream> = Vec::new();
+        let mut root_texts = Vec::new();
+        for item in items {
+            match item {
+                DemuxItem::Subagent(child) => children.push(child),
+                DemuxItem::Message(Message::Assistant(msg)) => root_texts.push(msg.text()),
+                DemuxItem::Message(_) => {}
+            }
+        }
+
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[0].tool_use_id, "task_a");
+        assert_eq!(children[0].subagent_type.as_deref(), Some("explorer"));
+        assert_eq!(children[1].subagent_type.as_deref(), Some("reviewer"));
+        assert_eq!(collect_texts(&mut children[0]).await, vec!["a1", "a2"]);
+        assert_eq!(collect_texts(&mut children[1]).await, vec!["b1"]);
+        // Messages for a finished subagent fall back to the root stream
+        assert_eq!(root_texts, vec!["", "", "late", "root"]);
+    }
+
+    #[tokio::test]
+    async fn test_nested_subagent_streams() {
+        let messages = vec![
+            Message::Assistant(task("outer", "planner")),
+            Message::Assistant(task("inner", "explorer").with_parent_tool_use_id("outer")),
+            Message::Assistant(text("deep").with_parent_tool_use_id("inner")),
+            Message::User(result_for("inner").with_parent_tool_use_id("outer")),
+            Message::User(result_for("outer")),
+        ];
+
+        let root = demux_subagents(futures::stream::iter(messages.into_iter().map(Ok)));
+        let items: Vec<DemuxItem> = root.map(|item| item.unwrap()).collect().await;
+        let mut outer = items
+            .into_iter()
+            .find_map(|item| match item {
+                DemuxItem::Subagent(child) => Some(child),
+                _ => None,
+            })
+            .unwrap();
+
+        let mut inner = None;
+        let mut outer_messages = 0;
+        while let Some(item) = outer.next().await {
+            match item {
+                DemuxItem::Subagent(child) => inner = Some(child),
+                DemuxItem::Message(_) => outer_messages += 1,
+            }
+        }
+        assert_eq!(outer_messages, 2);
+        let mut inner = inner.unwrap();
+        assert_eq!(inner.subagent_type.as_deref(), Some("explorer"));
+        assert_eq!(collect_texts(&mut inner).await, vec!["deep"]);
+    }
+
+    #[tokio::test]
+    async fn test_errors_stay_on_root() {
+        let messages = vec![
+            Ok(Message::Assistant(task("task_a", "explorer"))),
+            Err(crate::error::ClaudeSDKError::MessageParse {
+                message: "bad line".to_string(),
+                data: None,
+            }),
+        ];
+        let root = demux_subagents(futures::stream::iter(messages));
+        let items: Vec<_> = root.collect().await;
+        assert_eq!(items.len(), 3);
+        assert!(items[2].is_err());
+    }
+}
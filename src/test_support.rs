@@ -0,0 +1,41 @@
+//! Fixtures for tests that drive a fake Claude Code CLI.
+//!
+//! [`fake_cli`] writes a shell script that answers `-v` like a supported
+//! CLI and defines helpers for speaking the stream-json protocol:
+//!
+//! - `request_id "$line"` prints the `request_id` of a control request.
+//! - `control_success "$id" [response]` answers it with success.
+//! - `is_user_message "$line"` checks for a user message.
+//! - `result_success` prints a successful result message.
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+
+/// Shell preamble shared by every fake CLI.
+const PREAMBLE: &str = r#"#!/bin/sh
+if [ "$1" = "-v" ]; then echo "2.1.0 (Claude Code)"; exit 0; fi
+request_id() { printf '%s' "$1" | sed -n 's/.*"request_id":"\([^"]*\)".*/\1/p'; }
+control_success() {
+  printf '{"type":"control_response","response":{"subtype":"success","request_id":"%s","response":%s}}\n' "$1" "${2:-"{}"}"
+}
+is_user_message() { [ "${1#*\"type\":\"user\"}" != "$1" ]; }
+result_success() {
+  printf '{"type":"result","subtype":"success","duration_ms":1,"duration_api_ms":1,"is_error":false,"num_turns":1,"session_id":"s"}\n'
+}
+"#;
+
+/// Fake CLI that answers every control request with success and ignores
+/// everything else.
+pub(crate) const CONTROL_ONLY: &str = r#"while IFS= read -r line; do
+  id=$(request_id "$line")
+  if [ -n "$id" ]; then control_success "$id"; fi
+done
+"#;
+
+/// Write an executable fake CLI running `script` after the preamble.
+pub(crate) fn fake_cli(dir: &tempfile::TempDir, script: &str) -> PathBuf {
+    let path = dir.path().join("claude");
+    std::fs::write(&path, format!("{}{}", PREAMBLE, script)).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    path
+}
@@ -0,0 +1,633 @@
+//! Typed inputs and outputs for Claude Code's built-in tools.
+//!
+//! Tool inputs arrive as raw JSON in [`ToolUseBlock`], hook inputs and the
+//! `can_use_tool` callback. [`BuiltinToolInput::parse`] turns them into typed
+//! structs so callers can pattern-match instead of indexing JSON.
+//!
+//! Every input struct, including the items nested in them, has a flattened
+//! `extra` map holding the fields it does not model, such as ones added by
+//! newer CLI versions, so an input parsed here serializes back to the JSON
+//! it came from.
+//!
+//! ```
+//! use claude_agent_sdk::tools::BuiltinToolInput;
+//! use serde_json::json;
+//!
+//! let input = BuiltinToolInput::parse("Bash", &json!({"command": "ls -la"})).unwrap();
+//! match input {
+//!     BuiltinToolInput::Bash(bash) => assert_eq!(bash.command, "ls -la"),
+//!     _ => unreachable!(),
+//! }
+//! ```
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::error::{ClaudeSDKError, Result};
+use crate::types::{PostToolUseHookInput, PreToolUseHookInput, ToolUseBlock};
+
+/// Input for the Bash tool.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BashInput {
+    pub command: String,
+    /// Timeout in milliseconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_in_background: Option<bool>,
+    #[serde(
+        rename = "dangerouslyDisableSandbox",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub dangerously_disable_sandbox: Option<bool>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Input for the Read tool.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReadInput {
+    pub file_path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Input for the Write tool.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WriteInput {
+    pub file_path: String,
+    pub content: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Input for the Edit tool.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EditInput {
+    pub file_path: String,
+    pub old_string: String,
+    pub new_string: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replace_all: Option<bool>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A single edit within a MultiEdit call.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EditOperation {
+    pub old_string: String,
+    pub new_string: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replace_all: Option<bool>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Input for the MultiEdit tool.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MultiEditInput {
+    pub file_path: String,
+    pub edits: Vec<EditOperation>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Input for the Glob tool.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GlobInput {
+    pub pattern: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Output mode for the Grep tool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GrepOutputMode {
+    Content,
+    FilesWithMatches,
+    Count,
+}
+
+/// Input for the Grep tool.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GrepInput {
+    pub pattern: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub glob: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_mode: Option<GrepOutputMode>,
+    #[serde(rename = "-B", skip_serializing_if = "Option::is_none")]
+    pub lines_before: Option<u64>,
+    #[serde(rename = "-A", skip_serializing_if = "Option::is_none")]
+    pub lines_after: Option<u64>,
+    #[serde(rename = "-C", skip_serializing_if = "Option::is_none")]
+    pub lines_context: Option<u64>,
+    #[serde(rename = "-n", skip_serializing_if = "Option::is_none")]
+    pub line_numbers: Option<bool>,
+    #[serde(rename = "-i", skip_serializing_if = "Option::is_none")]
+    pub case_insensitive: Option<bool>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub file_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub head_limit: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub multiline: Option<bool>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Input for the WebFetch tool.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebFetchInput {
+    pub url: String,
+    pub prompt: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Input for the WebSearch tool.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebSearchInput {
+    pub query: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_domains: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blocked_domains: Option<Vec<String>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Input for the Task tool, which launches a subagent.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskInput {
+    pub description: String,
+    pub prompt: String,
+    pub subagent_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Status of a todo item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TodoStatus {
+    Pending,
+    InProgress,
+    Completed,
+}
+
+/// A single item in a TodoWrite call.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TodoItem {
+    pub content: String,
+    pub status: TodoStatus,
+    #[serde(rename = "activeForm", skip_serializing_if = "Option::is_none")]
+    pub active_form: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Input for the TodoWrite tool.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TodoWriteInput {
+    pub todos: Vec<TodoItem>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Cell type for the NotebookEdit tool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotebookCellType {
+    Code,
+    Markdown,
+}
+
+/// Edit mode for the NotebookEdit tool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotebookEditMode {
+    Replace,
+    Insert,
+    Delete,
+}
+
+/// Input for the NotebookEdit tool.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NotebookEditInput {
+    pub notebook_path: String,
+    pub new_source: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cell_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cell_type: Option<NotebookCellType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub edit_mode: Option<NotebookEditMode>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Typed input for a built-in tool call.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BuiltinToolInput {
+    Bash(BashInput),
+    Read(ReadInput),
+    Write(WriteInput),
+    Edit(EditInput),
+    MultiEdit(MultiEditInput),
+    Glob(GlobInput),
+    Grep(GrepInput),
+    WebFetch(WebFetchInput),
+    WebSearch(WebSearchInput),
+    Task(TaskInput),
+    TodoWrite(TodoWriteInput),
+    NotebookEdit(NotebookEditInput),
+    /// A tool without a typed representation, such as an MCP tool.
+    Other {
+        name: String,
+        input: Value,
+    },
+}
+
+impl BuiltinToolInput {
+    /// Parse a tool input by tool name.
+    ///
+    /// Unknown tool names produce [`BuiltinToolInput::Other`]; known tools
+    /// whose input does not match the expected shape produce an error.
+    pub fn parse(tool_name: &str, input: &Value) -> Result<Self> {
+        Ok(match tool_name {
+            "Bash" => Self::Bash(parse_as(tool_name, input)?),
+            "Read" => Self::Read(parse_as(tool_name, input)?),
+            "Write" => Self::Write(parse_as(tool_name, input)?),
+            "Edit" => Self::Edit(parse_as(tool_name, input)?),
+            "MultiEdit" => Self::MultiEdit(parse_as(tool_name, input)?),
+            "Glob" => Self::Glob(parse_as(tool_name, input)?),
+            "Grep" => Self::Grep(parse_as(tool_name, input)?),
+            "WebFetch" => Self::WebFetch(parse_as(tool_name, input)?),
+            "WebSearch" => Self::WebSearch(parse_as(tool_name, input)?),
+            "Task" => Self::Task(parse_as(tool_name, input)?),
+            "TodoWrite" => Self::TodoWrite(parse_as(tool_name, input)?),
+            "NotebookEdit" => Self::NotebookEdit(parse_as(tool_name, input)?),
+            _ => Self::Other {
+                name: tool_name.to_string(),
+                input: input.clone(),
+            },
+        })
+    }
+
+    /// Get the tool name.
+    pub fn tool_name(&self) -> &str {
+        match self {
+            Self::Bash(_) => "Bash",
+            Self::Read(_) => "Read",
+            Self::Write(_) => "Write",
+            Self::Edit(_) => "Edit",
+            Self::MultiEdit(_) => "MultiEdit",
+            Self::Glob(_) => "Glob",
+            Self::Grep(_) => "Grep",
+            Self::WebFetch(_) => "WebFetch",
+            Self::WebSearch(_) => "WebSearch",
+            Self::Task(_) => "Task",
+            Self::TodoWrite(_) => "TodoWrite",
+            Self::NotebookEdit(_) => "NotebookEdit",
+            Self::Other { name, .. } => name,
+        }
+    }
+
+    /// Get the file path the tool operates on, if any.
+    pub fn file_path(&self) -> Option<&str> {
+        match self {
+            Self::Read(input) => Some(&input.file_path),
+            Self::Write(input) => Some(&input.file_path),
+            Self::Edit(input) => Some(&input.file_path),
+            Self::MultiEdit(input) => Some(&input.file_path),
+            Self::NotebookEdit(input) => Some(&input.notebook_path),
+            _ => None,
+        }
+    }
+
+    /// Convert back into the JSON sent to the CLI, e.g. as `updated_input`.
+    ///
+    /// The input structs hold only strings, numbers, booleans and JSON
+    /// values under string keys, so serializing them cannot fail.
+    pub fn to_value(&self) -> Value {
+        let value = match self {
+            Self::Bash(input) => serde_json::to_value(input),
+            Self::Read(input) => serde_json::to_value(input),
+            Self::Write(input) => serde_json::to_value(input),
+            Self::Edit(input) => serde_json::to_value(input),
+            Self::MultiEdit(input) => serde_json::to_value(input),
+            Self::Glob(input) => serde_json::to_value(input),
+            Self::Grep(input) => serde_json::to_value(input),
+            Self::WebFetch(input) => serde_json::to_value(input),
+            Self::WebSearch(input) => serde_json::to_value(input),
+            Self::Task(input) => serde_json::to_value(input),
+            Self::TodoWrite(input) => serde_json::to_value(input),
+            Self::NotebookEdit(input) => serde_json::to_value(input),
+            Self::Other { input, .. } => Ok(input.clone()),
+        };
+        value.expect("tool inputs serialize to JSON")
+    }
+}
+
+/// Output of the Bash tool.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BashOutput {
+    #[serde(default)]
+    pub stdout: String,
+    #[serde(default)]
+    pub stderr: String,
+    #[serde(default)]
+    pub interrupted: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background_task_id: Option<String>,
+}
+
+/// Output of the Write tool.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WriteOutput {
+    /// Either "create" or "update".
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub file_path: String,
+    pub content: String,
+}
+
+/// Output of the Edit tool.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditOutput {
+    pub file_path: String,
+    pub old_string: String,
+    pub new_string: String,
+    #[serde(default)]
+    pub replace_all: bool,
+}
+
+/// Output of the Glob tool.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GlobOutput {
+    pub filenames: Vec<String>,
+    #[serde(default)]
+    pub num_files: u64,
+    #[serde(default)]
+    pub truncated: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+}
+
+/// Output of the Grep tool.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrepOutput {
+    pub mode: GrepOutputMode,
+    #[serde(default)]
+    pub filenames: Vec<String>,
+    #[serde(default)]
+    pub num_files: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_lines: Option<u64>,
+}
+
+/// Parse a tool response into a typed output.
+///
+/// ```
+/// use claude_agent_sdk::tools::{parse_tool_output, BashOutput};
+/// use serde_json::json;
+///
+/// let output: BashOutput = parse_tool_output(
+///     "Bash",
+///     &json!({"stdout": "hi\n", "stderr": "", "interrupted": false}),
+/// )
+/// .unwrap();
+/// assert_eq!(output.stdout, "hi\n");
+/// ```
+pub fn parse_tool_output<T: DeserializeOwned>(tool_name: &str, response: &Value) -> Result<T> {
+    serde_json::from_value(response.clone()).map_err(|e| ClaudeSDKError::MessageParse {
+        message: format!("Invalid {} tool output: {}", tool_name, e),
+        data: Some(response.clone()),
+    })
+}
+
+fn parse_as<T: DeserializeOwned>(tool_name: &str, input: &Value) -> Result<T> {
+    serde_json::from_value(input.clone()).map_err(|e| ClaudeSDKError::MessageParse {
+        message: format!("Invalid {} tool input: {}", tool_name, e),
+        data: Some(input.clone()),
+    })
+}
+
+impl ToolUseBlock {
+    /// Parse the tool input into a typed built-in tool input.
+    pub fn typed_input(&self) -> Result<BuiltinToolInput> {
+        BuiltinToolInput::parse(&self.name, &self.input)
+    }
+}
+
+impl PreToolUseHookInput {
+    /// Parse the tool input into a typed built-in tool input.
+    pub fn typed_input(&self) -> Result<BuiltinToolInput> {
+        BuiltinToolInput::parse(&self.tool_name, &self.tool_input)
+    }
+}
+
+impl PostToolUseHookInput {
+    /// Parse the tool input into a typed built-in tool input.
+    pub fn typed_input(&self) -> Result<BuiltinToolInput> {
+        BuiltinToolInput::parse(&self.tool_name, &self.tool_input)
+    }
+
+    /// Parse the tool response into a typed output.
+    pub fn typed_response<T: DeserializeOwned>(&self) -> Result<T> {
+        parse_tool_output(&self.tool_name, &self.tool_response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_bash() {
+        let input = BuiltinToolInput::parse(
+            "Bash",
+            &json!({"command": "cargo test", "timeout": 120000, "dangerouslyDisableSandbox": true}),
+        )
+        .unwrap();
+
+        match input {
+            BuiltinToolInput::Bash(bash) => {
+                assert_eq!(bash.command, "cargo test");
+                assert_eq!(bash.timeout, Some(120000));
+                assert_eq!(bash.dangerously_disable_sandbox, Some(true));
+            }
+            other => panic!("Expected Bash, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_file_tools() {
+        let edit = BuiltinToolInput::parse(
+            "Edit",
+            &json!({"file_path": "/a.rs", "old_string": "a", "new_string": "b"}),
+        )
+        .unwrap();
+        assert_eq!(edit.file_path(), Some("/a.rs"));
+
+        let multi = BuiltinToolInput::parse(
+            "MultiEdit",
+            &json!({
+                "file_path": "/b.rs",
+                "edits": [
+                    {"old_string": "a", "new_string": "b"},
+                    {"old_string": "c", "new_string": "d", "replace_all": true}
+                ]
+            }),
+        )
+        .unwrap();
+        match &multi {
+            BuiltinToolInput::MultiEdit(input) => {
+                assert_eq!(input.edits.len(), 2);
+                assert_eq!(input.edits[1].replace_all, Some(true));
+            }
+            other => panic!("Expected MultiEdit, got {:?}", other),
+        }
+        assert_eq!(multi.file_path(), Some("/b.rs"));
+    }
+
+    #[test]
+    fn test_parse_grep_flags() {
+        let input = BuiltinToolInput::parse(
+            "Grep",
+            &json!({
+                "pattern": "fn main",
+                "output_mode": "content",
+                "-n": true,
+                "-C": 2,
+                "type": "rust"
+            }),
+        )
+        .unwrap();
+
+        match input {
+            BuiltinToolInput::Grep(grep) => {
+                assert_eq!(grep.output_mode, Some(GrepOutputMode::Content));
+                assert_eq!(grep.line_numbers, Some(true));
+                assert_eq!(grep.lines_context, Some(2));
+                assert_eq!(grep.file_type, Some("rust".to_string()));
+            }
+            other => panic!("Expected Grep, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_todo_write() {
+        let input = BuiltinToolInput::parse(
+            "TodoWrite",
+            &json!({"todos": [
+                {"content": "Write tests", "status": "in_progress", "activeForm": "Writing tests"}
+            ]}),
+        )
+        .unwrap();
+
+        match input {
+            BuiltinToolInput::TodoWrite(todo) => {
+                assert_eq!(todo.todos[0].status, TodoStatus::InProgress);
+                assert_eq!(todo.todos[0].active_form, Some("Writing tests".to_string()));
+            }
+            other => panic!("Expected TodoWrite, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_unknown_tool() {
+        let input = BuiltinToolInput::parse("mcp__fs__read", &json!({"path": "/tmp"})).unwrap();
+        assert_eq!(input.tool_name(), "mcp__fs__read");
+        assert_eq!(input.to_value(), json!({"path": "/tmp"}));
+    }
+
+    #[test]
+    fn test_parse_invalid_input() {
+        let err = BuiltinToolInput::parse("Write", &json!({"file_path": "/a"})).unwrap_err();
+        match err {
+            ClaudeSDKError::MessageParse { message, data } => {
+                assert!(message.contains("Invalid Write tool input"));
+                assert_eq!(data, Some(json!({"file_path": "/a"})));
+            }
+            other => panic!("Expected MessageParse, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_round_trip_to_value() {
+        let original = json!({"url": "https://example.com", "prompt": "Summarize"});
+        let input = BuiltinToolInput::parse("WebFetch", &original).unwrap();
+        assert_eq!(input.to_value(), original);
+    }
+
+    #[test]
+    fn test_round_trip_keeps_unknown_fields() {
+        let original = json!({
+            "file_path": "/tmp/a.rs",
+            "edits": [{"old_string": "a", "new_string": "b", "future_flag": true}],
+            "reason": "rename"
+        });
+        let input = BuiltinToolInput::parse("MultiEdit", &original).unwrap();
+        match &input {
+            BuiltinToolInput::MultiEdit(multi) => {
+                assert_eq!(multi.extra["reason"], "rename");
+                assert_eq!(multi.edits[0].extra["future_flag"], true);
+            }
+            other => panic!("expected MultiEdit, got {:?}", other),
+        }
+        assert_eq!(input.to_value(), original);
+    }
+
+    #[test]
+    fn test_tool_use_block_typed_input() {
+        let block = ToolUseBlock::new(
+            "tool_1",
+            "Task",
+            json!({"description": "Explore", "prompt": "Find usages", "subagent_type": "Explore"}),
+        );
+
+        match block.typed_input().unwrap() {
+            BuiltinToolInput::Task(task) => assert_eq!(task.subagent_type, "Explore"),
+            other => panic!("Expected Task, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_tool_output() {
+        let output: GlobOutput = parse_tool_output(
+            "Glob",
+            &json!({"filenames": ["/a.rs"], "numFiles": 1, "truncated": false, "durationMs": 3}),
+        )
+        .unwrap();
+        assert_eq!(output.filenames, vec!["/a.rs"]);
+        assert_eq!(output.num_files, 1);
+
+        let err = parse_tool_output::<GrepOutput>("Grep", &json!({})).unwrap_err();
+        assert!(err.to_string().contains("Invalid Grep tool output"));
+    }
+}
@@ -0,0 +1,221 @@
+//! Newline-delimited JSON framing for CLI stdout.
+
+use serde_json::Value;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+
+use crate::error::{ClaudeSDKError, Result};
+
+/// Maximum number of bytes of an offending line included in errors.
+const SNIPPET_LEN: usize = 200;
+
+/// Splits a byte stream into lines and parses each line as one JSON value.
+///
+/// Lines are buffered up to `max_line_size` bytes; anything beyond that is
+/// discarded as it is read. Malformed and oversized lines are reported as
+/// [`ClaudeSDKError::MessageParse`] carrying a snippet of the line, and the
+/// next call continues with the following line. State is kept between calls,
+/// so a read cancelled mid-line resumes where it stopped.
+#[derive(Debug)]
+pub(crate) struct LineFramer {
+    buffer: Vec<u8>,
+    /// Bytes of the current line dropped for exceeding the limit.
+    overflow: usize,
+    max_line_size: usize,
+}
+
+impl LineFramer {
+    pub(crate) fn new(max_line_size: usize) -> Self {
+        Self {
+            buffer: Vec::new(),
+            overflow: 0,
+            max_line_size,
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn max_line_size(&self) -> usize {
+        self.max_line_size
+    }
+
+    /// Read the next JSON line, or `None` at end of stream.
+    pub(crate) async fn next_message<R>(&mut self, reader: &mut R) -> Result<Option<Value>>
+    where
+        R: AsyncBufRead + Unpin,
+    {
+        loop {
+            let complete = self.fill_line(reader).await?;
+            if !complete && self.buffer.is_empty() && self.overflow == 0 {
+                return Ok(None);
+            }
+
+            let result = self.parse_line();
+            self.buffer.clear();
+            self.overflow = 0;
+
+            match result {
+                Ok(Some(value)) => return Ok(Some(value)),
+                Ok(None) if complete => continue,
+                Ok(None) => return Ok(None),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Read until a newline or end of stream; returns whether a newline was found.
+    async fn fill_line<R>(&mut self, reader: &mut R) -> Result<bool>
+    where
+        R: AsyncBufRead + Unpin,
+    {
+        loop {
+            let available = reader.fill_buf().await.map_err(|e| {
+                ClaudeSDKError::CLIConnection(format!("Failed to read from stdout: {}", e))
+            })?;
+            if available.is_empty() {
+                return Ok(false);
+            }
+
+            let (chunk, consumed, complete) = match available.iter().position(|&b| b == b'\n') {
+                Some(pos) => (&available[..pos], pos + 1, true),
+                None => (available, available.len(), false),
+            };
+
+            let room = self.max_line_size.saturating_sub(self.buffer.len());
+            if chunk.len() <= room {
+                self.buffer.extend_from_slice(chunk);
+            } else {
+                self.buffer.extend_from_slice(&chunk[..room]);
+                self.overflow += chunk.len() - room;
+            }
+
+            reader.consume(consumed);
+            if complete {
+                return Ok(true);
+            }
+        }
+    }
+
+    /// Parse the buffered line; `None` for a blank line.
+    fn parse_line(&self) -> Result<Option<Value>> {
+        let line = trim(&self.buffer);
+
+        if self.overflow > 0 {
+            return Err(ClaudeSDKError::message_parse(
+                format!(
+                    "JSON message exceeded maximum buffer size of {} bytes (got {})",
+                    self.max_line_size,
+                    self.buffer.len() + self.overflow
+                ),
+                Some(snippet(line)),
+            ));
+        }
+
+        if line.is_empty() {
+            return Ok(None);
+        }
+
+        serde_json::from_slice(line).map(Some).map_err(|e| {
+            ClaudeSDKError::message_parse(
+                format!("Invalid JSON from CLI stdout: {}", e),
+                Some(snippet(line)),
+            )
+        })
+    }
+}
+
+fn trim(bytes: &[u8]) -> &[u8] {
+    let start = bytes
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(bytes.len());
+    let end = bytes
+        .iter()
+        .rposition(|b| !b.is_ascii_whitespace())
+        .map_or(start, |pos| pos + 1);
+    &bytes[start..end]
+}
+
+fn snippet(line: &[u8]) -> Value {
+    if line.len() <= SNIPPET_LEN {
+        Value::String(String::from_utf8_lossy(line).into_owned())
+    } else {
+        Value::String(format!(
+            "{}...",
+            String::from_utf8_lossy(&line[..SNIPPET_LEN])
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::BufReader;
+
+    async fn collect(input: &[u8], max: usize) -> Vec<Result<Value>> {
+        let mut reader = BufReader::with_capacity(8, input);
+        let mut framer = LineFramer::new(max);
+        let mut out = Vec::new();
+        loop {
+            match framer.next_message(&mut reader).await {
+                Ok(Some(value)) => out.push(Ok(value)),
+                Ok(None) => break,
+                Err(e) => out.push(Err(e)),
+            }
+        }
+        out
+    }
+
+    #[tokio::test]
+    async fn test_parses_lines_and_skips_blanks() {
+        let out = collect(b"{\"a\":1}\n\n  \r\n{\"b\":2}", 1024).await;
+        assert_eq!(out.len(), 2);
+        assert_eq!(out[0].as_ref().unwrap()["a"], 1);
+        assert_eq!(out[1].as_ref().unwrap()["b"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_malformed_line_is_recoverable() {
+        let out = collect(b"{\"a\":1}\nnot json\n{\"b\":2}\n", 1024).await;
+        assert_eq!(out.len(), 3);
+        match &out[1] {
+            Err(ClaudeSDKError::MessageParse { message, data }) => {
+                assert!(message.contains("Invalid JSON"));
+                assert_eq!(data.as_ref().unwrap(), "not json");
+            }
+            other => panic!("expected parse error, got {:?}", other),
+        }
+        assert_eq!(out[2].as_ref().unwrap()["b"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_line_is_bounded_and_recoverable() {
+        let mut input = format!("{{\"big\":\"{}\"}}\n", "x".repeat(500)).into_bytes();
+        input.extend_from_slice(b"{\"ok\":true}\n");
+
+        let out = collect(&input, 64).await;
+        assert_eq!(out.len(), 2);
+        match &out[0] {
+            Err(ClaudeSDKError::MessageParse { message, data }) => {
+                assert!(message.contains("maximum buffer size of 64 bytes (got 510)"));
+                assert_eq!(data.as_ref().unwrap().as_str().unwrap().len(), 64);
+            }
+            other => panic!("expected size error, got {:?}", other),
+        }
+        assert_eq!(out[1].as_ref().unwrap()["ok"], true);
+    }
+
+    #[tokio::test]
+    async fn test_snippet_is_truncated() {
+        let line = format!("{{{}", "y".repeat(400));
+        let out = collect(line.as_bytes(), 1024).await;
+        match &out[0] {
+            Err(ClaudeSDKError::MessageParse {
+                data: Some(data), ..
+            }) => {
+                let text = data.as_str().unwrap();
+                assert!(text.ends_with("..."));
+                assert_eq!(text.len(), SNIPPET_LEN + 3);
+            }
+            other => panic!("expected parse error, got {:?}", other),
+        }
+    }
+}
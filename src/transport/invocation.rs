@@ -0,0 +1,919 @@
+//! Inspectable description of the CLI process the SDK spawns.
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::error::{ClaudeSDKError, Result};
+use crate::types::{
+    AgentDefinition, ClaudeAgentOptions, McpServerConfig, McpServers, SdkBeta, SettingSource,
+    SystemPrompt, Tools,
+};
+
+/// SDK version for environment variable.
+const SDK_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Placeholder shown in place of redacted values.
+const REDACTED: &str = "<redacted>";
+
+/// Key fragments marking an environment variable or JSON field as secret.
+const SECRET_MARKERS: &[&str] = &["KEY", "TOKEN", "SECRET", "PASSWORD", "CREDENTIAL", "AUTH"];
+
+/// Longest single argument passed inline before it is moved to a temp file.
+///
+/// On Windows this is just under the 32,767 character `CreateProcess` bound.
+#[cfg(windows)]
+pub(crate) const INLINE_ARG_LIMIT: usize = 32_000;
+#[cfg(not(windows))]
+pub(crate) const INLINE_ARG_LIMIT: usize = 100_000;
+
+/// Longest total command line before the largest arguments are moved to temp files.
+#[cfg(windows)]
+pub(crate) const COMMAND_LENGTH_LIMIT: usize = 8_000;
+#[cfg(not(windows))]
+pub(crate) const COMMAND_LENGTH_LIMIT: usize = 1_000_000;
+
+/// The program, arguments, environment and working directory used to start
+/// Claude Code.
+///
+/// Built from [`ClaudeAgentOptions`], it can be logged, snapshot-tested or
+/// used for a dry run before anything is spawned. `Display` renders a shell
+/// command line with secrets redacted.
+///
+/// ```
+/// use claude_agent_sdk::transport::CliInvocation;
+/// use claude_agent_sdk::ClaudeAgentOptions;
+///
+/// let options = ClaudeAgentOptions::builder().model("claude-sonnet-4-5").build();
+/// let invocation = CliInvocation::from_options("/usr/bin/claude", &options)
+///     .with_print_prompt("Hello");
+///
+/// assert!(invocation.args.windows(2).any(|w| w == ["--model", "claude-sonnet-4-5"]));
+/// assert_eq!(invocation.args.last().map(String::as_str), Some("Hello"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliInvocation {
+    /// Path to the CLI executable.
+    pub program: PathBuf,
+    /// Arguments passed to the CLI.
+    pub args: Vec<String>,
+    /// Environment variables set on top of the inherited environment.
+    pub env: HashMap<String, String>,
+    /// Working directory for the process.
+    pub cwd: Option<PathBuf>,
+}
+
+impl CliInvocation {
+    /// Build the invocation for the options, without prompt delivery flags.
+    pub fn from_options(program: impl Into<PathBuf>, options: &ClaudeAgentOptions) -> Self {
+        let mut env = options.env.clone();
+        env.insert("CLAUDE_CODE_ENTRYPOINT".to_string(), "sdk-rust".to_string());
+        env.insert(
+            "CLAUDE_AGENT_SDK_VERSION".to_string(),
+            SDK_VERSION.to_string(),
+        );
+
+        if options.enable_file_checkpointing {
+            env.insert(
+                "CLAUDE_CODE_ENABLE_SDK_FILE_CHECKPOINTING".to_string(),
+                "true".to_string(),
+            );
+        }
+
+        if let Some(ref cwd) = options.cwd {
+            env.insert("PWD".to_string(), cwd.to_string_lossy().to_string());
+        }
+
+        Self {
+            program: program.into(),
+            args: build_args(options),
+            env,
+            cwd: options.cwd.clone(),
+        }
+    }
+
+    /// Deliver the prompt as a one-shot `--print` argument.
+    pub fn with_print_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.args
+            .extend(["--print".to_string(), "--".to_string(), prompt.into()]);
+        self
+    }
+
+    /// Read input as a stream of JSON messages on stdin.
+    pub fn with_streaming_input(mut self) -> Self {
+        self.args
+            .extend(["--input-format".to_string(), "stream-json".to_string()]);
+        self
+    }
+
+    /// Get the full argv, starting with the program.
+    pub fn argv(&self) -> Vec<String> {
+        std::iter::once(self.program.to_string_lossy().to_string())
+            .chain(self.args.iter().cloned())
+            .collect()
+    }
+
+    /// Move oversized arguments into temp files, passing their paths instead.
+    ///
+    /// Only flags with a documented file form are moved: `--mcp-config` and
+    /// `--settings` take the path directly, and `--system-prompt` becomes
+    /// `--system-prompt-file`. Any argument longer than `arg_limit` is moved,
+    /// then the largest remaining ones until the command fits in
+    /// `command_limit`. The returned files are deleted when dropped.
+    ///
+    /// `--agents` and `--json-schema` have no file form; in streaming mode
+    /// [`defer_to_initialize`](Self::defer_to_initialize) takes them off the
+    /// command line first. A value of theirs still longer than `arg_limit` is
+    /// an [`ClaudeSDKError::InvalidConfig`].
+    pub(crate) fn spill_large_args(
+        &mut self,
+        arg_limit: usize,
+        command_limit: usize,
+    ) -> Result<Vec<TempArgFile>> {
+        let files = self
+            .spill_movable_args(arg_limit, command_limit)
+            .map_err(|e| {
+                ClaudeSDKError::CLIConnection(format!(
+                    "Failed to write oversized arguments to temp files: {}",
+                    e
+                ))
+            })?;
+
+        for i in 1..self.args.len() {
+            let flag = &self.args[i - 1];
+            if is_inline_only(flag) && self.args[i].len() > arg_limit {
+                return Err(ClaudeSDKError::InvalidConfig(format!(
+                    "{} is {} bytes, over the {} byte argument limit; it cannot be passed through a file",
+                    flag,
+                    self.args[i].len(),
+                    arg_limit
+                )));
+            }
+        }
+
+        Ok(files)
+    }
+
+    /// Remove `--agents` and `--json-schema` values longer than `arg_limit`,
+    /// returning them to send in the initialize control request instead.
+    ///
+    /// Only streaming sessions send an initialize request, so this is not
+    /// used for `--print` invocations.
+    pub(crate) fn defer_to_initialize(&mut self, arg_limit: usize) -> InitializeConfig {
+        let mut config = InitializeConfig::default();
+        let mut i = 1;
+        while i < self.args.len() {
+            let flag = self.args[i - 1].as_str();
+            if !is_inline_only(flag) || self.args[i].len() <= arg_limit {
+                i += 1;
+                continue;
+            }
+            let value = serde_json::from_str(&self.args[i])
+                .unwrap_or_else(|_| Value::String(self.args[i].clone()));
+            if flag == "--agents" {
+                config.agents = Some(value);
+            } else {
+                config.json_schema = Some(value);
+            }
+            self.args.drain(i - 1..=i);
+        }
+        config
+    }
+
+    fn spill_movable_args(
+        &mut self,
+        arg_limit: usize,
+        command_limit: usize,
+    ) -> std::io::Result<Vec<TempArgFile>> {
+        let mut files = Vec::new();
+
+        let candidates: Vec<usize> = (1..self.args.len())
+            .filter(|&i| spill_style(&self.args[i - 1]).is_some())
+            .collect();
+
+        for &i in &candidates {
+            if self.args[i].len() > arg_limit {
+                files.push(self.spill(i)?);
+            }
+        }
+
+        let mut remaining: Vec<usize> = candidates
+            .into_iter()
+            .filter(|&i| spill_style(&self.args[i - 1]).is_some() && !is_spilled(&files, i))
+            .collect();
+        remaining.sort_by_key(|&i| std::cmp::Reverse(self.args[i].len()));
+
+        for i in remaining {
+            if self.command_length() <= command_limit {
+                break;
+            }
+            files.push(self.spill(i)?);
+        }
+
+        Ok(files)
+    }
+
+    /// Write the value at `index` to a temp file and point its flag at it.
+    fn spill(&mut self, index: usize) -> std::io::Result<TempArgFile> {
+        let flag = self.args[index - 1].clone();
+        let file = TempArgFile::create(&flag, &self.args[index], index)?;
+        let path = file.path().to_string_lossy().to_string();
+
+        match spill_style(&flag) {
+            Some(SpillStyle::Path) => self.args[index] = path,
+            Some(SpillStyle::FileFlag(file_flag)) => {
+                self.args[index - 1] = file_flag.to_string();
+                self.args[index] = path;
+            }
+            None => {}
+        }
+        Ok(file)
+    }
+
+    fn command_length(&self) -> usize {
+        self.program.as_os_str().len() + self.args.iter().map(|a| a.len() + 1).sum::<usize>()
+    }
+
+    /// Create a process command for this invocation.
+    ///
+    /// Stdio is left at its defaults for the caller to configure.
+    pub fn to_command(&self) -> tokio::process::Command {
+        let mut command = tokio::process::Command::new(&self.program);
+        command.args(&self.args).envs(&self.env);
+        if let Some(ref cwd) = self.cwd {
+            command.current_dir(cwd);
+        }
+        command
+    }
+}
+
+impl fmt::Display for CliInvocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(ref cwd) = self.cwd {
+            write!(f, "cd {} && ", shell_quote(&cwd.to_string_lossy()))?;
+        }
+
+        let mut keys: Vec<&String> = self.env.keys().collect();
+        keys.sort();
+        for key in keys {
+            let value = if is_secret_key(key) {
+                REDACTED
+            } else {
+                &self.env[key]
+            };
+            write!(f, "{}={} ", key, shell_quote(value))?;
+        }
+
+        write!(f, "{}", shell_quote(&self.program.to_string_lossy()))?;
+        for arg in &self.args {
+            write!(f, " {}", shell_quote(&redact_arg(arg)))?;
+        }
+        Ok(())
+    }
+}
+
+/// Configuration moved from the command line to the initialize request.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct InitializeConfig {
+    pub agents: Option<Value>,
+    pub json_schema: Option<Value>,
+}
+
+/// How a flag's value is replaced once written to a file.
+enum SpillStyle {
+    /// The flag accepts a file path in place of inline JSON.
+    Path,
+    /// A sibling flag takes the file path.
+    FileFlag(&'static str),
+}
+
+fn spill_style(flag: &str) -> Option<SpillStyle> {
+    match flag {
+        "--mcp-config" | "--settings" => Some(SpillStyle::Path),
+        "--system-prompt" => Some(SpillStyle::FileFlag("--system-prompt-file")),
+        _ => None,
+    }
+}
+
+/// Whether the flag takes JSON that can only be passed inline.
+fn is_inline_only(flag: &str) -> bool {
+    matches!(flag, "--agents" | "--json-schema")
+}
+
+fn is_spilled(files: &[TempArgFile], index: usize) -> bool {
+    files.iter().any(|file| file.index == index)
+}
+
+/// A temp file holding an argument value, removed when dropped.
+///
+/// Values can carry secrets such as MCP server credentials, so the file is
+/// created exclusively under a random name and readable only by its owner.
+#[derive(Debug)]
+pub(crate) struct TempArgFile {
+    file: tempfile::NamedTempFile,
+    index: usize,
+}
+
+impl TempArgFile {
+    fn create(flag: &str, contents: &str, index: usize) -> std::io::Result<Self> {
+        let extension = if flag == "--system-prompt" {
+            ".txt"
+        } else {
+            ".json"
+        };
+        let prefix = format!("claude-agent-sdk-{}-", flag.trim_start_matches('-'));
+        let mut file = tempfile::Builder::new()
+            .prefix(&prefix)
+            .suffix(extension)
+            .tempfile()?;
+        file.write_all(contents.as_bytes())?;
+        file.flush()?;
+        Ok(Self { file, index })
+    }
+
+    /// Path of the file on disk.
+    pub(crate) fn path(&self) -> &Path {
+        self.file.path()
+    }
+}
+
+fn is_secret_key(key: &str) -> bool {
+    let upper = key.to_ascii_uppercase();
+    SECRET_MARKERS.iter().any(|marker| upper.contains(marker))
+}
+
+/// Redact secrets inside JSON arguments such as `--mcp-config` and `--settings`.
+fn redact_arg(arg: &str) -> String {
+    let trimmed = arg.trim_start();
+    if !trimmed.starts_with('{') {
+        return arg.to_string();
+    }
+    match serde_json::from_str::<Value>(arg) {
+        Ok(mut value) => {
+            redact_value(&mut value);
+            value.to_string()
+        }
+        Err(_) => arg.to_string(),
+    }
+}
+
+fn redact_value(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                if key == "env" || key == "headers" {
+                    if let Value::Object(entries) = field {
+                        for entry in entries.values_mut() {
+                            *entry = Value::String(REDACTED.to_string());
+                        }
+                        continue;
+                    }
+                }
+                if is_secret_key(key) && field.is_string() {
+                    *field = Value::String(REDACTED.to_string());
+                } else {
+                    redact_value(field);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_value),
+        _ => {}
+    }
+}
+
+fn shell_quote(value: &str) -> String {
+    if !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:,@+".contains(c))
+    {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+/// Build the CLI flags for the options, excluding prompt delivery.
+fn build_args(options: &ClaudeAgentOptions) -> Vec<String> {
+    let mut cmd = vec![
+        "--output-format".to_string(),
+        "stream-json".to_string(),
+        "--verbose".to_string(),
+    ];
+
+    // System prompt
+    match &options.system_prompt {
+        None => {
+            cmd.extend(["--system-prompt".to_string(), String::new()]);
+        }
+        Some(SystemPrompt::Text(text)) => {
+            cmd.extend(["--system-prompt".to_string(), text.clone()]);
+        }
+        Some(SystemPrompt::Preset(preset)) => {
+            if preset.preset_type == "preset" {
+                if let Some(ref append) = preset.append {
+                    cmd.extend(["--append-system-prompt".to_string(), append.clone()]);
+                }
+            }
+        }
+    }
+
+    // Tools
+    if let Some(ref tools) = options.tools {
+        match tools {
+            Tools::List(list) => {
+                if list.is_empty() {
+                    cmd.extend(["--tools".to_string(), String::new()]);
+                } else {
+                    cmd.extend(["--tools".to_string(), list.join(",")]);
+                }
+            }
+            Tools::Preset(_) => {
+                cmd.extend(["--tools".to_string(), "default".to_string()]);
+            }
+        }
+    }
+
+    // Allowed tools
+    if !options.allowed_tools.is_empty() {
+        cmd.extend([
+            "--allowedTools".to_string(),
+            options.allowed_tools.join(","),
+        ]);
+    }
+
+    // Max turns
+    if let Some(max_turns) = options.max_turns {
+        cmd.extend(["--max-turns".to_string(), max_turns.to_string()]);
+    }
+
+    // Max budget
+    if let Some(budget) = options.max_budget_usd {
+        cmd.extend(["--max-budget-usd".to_string(), budget.to_string()]);
+    }
+
+    // Disallowed tools
+    if !options.disallowed_tools.is_empty() {
+        cmd.extend([
+            "--disallowedTools".to_string(),
+            options.disallowed_tools.join(","),
+        ]);
+    }
+
+    // Model
+    if let Some(ref model) = options.model {
+        cmd.extend(["--model".to_string(), model.clone()]);
+    }
+
+    // Fallback model
+    if let Some(ref model) = options.fallback_model {
+        cmd.extend(["--fallback-model".to_string(), model.clone()]);
+    }
+
+    // Betas
+    if !options.betas.is_empty() {
+        let betas: Vec<String> = options
+            .betas
+            .iter()
+            .map(|b| match b {
+                SdkBeta::Context1m20250807 => "context-1m-2025-08-07".to_string(),
+            })
+            .collect();
+        cmd.extend(["--betas".to_string(), betas.join(",")]);
+    }
+
+    // Permission prompt tool name
+    if let Some(ref name) = options.permission_prompt_tool_name {
+        cmd.extend(["--permission-prompt-tool".to_string(), name.clone()]);
+    }
+
+    // Permission mode
+    if let Some(mode) = options.permission_mode {
+        cmd.extend(["--permission-mode".to_string(), mode.to_string()]);
+    }
+
+    // Continue conversation
+    if options.continue_conversation {
+        cmd.push("--continue".to_string());
+    }
+
+    // Resume session
+    if let Some(ref session) = options.resume {
+        cmd.extend(["--resume".to_string(), session.clone()]);
+    }
+
+    // Settings
+    if let Some(ref settings) = build_settings_value(options) {
+        cmd.extend(["--settings".to_string(), settings.clone()]);
+    }
+
+    // Add directories
+    for dir in &options.add_dirs {
+        cmd.extend(["--add-dir".to_string(), dir.to_string_lossy().to_string()]);
+    }
+
+    // MCP servers
+    if let Some(ref mcp_servers) = options.mcp_servers {
+        match mcp_servers {
+            McpServers::Map(map) => {
+                if !map.is_empty() {
+                    // Filter out SDK server instances
+                    let servers_for_cli: HashMap<String, &McpServerConfig> = map
+                        .iter()
+                        .filter(|(_, config)| !config.is_sdk())
+                        .map(|(k, v)| (k.clone(), v))
+                        .collect();
+
+                    if !servers_for_cli.is_empty() {
+                        let config = serde_json::json!({ "mcpServers": servers_for_cli });
+                        cmd.extend(["--mcp-config".to_string(), config.to_string()]);
+                    }
+                }
+            }
+            McpServers::Path(path) => {
+                cmd.extend([
+                    "--mcp-config".to_string(),
+                    path.to_string_lossy().to_string(),
+                ]);
+            }
+            McpServers::Json(json) => {
+                cmd.extend(["--mcp-config".to_string(), json.clone()]);
+            }
+        }
+    }
+
+    // Include partial messages
+    if options.include_partial_messages {
+        cmd.push("--include-partial-messages".to_string());
+    }
+
+    // Fork session
+    if options.fork_session {
+        cmd.push("--fork-session".to_string());
+    }
+
+    // Agents
+    if let Some(ref agents) = options.agents {
+        let agents_json = serialize_agents(agents);
+        cmd.extend(["--agents".to_string(), agents_json]);
+    }
+
+    // Setting sources
+    let sources = if let Some(ref sources) = options.setting_sources {
+        sources
+            .iter()
+            .map(|s| match s {
+                SettingSource::User => "user",
+                SettingSource::Project => "project",
+                SettingSource::Local => "local",
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    } else {
+        String::new()
+    };
+    cmd.extend(["--setting-sources".to_string(), sources]);
+
+    // Plugins
+    for plugin in &options.plugins {
+        if plugin.plugin_type == "local" {
+            cmd.extend(["--plugin-dir".to_string(), plugin.path.clone()]);
+        }
+    }
+
+    // Extra args
+    for (flag, value) in &options.extra_args {
+        if let Some(val) = value {
+            cmd.extend([format!("--{}", flag), val.clone()]);
+        } else {
+            cmd.push(format!("--{}", flag));
+        }
+    }
+
+    // Max thinking tokens
+    if let Some(tokens) = options.max_thinking_tokens {
+        cmd.extend(["--max-thinking-tokens".to_string(), tokens.to_string()]);
+    }
+
+    // Output format (JSON schema)
+    if let Some(ref format) = options.output_format {
+        if let Some(schema) = format.get("schema") {
+            if format.get("type") == Some(&serde_json::json!("json_schema")) {
+                cmd.extend(["--json-schema".to_string(), schema.to_string()]);
+            }
+        }
+    }
+
+    cmd
+}
+
+/// Build settings value, merging sandbox settings if provided.
+pub(crate) fn build_settings_value(options: &ClaudeAgentOptions) -> Option<String> {
+    let has_settings = options.settings.is_some();
+    let has_sandbox = options.sandbox.is_some();
+
+    if !has_settings && !has_sandbox {
+        return None;
+    }
+
+    // If only settings path and no sandbox, pass through as-is
+    if has_settings && !has_sandbox {
+        return options.settings.clone();
+    }
+
+    // If we have sandbox settings, we need to merge into a JSON object
+    let mut settings_obj: serde_json::Map<String, Value> = serde_json::Map::new();
+
+    if let Some(ref settings_str) = options.settings {
+        let trimmed = settings_str.trim();
+        if trimmed.starts_with('{') && trimmed.ends_with('}') {
+            // Parse JSON string
+            if let Ok(parsed) = serde_json::from_str::<serde_json::Map<String, Value>>(trimmed) {
+                settings_obj = parsed;
+            }
+        } else {
+            // It's a file path - read and parse
+            if let Ok(content) = std::fs::read_to_string(trimmed) {
+                if let Ok(parsed) = serde_json::from_str::<serde_json::Map<String, Value>>(&content)
+                {
+                    settings_obj = parsed;
+                }
+            }
+        }
+    }
+
+    // Merge sandbox settings
+    if let Some(ref sandbox) = options.sandbox {
+        settings_obj.insert(
+            "sandbox".to_string(),
+            serde_json::to_value(sandbox).unwrap_or_default(),
+        );
+    }
+
+    Some(serde_json::to_string(&settings_obj).unwrap_or_default())
+}
+
+/// Serialize agents to JSON.
+fn serialize_agents(agents: &HashMap<String, AgentDefinition>) -> String {
+    let agents_map: HashMap<String, Value> = agents
+        .iter()
+        .map(|(name, def)| {
+            let mut obj = serde_json::Map::new();
+            obj.insert(
+                "description".to_string(),
+                Value::String(def.description.clone()),
+            );
+            obj.insert("prompt".to_string(), Value::String(def.prompt.clone()));
+            if let Some(ref tools) = def.tools {
+                obj.insert("tools".to_string(), serde_json::json!(tools));
+            }
+            if let Some(ref model) = def.model {
+                obj.insert("model".to_string(), Value::String(model.clone()));
+            }
+            (name.clone(), Value::Object(obj))
+        })
+        .collect();
+    serde_json::to_string(&agents_map).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options_with_env() -> ClaudeAgentOptions {
+        let mut env = HashMap::new();
+        env.insert("ANTHROPIC_API_KEY".to_string(), "sk-secret".to_string());
+        env.insert("DEBUG".to_string(), "1".to_string());
+        ClaudeAgentOptions::builder()
+            .env(env)
+            .cwd("/work dir")
+            .model("claude-sonnet-4-5")
+            .build()
+    }
+
+    #[test]
+    fn test_from_options_env() {
+        let invocation = CliInvocation::from_options("/usr/bin/claude", &options_with_env());
+
+        assert_eq!(invocation.env["CLAUDE_CODE_ENTRYPOINT"], "sdk-rust");
+        assert_eq!(invocation.env["CLAUDE_AGENT_SDK_VERSION"], SDK_VERSION);
+        assert_eq!(invocation.env["PWD"], "/work dir");
+        assert_eq!(invocation.env["ANTHROPIC_API_KEY"], "sk-secret");
+        assert!(!invocation
+            .env
+            .contains_key("CLAUDE_CODE_ENABLE_SDK_FILE_CHECKPOINTING"));
+        assert_eq!(invocation.cwd, Some(PathBuf::from("/work dir")));
+    }
+
+    #[test]
+    fn test_argv_and_prompt_delivery() {
+        let options = ClaudeAgentOptions::default();
+        let print =
+            CliInvocation::from_options("/usr/bin/claude", &options).with_print_prompt("hi");
+        let argv = print.argv();
+        assert_eq!(argv[0], "/usr/bin/claude");
+        assert_eq!(argv[1..4], ["--output-format", "stream-json", "--verbose"]);
+        assert_eq!(argv[argv.len() - 3..], ["--print", "--", "hi"]);
+
+        let streaming =
+            CliInvocation::from_options("/usr/bin/claude", &options).with_streaming_input();
+        assert_eq!(
+            streaming.args[streaming.args.len() - 2..],
+            ["--input-format", "stream-json"]
+        );
+    }
+
+    #[test]
+    fn test_display_redacts_env() {
+        let invocation = CliInvocation::from_options("/usr/bin/claude", &options_with_env())
+            .with_print_prompt("what's up");
+        let rendered = invocation.to_string();
+
+        assert!(rendered.starts_with("cd '/work dir' && "));
+        assert!(rendered.contains("ANTHROPIC_API_KEY='<redacted>'"));
+        assert!(rendered.contains("DEBUG=1"));
+        assert!(!rendered.contains("sk-secret"));
+        assert!(rendered.contains("--model claude-sonnet-4-5"));
+        assert!(rendered.ends_with("--print -- 'what'\\''s up'"));
+    }
+
+    #[test]
+    fn test_redact_json_arg() {
+        let arg = r#"{"mcpServers":{"db":{"command":"srv","env":{"DB_URL":"postgres://u:p@h"},"headers":{"X":"y"},"apiToken":"t","args":["a"]}}}"#;
+        let redacted: Value = serde_json::from_str(&redact_arg(arg)).unwrap();
+        let server = &redacted["mcpServers"]["db"];
+
+        assert_eq!(server["command"], "srv");
+        assert_eq!(server["env"]["DB_URL"], REDACTED);
+        assert_eq!(server["headers"]["X"], REDACTED);
+        assert_eq!(server["apiToken"], REDACTED);
+        assert_eq!(server["args"][0], "a");
+    }
+
+    #[test]
+    fn test_redact_arg_leaves_plain_text() {
+        assert_eq!(redact_arg("plain"), "plain");
+        assert_eq!(redact_arg("{not json"), "{not json");
+    }
+
+    #[test]
+    fn test_spill_large_args() {
+        let mut agents = HashMap::new();
+        agents.insert(
+            "reviewer".to_string(),
+            AgentDefinition::new("Reviews code", "x"),
+        );
+        let mcp_json = format!(
+            r#"{{"mcpServers":{{"fs":{{"command":"{}"}}}}}}"#,
+            "y".repeat(200)
+        );
+        let options = ClaudeAgentOptions::builder()
+            .system_prompt("short")
+            .agents(agents)
+            .mcp_servers(McpServers::Json(mcp_json.clone()))
+            .build();
+        let mut invocation = CliInvocation::from_options("/usr/bin/claude", &options);
+        let agents_json = serialize_agents(&options.agents.clone().unwrap());
+
+        let files = invocation.spill_large_args(100, usize::MAX).unwrap();
+        assert_eq!(files.len(), 1);
+
+        let index = invocation
+            .args
+            .iter()
+            .position(|a| a == "--mcp-config")
+            .unwrap();
+        let path = files[0].path().to_path_buf();
+        assert_eq!(invocation.args[index + 1], path.display().to_string());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), mcp_json);
+
+        // Flags without a documented file form stay inline
+        assert!(invocation.args.contains(&agents_json));
+        assert!(invocation.args.contains(&"short".to_string()));
+
+        drop(files);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_spill_rejects_oversized_inline_only_args() {
+        let mut agents = HashMap::new();
+        agents.insert(
+            "reviewer".to_string(),
+            AgentDefinition::new("Reviews code", "x".repeat(200)),
+        );
+        let options = ClaudeAgentOptions::builder().agents(agents).build();
+        let mut invocation = CliInvocation::from_options("/usr/bin/claude", &options);
+        match invocation.spill_large_args(100, usize::MAX) {
+            Err(ClaudeSDKError::InvalidConfig(message)) => assert!(message.contains("--agents")),
+            other => panic!("expected invalid config error, got {:?}", other),
+        }
+
+        let options = ClaudeAgentOptions::builder()
+            .output_format(serde_json::json!({
+                "type": "json_schema",
+                "schema": {"description": "s".repeat(200)},
+            }))
+            .build();
+        let mut invocation = CliInvocation::from_options("/usr/bin/claude", &options);
+        let err = invocation.spill_large_args(100, usize::MAX).unwrap_err();
+        assert!(matches!(err, ClaudeSDKError::InvalidConfig(_)));
+    }
+
+    #[test]
+    fn test_defer_oversized_args_to_initialize() {
+        let mut agents = HashMap::new();
+        agents.insert(
+            "reviewer".to_string(),
+            AgentDefinition::new("Reviews code", "x".repeat(200)),
+        );
+        let schema = serde_json::json!({"description": "s".repeat(200)});
+        let options = ClaudeAgentOptions::builder()
+            .agents(agents)
+            .output_format(serde_json::json!({"type": "json_schema", "schema": schema}))
+            .model("claude-sonnet-4-5")
+            .build();
+        let mut invocation =
+            CliInvocation::from_options("/usr/bin/claude", &options).with_streaming_input();
+
+        let config = invocation.defer_to_initialize(100);
+        assert_eq!(
+            config.agents.unwrap()["reviewer"]["prompt"],
+            "x".repeat(200)
+        );
+        assert_eq!(config.json_schema, Some(schema));
+        assert!(!invocation.args.contains(&"--agents".to_string()));
+        assert!(!invocation.args.contains(&"--json-schema".to_string()));
+        assert!(invocation
+            .args
+            .windows(2)
+            .any(|w| w == ["--model", "claude-sonnet-4-5"]));
+
+        // Nothing is left for spilling to reject
+        assert!(invocation.spill_large_args(100, usize::MAX).is_ok());
+    }
+
+    #[test]
+    fn test_spill_to_fit_command_length() {
+        let options = ClaudeAgentOptions::builder()
+            .system_prompt("p".repeat(500))
+            .build();
+        let mut invocation = CliInvocation::from_options("/usr/bin/claude", &options);
+
+        let files = invocation.spill_large_args(usize::MAX, 200).unwrap();
+        assert_eq!(files.len(), 1);
+        assert!(!invocation.args.contains(&"--system-prompt".to_string()));
+
+        let index = invocation
+            .args
+            .iter()
+            .position(|a| a == "--system-prompt-file")
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&invocation.args[index + 1]).unwrap(),
+            "p".repeat(500)
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_spilled_file_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let options = ClaudeAgentOptions::builder()
+            .system_prompt("p".repeat(500))
+            .build();
+        let mut invocation = CliInvocation::from_options("/usr/bin/claude", &options);
+        let files = invocation.spill_large_args(100, usize::MAX).unwrap();
+
+        let mode = std::fs::metadata(files[0].path())
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn test_spill_leaves_small_args_inline() {
+        let options = ClaudeAgentOptions::builder().system_prompt("short").build();
+        let mut invocation = CliInvocation::from_options("/usr/bin/claude", &options);
+        let before = invocation.args.clone();
+
+        let files = invocation
+            .spill_large_args(INLINE_ARG_LIMIT, COMMAND_LENGTH_LIMIT)
+            .unwrap();
+        assert!(files.is_empty());
+        assert_eq!(invocation.args, before);
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("--verbose"), "--verbose");
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_quote("a b"), "'a b'");
+        assert_eq!(shell_quote("a>b"), "'a>b'");
+        assert_eq!(shell_quote("<x>"), "'<x>'");
+    }
+}
@@ -0,0 +1,405 @@
+//! Locating the Claude Code CLI and detecting its version.
+
+use std::env;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::error::{ClaudeSDKError, Result};
+use crate::types::{ClaudeAgentOptions, CliVersion, CliVersionPolicy};
+
+/// Environment variable naming the CLI executable.
+pub const CLI_PATH_ENV_VAR: &str = "CLAUDE_CLI_PATH";
+
+/// Environment variable that disables version detection.
+const SKIP_VERSION_CHECK_ENV_VAR: &str = "CLAUDE_AGENT_SDK_SKIP_VERSION_CHECK";
+
+/// How long `claude -v` may take before the version counts as undetected.
+const VERSION_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Executable name searched for in each location.
+#[cfg(windows)]
+const CLI_BINARY: &str = "claude.exe";
+#[cfg(not(windows))]
+const CLI_BINARY: &str = "claude";
+
+/// Looks up an environment variable; see [`CliLocator::with_env_lookup`].
+pub type EnvLookupFn = Arc<dyn Fn(&str) -> Option<OsString> + Send + Sync>;
+
+/// A place the locator looks for the CLI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CliSource {
+    /// The path set with [`CliLocator::with_explicit_path`].
+    Explicit,
+    /// The path in the locator's environment variable.
+    EnvVar,
+    /// The bundled CLI directory.
+    Bundled,
+    /// The `PATH` environment variable.
+    Path,
+    /// Common install directories such as `~/.local/bin`.
+    KnownDirs,
+}
+
+/// A located CLI executable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliInfo {
+    /// Path to the executable.
+    pub path: PathBuf,
+    /// Version reported by `claude -v`, if it could be detected.
+    pub version: Option<CliVersion>,
+}
+
+/// Finds the Claude Code CLI and checks its version.
+///
+/// Sources are searched in order; the default order is explicit path,
+/// `CLAUDE_CLI_PATH`, bundled directory, `PATH`, then known install
+/// directories.
+///
+/// ```no_run
+/// # async fn example() -> claude_agent_sdk::Result<()> {
+/// use claude_agent_sdk::transport::{CliLocator, CliSource};
+///
+/// let info = CliLocator::new()
+///     .with_search_order(vec![CliSource::EnvVar, CliSource::Path])
+///     .locate()
+///     .await?;
+/// println!("{} {:?}", info.path.display(), info.version);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct CliLocator {
+    explicit_path: Option<PathBuf>,
+    env_var: String,
+    bundled_dir: Option<PathBuf>,
+    search_order: Vec<CliSource>,
+    version_policy: CliVersionPolicy,
+    env_lookup: EnvLookupFn,
+}
+
+impl std::fmt::Debug for CliLocator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CliLocator")
+            .field("explicit_path", &self.explicit_path)
+            .field("env_var", &self.env_var)
+            .field("bundled_dir", &self.bundled_dir)
+            .field("search_order", &self.search_order)
+            .field("version_policy", &self.version_policy)
+            .finish()
+    }
+}
+
+impl Default for CliLocator {
+    fn default() -> Self {
+        Self {
+            explicit_path: None,
+            env_var: CLI_PATH_ENV_VAR.to_string(),
+            bundled_dir: None,
+            search_order: vec![
+                CliSource::Explicit,
+                CliSource::EnvVar,
+                CliSource::Bundled,
+                CliSource::Path,
+                CliSource::KnownDirs,
+            ],
+            version_policy: CliVersionPolicy::default(),
+            env_lookup: Arc::new(|name: &str| env::var_os(name)),
+        }
+    }
+}
+
+impl CliLocator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a locator from `cli_path`, `bundled_cli_dir` and `cli_version_policy`.
+    pub fn from_options(options: &ClaudeAgentOptions) -> Self {
+        let mut locator = Self::new().with_version_policy(options.cli_version_policy.clone());
+        locator.explicit_path = options.cli_path.clone();
+        locator.bundled_dir = options.bundled_cli_dir.clone();
+        locator
+    }
+
+    pub fn with_explicit_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.explicit_path = Some(path.into());
+        self
+    }
+
+    pub fn with_env_var(mut self, name: impl Into<String>) -> Self {
+        self.env_var = name.into();
+        self
+    }
+
+    pub fn with_bundled_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.bundled_dir = Some(dir.into());
+        self
+    }
+
+    pub fn with_search_order(mut self, order: Vec<CliSource>) -> Self {
+        self.search_order = order;
+        self
+    }
+
+    pub fn with_version_policy(mut self, policy: CliVersionPolicy) -> Self {
+        self.version_policy = policy;
+        self
+    }
+
+    /// Read environment variables through `lookup` instead of the process
+    /// environment.
+    pub fn with_env_lookup<F>(mut self, lookup: F) -> Self
+    where
+        F: Fn(&str) -> Option<OsString> + Send + Sync + 'static,
+    {
+        self.env_lookup = Arc::new(lookup);
+        self
+    }
+
+    /// Find the CLI executable without checking its version.
+    ///
+    /// An explicit path is returned as-is so a missing file is reported when
+    /// the process is spawned.
+    pub fn find(&self) -> Result<PathBuf> {
+        for source in &self.search_order {
+            if let Some(path) = self.find_in(*source) {
+                return Ok(path);
+            }
+        }
+        Err(ClaudeSDKError::cli_not_found(None))
+    }
+
+    /// Find the CLI, detect its version and apply the version policy.
+    pub async fn locate(&self) -> Result<CliInfo> {
+        let path = self.find()?;
+        self.inspect(path).await
+    }
+
+    /// Detect the version of the CLI at `path` and apply the version policy.
+    ///
+    /// Detection is skipped when `CLAUDE_AGENT_SDK_SKIP_VERSION_CHECK` is set
+    /// and the default policy is in use; a policy set with
+    /// [`with_version_policy`](Self::with_version_policy) is always applied.
+    pub async fn inspect(&self, path: PathBuf) -> Result<CliInfo> {
+        if (self.env_lookup)(SKIP_VERSION_CHECK_ENV_VAR).is_some()
+            && self.version_policy == CliVersionPolicy::default()
+        {
+            return Ok(CliInfo {
+                path,
+                version: None,
+            });
+        }
+
+        let version = detect_version(&path).await;
+        match version {
+            Some(version) => self.version_policy.check(version)?,
+            None => self.version_policy.check_undetected()?,
+        }
+        Ok(CliInfo { path, version })
+    }
+
+    fn find_in(&self, source: CliSource) -> Option<PathBuf> {
+        match source {
+            CliSource::Explicit => self.explicit_path.clone(),
+            CliSource::EnvVar => (self.env_lookup)(&self.env_var)
+                .filter(|value| !value.is_empty())
+                .map(PathBuf::from),
+            CliSource::Bundled => self
+                .bundled_dir
+                .as_ref()
+                .map(|dir| dir.join(CLI_BINARY))
+                .filter(|path| is_file(path)),
+            CliSource::Path => {
+                let cwd = env::current_dir().ok()?;
+                which::which_in("claude", (self.env_lookup)("PATH"), cwd).ok()
+            }
+            CliSource::KnownDirs => known_locations().into_iter().find(|path| is_file(path)),
+        }
+    }
+}
+
+/// Run `claude -v` and parse its output, giving up after
+/// [`VERSION_CHECK_TIMEOUT`].
+async fn detect_version(path: &Path) -> Option<CliVersion> {
+    let output = tokio::process::Command::new(path)
+        .arg("-v")
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(VERSION_CHECK_TIMEOUT, output)
+        .await
+        .ok()?
+        .ok()?;
+    CliVersion::parse(&String::from_utf8_lossy(&output.stdout))
+}
+
+fn known_locations() -> Vec<PathBuf> {
+    let home = dirs::home_dir();
+    [
+        home.as_ref().map(|h| h.join(".npm-global/bin/claude")),
+        Some(PathBuf::from("/usr/local/bin/claude")),
+        home.as_ref().map(|h| h.join(".local/bin/claude")),
+        home.as_ref().map(|h| h.join("node_modules/.bin/claude")),
+        home.as_ref().map(|h| h.join(".yarn/bin/claude")),
+        home.as_ref().map(|h| h.join(".claude/local/claude")),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+fn is_file(path: &Path) -> bool {
+    path.exists() && path.is_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explicit_path_wins() {
+        let locator = CliLocator::new().with_explicit_path("/opt/claude");
+        assert_eq!(locator.find().unwrap(), PathBuf::from("/opt/claude"));
+    }
+
+    #[test]
+    fn test_env_var_source() {
+        let locator = CliLocator::new()
+            .with_env_var("MY_CLAUDE")
+            .with_env_lookup(|name| (name == "MY_CLAUDE").then(|| "/env/claude".into()))
+            .with_search_order(vec![CliSource::EnvVar]);
+        assert_eq!(locator.find().unwrap(), PathBuf::from("/env/claude"));
+
+        let empty = locator.with_env_lookup(|_| Some(OsString::new()));
+        assert!(empty.find().is_err());
+    }
+
+    #[test]
+    fn test_bundled_dir_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let locator = CliLocator::new()
+            .with_bundled_dir(dir.path())
+            .with_search_order(vec![CliSource::Bundled]);
+        assert!(matches!(
+            locator.find(),
+            Err(ClaudeSDKError::CLINotFound { .. })
+        ));
+
+        let binary = dir.path().join(CLI_BINARY);
+        std::fs::write(&binary, "").unwrap();
+        assert_eq!(locator.find().unwrap(), binary);
+    }
+
+    #[test]
+    fn test_empty_search_order_is_not_found() {
+        let locator = CliLocator::new()
+            .with_explicit_path("/opt/claude")
+            .with_search_order(vec![]);
+        assert!(matches!(
+            locator.find(),
+            Err(ClaudeSDKError::CLINotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_from_options() {
+        let options = ClaudeAgentOptions::builder()
+            .cli_path("/opt/claude")
+            .bundled_cli_dir("/opt/bundle")
+            .cli_version_policy(CliVersionPolicy::any())
+            .build();
+        let locator = CliLocator::from_options(&options);
+        assert_eq!(locator.explicit_path, Some(PathBuf::from("/opt/claude")));
+        assert_eq!(locator.bundled_dir, Some(PathBuf::from("/opt/bundle")));
+        assert_eq!(locator.version_policy, CliVersionPolicy::any());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_inspect_detects_version() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("claude");
+        std::fs::write(&script, "#!/bin/sh\necho '2.3.4 (Claude Code)'\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let info = CliLocator::new().inspect(script.clone()).await.unwrap();
+        assert_eq!(info.path, script);
+        assert_eq!(info.version, Some(CliVersion::new(2, 3, 4)));
+
+        let strict = CliLocator::new().with_version_policy(
+            CliVersionPolicy::any()
+                .with_max(CliVersion::new(2, 0, 0))
+                .with_action(crate::types::VersionMismatchAction::Error),
+        );
+        assert!(strict.inspect(script).await.is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_skip_version_check_only_applies_to_default_policy() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("claude");
+        std::fs::write(&script, "#!/bin/sh\necho '1.0.0 (Claude Code)'\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let skip = |name: &str| (name == SKIP_VERSION_CHECK_ENV_VAR).then(|| "1".into());
+
+        let info = CliLocator::new()
+            .with_env_lookup(skip)
+            .inspect(script.clone())
+            .await
+            .unwrap();
+        assert_eq!(info.version, None);
+
+        let strict = CliLocator::new().with_env_lookup(skip).with_version_policy(
+            CliVersionPolicy::default().with_action(crate::types::VersionMismatchAction::Error),
+        );
+        assert!(strict.inspect(script).await.is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_path_source_uses_env_lookup() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("claude");
+        std::fs::write(&script, "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let path = dir.path().as_os_str().to_owned();
+
+        let locator = CliLocator::new()
+            .with_env_lookup(move |name| (name == "PATH").then(|| path.clone()))
+            .with_search_order(vec![CliSource::Path]);
+        assert_eq!(locator.find().unwrap(), script);
+
+        let empty = locator.with_env_lookup(|_| None);
+        assert!(empty.find().is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_hung_version_check_is_undetected() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("claude");
+        std::fs::write(&script, "#!/bin/sh\nexec sleep 30\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let info = CliLocator::new().inspect(script.clone()).await.unwrap();
+        assert_eq!(info.version, None);
+
+        let strict = CliLocator::new().with_version_policy(
+            CliVersionPolicy::default().with_action(crate::types::VersionMismatchAction::Error),
+        );
+        assert!(matches!(
+            strict.inspect(script).await,
+            Err(ClaudeSDKError::CLIConnection(_))
+        ));
+    }
+}
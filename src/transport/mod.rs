@@ -1,7 +1,21 @@
 //! Transport implementations for Claude SDK.
 
+#[cfg(feature = "tokio")]
+mod framing;
+#[cfg(feature = "tokio")]
+mod invocation;
+#[cfg(feature = "tokio")]
+mod locator;
+#[cfg(feature = "tokio")]
 mod subprocess;
 
+#[cfg(feature = "tokio")]
+pub use invocation::CliInvocation;
+#[cfg(feature = "tokio")]
+pub(crate) use invocation::InitializeConfig;
+#[cfg(feature = "tokio")]
+pub use locator::{CliInfo, CliLocator, CliSource, EnvLookupFn, CLI_PATH_ENV_VAR};
+#[cfg(feature = "tokio")]
 pub use subprocess::SubprocessCLITransport;
 
 use async_trait::async_trait;
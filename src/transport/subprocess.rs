@@ -3,30 +3,24 @@
 use async_trait::async_trait;
 use futures::Stream;
 use serde_json::Value;
-use std::collections::HashMap;
-use std::env;
 use std::path::PathBuf;
 use std::pin::Pin;
 use std::process::Stdio;
 use std::sync::Arc;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::io::{AsyncWriteExt, BufReader};
 use tokio::process::{Child, ChildStdin, ChildStdout};
 use tokio::sync::Mutex;
 
 use crate::error::{ClaudeSDKError, Result};
-use crate::types::{
-    AgentDefinition, ClaudeAgentOptions, McpServerConfig, McpServers, SdkBeta, SettingSource,
-    SystemPrompt, Tools,
-};
+use crate::types::ClaudeAgentOptions;
 
+use super::framing::LineFramer;
+use super::invocation::{
+    CliInvocation, InitializeConfig, TempArgFile, COMMAND_LENGTH_LIMIT, INLINE_ARG_LIMIT,
+};
+use super::locator::{CliInfo, CliLocator};
 use super::Transport;
 
-/// SDK version for environment variable.
-const SDK_VERSION: &str = env!("CARGO_PKG_VERSION");
-
-/// Minimum required Claude Code version.
-const MINIMUM_CLAUDE_CODE_VERSION: &str = "2.0.0";
-
 /// Default maximum buffer size (1MB).
 const DEFAULT_MAX_BUFFER_SIZE: usize = 1024 * 1024;
 
@@ -35,441 +29,116 @@ pub struct SubprocessCLITransport {
     prompt: Option<String>,
     options: ClaudeAgentOptions,
     cli_path: PathBuf,
-    cwd: Option<PathBuf>,
     process: Option<Child>,
     stdin: Option<Arc<Mutex<ChildStdin>>>,
     stdout: Option<BufReader<ChildStdout>>,
     ready: bool,
-    max_buffer_size: usize,
     is_streaming: bool,
-    /// Buffer for accumulating partial JSON
-    json_buffer: String,
+    /// Splits stdout into JSON lines
+    framer: LineFramer,
+    /// Oversized arguments written to disk, removed on close or drop
+    temp_files: Vec<TempArgFile>,
+    /// Oversized arguments to send in the initialize request instead
+    initialize_config: InitializeConfig,
+    /// CLI path and version detected on connect
+    cli_info: Option<CliInfo>,
 }
 
 impl SubprocessCLITransport {
     /// Create a new subprocess transport with a string prompt.
+    ///
+    /// The options are checked with [`ClaudeAgentOptions::validate`] unless
+    /// `skip_validation` is set.
     pub fn new(prompt: impl Into<String>, options: ClaudeAgentOptions) -> Result<Self> {
-        let cli_path = if let Some(ref path) = options.cli_path {
-            path.clone()
-        } else {
-            Self::find_cli()?
-        };
-
-        let cwd = options.cwd.clone();
-        let max_buffer_size = options.max_buffer_size.unwrap_or(DEFAULT_MAX_BUFFER_SIZE);
-
-        Ok(Self {
-            prompt: Some(prompt.into()),
-            options,
-            cli_path,
-            cwd,
-            process: None,
-            stdin: None,
-            stdout: None,
-            ready: false,
-            max_buffer_size,
-            is_streaming: false,
-            json_buffer: String::new(),
-        })
+        Self::with_prompt(Some(prompt.into()), options)
     }
 
     /// Create a new subprocess transport for streaming mode (no initial prompt).
+    ///
+    /// The options are checked with [`ClaudeAgentOptions::validate`] unless
+    /// `skip_validation` is set.
     pub fn streaming(options: ClaudeAgentOptions) -> Result<Self> {
-        let cli_path = if let Some(ref path) = options.cli_path {
-            path.clone()
-        } else {
-            Self::find_cli()?
-        };
+        Self::with_prompt(None, options)
+    }
+
+    fn with_prompt(prompt: Option<String>, options: ClaudeAgentOptions) -> Result<Self> {
+        if !options.skip_validation {
+            Self::check_options(&options)?;
+        }
+
+        let cli_path = CliLocator::from_options(&options).find()?;
 
-        let cwd = options.cwd.clone();
         let max_buffer_size = options.max_buffer_size.unwrap_or(DEFAULT_MAX_BUFFER_SIZE);
+        let is_streaming = prompt.is_none();
 
         Ok(Self {
-            prompt: None,
+            prompt,
             options,
             cli_path,
-            cwd,
             process: None,
             stdin: None,
             stdout: None,
             ready: false,
-            max_buffer_size,
-            is_streaming: true,
-            json_buffer: String::new(),
+            is_streaming,
+            framer: LineFramer::new(max_buffer_size),
+            temp_files: Vec::new(),
+            initialize_config: InitializeConfig::default(),
+            cli_info: None,
         })
     }
 
-    /// Find Claude Code CLI binary.
-    fn find_cli() -> Result<PathBuf> {
-        // Check for bundled CLI first (not implemented yet)
-
-        // Fall back to system-wide search
-        if let Ok(path) = which::which("claude") {
-            return Ok(path);
-        }
-
-        // Check common installation locations
-        let home = dirs::home_dir();
-        let locations = [
-            home.as_ref().map(|h| h.join(".npm-global/bin/claude")),
-            Some(PathBuf::from("/usr/local/bin/claude")),
-            home.as_ref().map(|h| h.join(".local/bin/claude")),
-            home.as_ref().map(|h| h.join("node_modules/.bin/claude")),
-            home.as_ref().map(|h| h.join(".yarn/bin/claude")),
-            home.as_ref().map(|h| h.join(".claude/local/claude")),
-        ];
-
-        for location in locations.into_iter().flatten() {
-            if location.exists() && location.is_file() {
-                return Ok(location);
+    /// Fail with a single `InvalidConfig` error listing every validation problem.
+    fn check_options(options: &ClaudeAgentOptions) -> Result<()> {
+        let mut problems = options.validate();
+        match problems.len() {
+            0 => Ok(()),
+            1 => Err(problems.remove(0)),
+            _ => {
+                let messages: Vec<String> = problems
+                    .into_iter()
+                    .map(|problem| match problem {
+                        ClaudeSDKError::InvalidConfig(message) => message,
+                        other => other.to_string(),
+                    })
+                    .collect();
+                Err(ClaudeSDKError::InvalidConfig(messages.join("; ")))
             }
         }
-
-        Err(ClaudeSDKError::cli_not_found(None))
     }
 
-    /// Build the CLI command with all arguments.
-    fn build_command(&self) -> Vec<String> {
-        let mut cmd = vec![
-            self.cli_path.to_string_lossy().to_string(),
-            "--output-format".to_string(),
-            "stream-json".to_string(),
-            "--verbose".to_string(),
-        ];
-
-        // System prompt
-        match &self.options.system_prompt {
-            None => {
-                cmd.extend(["--system-prompt".to_string(), String::new()]);
-            }
-            Some(SystemPrompt::Text(text)) => {
-                cmd.extend(["--system-prompt".to_string(), text.clone()]);
-            }
-            Some(SystemPrompt::Preset(preset)) => {
-                if preset.preset_type == "preset" {
-                    if let Some(ref append) = preset.append {
-                        cmd.extend(["--append-system-prompt".to_string(), append.clone()]);
-                    }
-                }
-            }
-        }
-
-        // Tools
-        if let Some(ref tools) = self.options.tools {
-            match tools {
-                Tools::List(list) => {
-                    if list.is_empty() {
-                        cmd.extend(["--tools".to_string(), String::new()]);
-                    } else {
-                        cmd.extend(["--tools".to_string(), list.join(",")]);
-                    }
-                }
-                Tools::Preset(_) => {
-                    cmd.extend(["--tools".to_string(), "default".to_string()]);
-                }
-            }
-        }
-
-        // Allowed tools
-        if !self.options.allowed_tools.is_empty() {
-            cmd.extend([
-                "--allowedTools".to_string(),
-                self.options.allowed_tools.join(","),
-            ]);
-        }
-
-        // Max turns
-        if let Some(max_turns) = self.options.max_turns {
-            cmd.extend(["--max-turns".to_string(), max_turns.to_string()]);
-        }
-
-        // Max budget
-        if let Some(budget) = self.options.max_budget_usd {
-            cmd.extend(["--max-budget-usd".to_string(), budget.to_string()]);
-        }
-
-        // Disallowed tools
-        if !self.options.disallowed_tools.is_empty() {
-            cmd.extend([
-                "--disallowedTools".to_string(),
-                self.options.disallowed_tools.join(","),
-            ]);
-        }
-
-        // Model
-        if let Some(ref model) = self.options.model {
-            cmd.extend(["--model".to_string(), model.clone()]);
-        }
-
-        // Fallback model
-        if let Some(ref model) = self.options.fallback_model {
-            cmd.extend(["--fallback-model".to_string(), model.clone()]);
-        }
-
-        // Betas
-        if !self.options.betas.is_empty() {
-            let betas: Vec<String> = self
-                .options
-                .betas
-                .iter()
-                .map(|b| match b {
-                    SdkBeta::Context1m20250807 => "context-1m-2025-08-07".to_string(),
-                })
-                .collect();
-            cmd.extend(["--betas".to_string(), betas.join(",")]);
-        }
-
-        // Permission prompt tool name
-        if let Some(ref name) = self.options.permission_prompt_tool_name {
-            cmd.extend(["--permission-prompt-tool".to_string(), name.clone()]);
-        }
-
-        // Permission mode
-        if let Some(mode) = self.options.permission_mode {
-            cmd.extend(["--permission-mode".to_string(), mode.to_string()]);
-        }
-
-        // Continue conversation
-        if self.options.continue_conversation {
-            cmd.push("--continue".to_string());
-        }
-
-        // Resume session
-        if let Some(ref session) = self.options.resume {
-            cmd.extend(["--resume".to_string(), session.clone()]);
-        }
-
-        // Settings
-        if let Some(ref settings) = self.build_settings_value() {
-            cmd.extend(["--settings".to_string(), settings.clone()]);
-        }
-
-        // Add directories
-        for dir in &self.options.add_dirs {
-            cmd.extend(["--add-dir".to_string(), dir.to_string_lossy().to_string()]);
-        }
-
-        // MCP servers
-        if let Some(ref mcp_servers) = self.options.mcp_servers {
-            match mcp_servers {
-                McpServers::Map(map) => {
-                    if !map.is_empty() {
-                        // Filter out SDK server instances
-                        let servers_for_cli: HashMap<String, &McpServerConfig> = map
-                            .iter()
-                            .filter(|(_, config)| !config.is_sdk())
-                            .map(|(k, v)| (k.clone(), v))
-                            .collect();
-
-                        if !servers_for_cli.is_empty() {
-                            let config = serde_json::json!({ "mcpServers": servers_for_cli });
-                            cmd.extend(["--mcp-config".to_string(), config.to_string()]);
-                        }
-                    }
-                }
-                McpServers::Path(path) => {
-                    cmd.extend([
-                        "--mcp-config".to_string(),
-                        path.to_string_lossy().to_string(),
-                    ]);
-                }
-                McpServers::Json(json) => {
-                    cmd.extend(["--mcp-config".to_string(), json.clone()]);
-                }
-            }
-        }
-
-        // Include partial messages
-        if self.options.include_partial_messages {
-            cmd.push("--include-partial-messages".to_string());
-        }
-
-        // Fork session
-        if self.options.fork_session {
-            cmd.push("--fork-session".to_string());
-        }
-
-        // Agents
-        if let Some(ref agents) = self.options.agents {
-            let agents_json = self.serialize_agents(agents);
-            cmd.extend(["--agents".to_string(), agents_json]);
-        }
-
-        // Setting sources
-        let sources = if let Some(ref sources) = self.options.setting_sources {
-            sources
-                .iter()
-                .map(|s| match s {
-                    SettingSource::User => "user",
-                    SettingSource::Project => "project",
-                    SettingSource::Local => "local",
-                })
-                .collect::<Vec<_>>()
-                .join(",")
-        } else {
-            String::new()
-        };
-        cmd.extend(["--setting-sources".to_string(), sources]);
-
-        // Plugins
-        for plugin in &self.options.plugins {
-            if plugin.plugin_type == "local" {
-                cmd.extend(["--plugin-dir".to_string(), plugin.path.clone()]);
-            }
-        }
-
-        // Extra args
-        for (flag, value) in &self.options.extra_args {
-            if let Some(val) = value {
-                cmd.extend([format!("--{}", flag), val.clone()]);
-            } else {
-                cmd.push(format!("--{}", flag));
-            }
-        }
-
-        // Max thinking tokens
-        if let Some(tokens) = self.options.max_thinking_tokens {
-            cmd.extend(["--max-thinking-tokens".to_string(), tokens.to_string()]);
-        }
+    /// Get the CLI path and version detected on connect.
+    pub fn cli_info(&self) -> Option<&CliInfo> {
+        self.cli_info.as_ref()
+    }
 
-        // Output format (JSON schema)
-        if let Some(ref format) = self.options.output_format {
-            if let Some(schema) = format.get("schema") {
-                if format.get("type") == Some(&serde_json::json!("json_schema")) {
-                    cmd.extend(["--json-schema".to_string(), schema.to_string()]);
-                }
-            }
-        }
+    /// Configuration taken off the command line on connect, for the client
+    /// to send in its initialize request.
+    pub(crate) fn initialize_config(&self) -> &InitializeConfig {
+        &self.initialize_config
+    }
 
-        // Prompt handling
+    /// Describe the process this transport spawns on [`Transport::connect`].
+    pub fn invocation(&self) -> CliInvocation {
+        let invocation = CliInvocation::from_options(&self.cli_path, &self.options);
         if self.is_streaming {
-            cmd.extend(["--input-format".to_string(), "stream-json".to_string()]);
+            invocation.with_streaming_input()
         } else if let Some(ref prompt) = self.prompt {
-            cmd.extend(["--print".to_string(), "--".to_string(), prompt.clone()]);
+            invocation.with_print_prompt(prompt.clone())
+        } else {
+            invocation
         }
+    }
 
-        cmd
+    /// Build the CLI command with all arguments.
+    #[cfg(test)]
+    fn build_command(&self) -> Vec<String> {
+        self.invocation().argv()
     }
 
     /// Build settings value, merging sandbox settings if provided.
+    #[cfg(test)]
     fn build_settings_value(&self) -> Option<String> {
-        let has_settings = self.options.settings.is_some();
-        let has_sandbox = self.options.sandbox.is_some();
-
-        if !has_settings && !has_sandbox {
-            return None;
-        }
-
-        // If only settings path and no sandbox, pass through as-is
-        if has_settings && !has_sandbox {
-            return self.options.settings.clone();
-        }
-
-        // If we have sandbox settings, we need to merge into a JSON object
-        let mut settings_obj: serde_json::Map<String, Value> = serde_json::Map::new();
-
-        if let Some(ref settings_str) = self.options.settings {
-            let trimmed = settings_str.trim();
-            if trimmed.starts_with('{') && trimmed.ends_with('}') {
-                // Parse JSON string
-                if let Ok(parsed) = serde_json::from_str::<serde_json::Map<String, Value>>(trimmed)
-                {
-                    settings_obj = parsed;
-                }
-            } else {
-                // It's a file path - read and parse
-                if let Ok(content) = std::fs::read_to_string(trimmed) {
-                    if let Ok(parsed) =
-                        serde_json::from_str::<serde_json::Map<String, Value>>(&content)
-                    {
-                        settings_obj = parsed;
-                    }
-                }
-            }
-        }
-
-        // Merge sandbox settings
-        if let Some(ref sandbox) = self.options.sandbox {
-            settings_obj.insert(
-                "sandbox".to_string(),
-                serde_json::to_value(sandbox).unwrap_or_default(),
-            );
-        }
-
-        Some(serde_json::to_string(&settings_obj).unwrap_or_default())
-    }
-
-    /// Serialize agents to JSON.
-    fn serialize_agents(&self, agents: &HashMap<String, AgentDefinition>) -> String {
-        let agents_map: HashMap<String, Value> = agents
-            .iter()
-            .map(|(name, def)| {
-                let mut obj = serde_json::Map::new();
-                obj.insert(
-                    "description".to_string(),
-                    Value::String(def.description.clone()),
-                );
-                obj.insert("prompt".to_string(), Value::String(def.prompt.clone()));
-                if let Some(ref tools) = def.tools {
-                    obj.insert("tools".to_string(), serde_json::json!(tools));
-                }
-                if let Some(ref model) = def.model {
-                    obj.insert("model".to_string(), Value::String(model.clone()));
-                }
-                (name.clone(), Value::Object(obj))
-            })
-            .collect();
-        serde_json::to_string(&agents_map).unwrap_or_default()
-    }
-
-    /// Check Claude Code version.
-    async fn check_version(&self) -> Result<()> {
-        if env::var("CLAUDE_AGENT_SDK_SKIP_VERSION_CHECK").is_ok() {
-            return Ok(());
-        }
-
-        let output = tokio::process::Command::new(&self.cli_path)
-            .arg("-v")
-            .output()
-            .await;
-
-        if let Ok(output) = output {
-            if let Ok(version_str) = String::from_utf8(output.stdout) {
-                let version_str = version_str.trim();
-                if let Some(version) = version_str.split_whitespace().next() {
-                    if Self::version_compare(version, MINIMUM_CLAUDE_CODE_VERSION) < 0 {
-                        tracing::warn!(
-                            "Claude Code version {} is unsupported. Minimum required: {}",
-                            version,
-                            MINIMUM_CLAUDE_CODE_VERSION
-                        );
-                    }
-                }
-            }
-        }
-
-        Ok(())
-    }
-
-    /// Compare version strings.
-    fn version_compare(v1: &str, v2: &str) -> i32 {
-        let parse_version =
-            |v: &str| -> Vec<i32> { v.split('.').filter_map(|s| s.parse::<i32>().ok()).collect() };
-
-        let v1_parts = parse_version(v1);
-        let v2_parts = parse_version(v2);
-
-        for i in 0..std::cmp::max(v1_parts.len(), v2_parts.len()) {
-            let p1 = v1_parts.get(i).copied().unwrap_or(0);
-            let p2 = v2_parts.get(i).copied().unwrap_or(0);
-            if p1 < p2 {
-                return -1;
-            }
-            if p1 > p2 {
-                return 1;
-            }
-        }
-        0
+        super::invocation::build_settings_value(&self.options)
     }
 }
 
@@ -480,47 +149,28 @@ impl Transport for SubprocessCLITransport {
             return Ok(());
         }
 
-        self.check_version().await?;
-
-        let cmd = self.build_command();
-        let program = &cmd[0];
-        let args = &cmd[1..];
-
-        // Build environment
-        let mut env_vars: HashMap<String, String> = env::vars().collect();
-        env_vars.extend(self.options.env.clone());
-        env_vars.insert("CLAUDE_CODE_ENTRYPOINT".to_string(), "sdk-rust".to_string());
-        env_vars.insert(
-            "CLAUDE_AGENT_SDK_VERSION".to_string(),
-            SDK_VERSION.to_string(),
+        self.cli_info = Some(
+            CliLocator::from_options(&self.options)
+                .inspect(self.cli_path.clone())
+                .await?,
         );
 
-        if self.options.enable_file_checkpointing {
-            env_vars.insert(
-                "CLAUDE_CODE_ENABLE_SDK_FILE_CHECKPOINTING".to_string(),
-                "true".to_string(),
-            );
-        }
-
-        if let Some(ref cwd) = self.cwd {
-            env_vars.insert("PWD".to_string(), cwd.to_string_lossy().to_string());
+        let mut invocation = self.invocation();
+        if self.is_streaming {
+            self.initialize_config = invocation.defer_to_initialize(INLINE_ARG_LIMIT);
         }
-
-        let mut command = tokio::process::Command::new(program);
+        self.temp_files = invocation.spill_large_args(INLINE_ARG_LIMIT, COMMAND_LENGTH_LIMIT)?;
+        let mut command = invocation.to_command();
         command
-            .args(args)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
-            .stderr(Stdio::inherit())
-            .envs(&env_vars);
-
-        if let Some(ref cwd) = self.cwd {
-            command.current_dir(cwd);
-        }
+            .stderr(Stdio::inherit());
 
         let mut child = command.spawn().map_err(|e| {
             if e.kind() == std::io::ErrorKind::NotFound {
-                ClaudeSDKError::cli_not_found(Some(program.clone()))
+                ClaudeSDKError::cli_not_found(Some(
+                    invocation.program.to_string_lossy().to_string(),
+                ))
             } else {
                 ClaudeSDKError::CLIConnection(format!("Failed to start Claude Code: {}", e))
             }
@@ -574,9 +224,20 @@ impl Transport for SubprocessCLITransport {
     }
 
     fn read_messages(&mut self) -> Pin<Box<dyn Stream<Item = Result<Value>> + Send + '_>> {
-        Box::pin(async_stream::try_stream! {
-            while let Some(data) = self.read_next_message().await? {
-                yield data;
+        Box::pin(async_stream::stream! {
+            loop {
+                match self.read_next_message().await {
+                    Ok(Some(data)) => yield Ok(data),
+                    Ok(None) => break,
+                    Err(e) => {
+                        // Malformed lines are skipped; anything else ends the stream
+                        let recoverable = matches!(e, ClaudeSDKError::MessageParse { .. });
+                        yield Err(e);
+                        if !recoverable {
+                            break;
+                        }
+                    }
+                }
             }
         })
     }
@@ -587,47 +248,12 @@ impl Transport for SubprocessCLITransport {
             .as_mut()
             .ok_or_else(|| ClaudeSDKError::CLIConnection("Not connected".to_string()))?;
 
-        let mut line = String::new();
-
-        loop {
-            line.clear();
-            let bytes_read = stdout.read_line(&mut line).await.map_err(|e| {
-                ClaudeSDKError::CLIConnection(format!("Failed to read from stdout: {}", e))
-            })?;
-
-            if bytes_read == 0 {
-                return Ok(None); // EOF
-            }
-
-            let trimmed = line.trim();
-            if trimmed.is_empty() {
-                continue;
-            }
-
-            // Accumulate partial JSON
-            self.json_buffer.push_str(trimmed);
-
-            if self.json_buffer.len() > self.max_buffer_size {
-                let len = self.json_buffer.len();
-                self.json_buffer.clear();
-                return Err(ClaudeSDKError::CLIConnection(format!(
-                    "JSON message exceeded maximum buffer size of {} bytes (got {})",
-                    self.max_buffer_size, len
-                )));
-            }
-
-            // Try to parse
-            match serde_json::from_str::<Value>(&self.json_buffer) {
-                Ok(data) => {
-                    self.json_buffer.clear();
-                    return Ok(Some(data));
-                }
-                Err(_) => {
-                    // Keep accumulating
-                    continue;
-                }
-            }
+        let message = self.framer.next_message(stdout).await?;
+        if message.is_none() {
+            // EOF: the process has exited or closed stdout
+            self.ready = false;
         }
+        Ok(message)
     }
 
     async fn close(&mut self) -> Result<()> {
@@ -645,6 +271,7 @@ impl Transport for SubprocessCLITransport {
         }
 
         self.stdout = None;
+        self.temp_files.clear();
 
         Ok(())
     }
@@ -674,16 +301,37 @@ impl Drop for SubprocessCLITransport {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::{SdkBeta, SettingSource};
 
     #[test]
-    fn test_version_compare() {
-        assert_eq!(SubprocessCLITransport::version_compare("2.0.0", "2.0.0"), 0);
-        assert_eq!(SubprocessCLITransport::version_compare("2.1.0", "2.0.0"), 1);
-        assert_eq!(
-            SubprocessCLITransport::version_compare("1.9.0", "2.0.0"),
-            -1
-        );
-        assert_eq!(SubprocessCLITransport::version_compare("2.0.1", "2.0.0"), 1);
+    fn test_new_rejects_invalid_options() {
+        let options = ClaudeAgentOptions::builder()
+            .cli_path("/usr/bin/claude")
+            .max_budget_usd(-5.0)
+            .cwd("/nonexistent/cwd")
+            .build();
+
+        match SubprocessCLITransport::new("test", options) {
+            Err(ClaudeSDKError::InvalidConfig(message)) => {
+                assert!(message.contains("max_budget_usd"));
+                assert!(message.contains("/nonexistent/cwd"));
+            }
+            Err(other) => panic!("Expected InvalidConfig, got {:?}", other),
+            Ok(_) => panic!("Expected invalid options to be rejected"),
+        }
+    }
+
+    #[test]
+    fn test_streaming_skip_validation() {
+        let options = ClaudeAgentOptions::builder()
+            .cli_path("/usr/bin/claude")
+            .max_budget_usd(-5.0)
+            .skip_validation(true)
+            .build();
+
+        let transport = SubprocessCLITransport::streaming(options).unwrap();
+        assert!(transport.is_streaming);
+        assert!(transport.prompt.is_none());
     }
 
     #[test]
@@ -697,14 +345,15 @@ mod tests {
             prompt: Some("Hello".to_string()),
             options,
             cli_path: PathBuf::from("/usr/bin/claude"),
-            cwd: None,
             process: None,
             stdin: None,
             stdout: None,
             ready: false,
-            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
             is_streaming: false,
-            json_buffer: String::new(),
+            framer: LineFramer::new(DEFAULT_MAX_BUFFER_SIZE),
+            temp_files: Vec::new(),
+            initialize_config: InitializeConfig::default(),
+            cli_info: None,
         };
 
         let cmd = transport.build_command();
@@ -727,14 +376,15 @@ mod tests {
             prompt: None,
             options,
             cli_path: PathBuf::from("/usr/bin/claude"),
-            cwd: None,
             process: None,
             stdin: None,
             stdout: None,
             ready: false,
-            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
             is_streaming: true,
-            json_buffer: String::new(),
+            framer: LineFramer::new(DEFAULT_MAX_BUFFER_SIZE),
+            temp_files: Vec::new(),
+            initialize_config: InitializeConfig::default(),
+            cli_info: None,
         };
 
         let cmd = transport.build_command();
@@ -756,14 +406,15 @@ mod tests {
             prompt: Some("test".to_string()),
             options,
             cli_path: PathBuf::from("/usr/bin/claude"),
-            cwd: None,
             process: None,
             stdin: None,
             stdout: None,
             ready: false,
-            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
             is_streaming: false,
-            json_buffer: String::new(),
+            framer: LineFramer::new(DEFAULT_MAX_BUFFER_SIZE),
+            temp_files: Vec::new(),
+            initialize_config: InitializeConfig::default(),
+            cli_info: None,
         };
 
         let cmd = transport.build_command();
@@ -786,14 +437,15 @@ mod tests {
             prompt: Some("test".to_string()),
             options,
             cli_path: PathBuf::from("/usr/bin/claude"),
-            cwd: None,
             process: None,
             stdin: None,
             stdout: None,
             ready: false,
-            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
             is_streaming: false,
-            json_buffer: String::new(),
+            framer: LineFramer::new(DEFAULT_MAX_BUFFER_SIZE),
+            temp_files: Vec::new(),
+            initialize_config: InitializeConfig::default(),
+            cli_info: None,
         };
 
         let cmd = transport.build_command();
@@ -806,28 +458,6 @@ mod tests {
         assert!(cmd.contains(&"1000".to_string()));
     }
 
-    #[test]
-    fn test_version_compare_edge_cases() {
-        // Same versions
-        assert_eq!(SubprocessCLITransport::version_compare("1.0.0", "1.0.0"), 0);
-        assert_eq!(SubprocessCLITransport::version_compare("0.0.1", "0.0.1"), 0);
-
-        // Different number of components
-        assert_eq!(SubprocessCLITransport::version_compare("1.0", "1.0.0"), 0);
-        assert_eq!(SubprocessCLITransport::version_compare("1", "1.0.0"), 0);
-        assert_eq!(SubprocessCLITransport::version_compare("2", "1.9.9"), 1);
-
-        // Early vs later versions
-        assert_eq!(
-            SubprocessCLITransport::version_compare("0.1.0", "1.0.0"),
-            -1
-        );
-        assert_eq!(
-            SubprocessCLITransport::version_compare("10.0.0", "2.0.0"),
-            1
-        );
-    }
-
     #[test]
     fn test_build_command_with_permission_mode() {
         let options = ClaudeAgentOptions::builder()
@@ -838,14 +468,15 @@ mod tests {
             prompt: Some("test".to_string()),
             options,
             cli_path: PathBuf::from("/usr/bin/claude"),
-            cwd: None,
             process: None,
             stdin: None,
             stdout: None,
             ready: false,
-            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
             is_streaming: false,
-            json_buffer: String::new(),
+            framer: LineFramer::new(DEFAULT_MAX_BUFFER_SIZE),
+            temp_files: Vec::new(),
+            initialize_config: InitializeConfig::default(),
+            cli_info: None,
         };
 
         let cmd = transport.build_command();
@@ -862,14 +493,15 @@ mod tests {
             prompt: Some("test".to_string()),
             options,
             cli_path: PathBuf::from("/usr/bin/claude"),
-            cwd: None,
             process: None,
             stdin: None,
             stdout: None,
             ready: false,
-            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
             is_streaming: false,
-            json_buffer: String::new(),
+            framer: LineFramer::new(DEFAULT_MAX_BUFFER_SIZE),
+            temp_files: Vec::new(),
+            initialize_config: InitializeConfig::default(),
+            cli_info: None,
         };
 
         let cmd = transport.build_command();
@@ -888,14 +520,15 @@ mod tests {
             prompt: Some("test".to_string()),
             options,
             cli_path: PathBuf::from("/usr/bin/claude"),
-            cwd: None,
             process: None,
             stdin: None,
             stdout: None,
             ready: false,
-            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
             is_streaming: false,
-            json_buffer: String::new(),
+            framer: LineFramer::new(DEFAULT_MAX_BUFFER_SIZE),
+            temp_files: Vec::new(),
+            initialize_config: InitializeConfig::default(),
+            cli_info: None,
         };
 
         let cmd = transport.build_command();
@@ -910,14 +543,15 @@ mod tests {
             prompt: Some("test".to_string()),
             options,
             cli_path: PathBuf::from("/usr/bin/claude"),
-            cwd: None,
             process: None,
             stdin: None,
             stdout: None,
             ready: false,
-            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
             is_streaming: false,
-            json_buffer: String::new(),
+            framer: LineFramer::new(DEFAULT_MAX_BUFFER_SIZE),
+            temp_files: Vec::new(),
+            initialize_config: InitializeConfig::default(),
+            cli_info: None,
         };
 
         assert!(!transport.is_ready());
@@ -931,18 +565,23 @@ mod tests {
             prompt: Some("test".to_string()),
             options,
             cli_path: PathBuf::from("/usr/bin/claude"),
-            cwd: Some(PathBuf::from("/some/path")),
             process: None,
             stdin: None,
             stdout: None,
             ready: false,
-            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
             is_streaming: false,
-            json_buffer: String::new(),
+            framer: LineFramer::new(DEFAULT_MAX_BUFFER_SIZE),
+            temp_files: Vec::new(),
+            initialize_config: InitializeConfig::default(),
+            cli_info: None,
         };
 
-        assert!(transport.cwd.is_some());
-        assert_eq!(transport.cwd.as_ref().unwrap().to_str(), Some("/some/path"));
+        let invocation = transport.invocation();
+        assert_eq!(invocation.cwd, Some(PathBuf::from("/some/path")));
+        assert_eq!(
+            invocation.env.get("PWD").map(String::as_str),
+            Some("/some/path")
+        );
     }
 
     #[test]
@@ -955,14 +594,15 @@ mod tests {
             prompt: Some("test".to_string()),
             options,
             cli_path: PathBuf::from("/usr/bin/claude"),
-            cwd: None,
             process: None,
             stdin: None,
             stdout: None,
             ready: false,
-            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
             is_streaming: false,
-            json_buffer: String::new(),
+            framer: LineFramer::new(DEFAULT_MAX_BUFFER_SIZE),
+            temp_files: Vec::new(),
+            initialize_config: InitializeConfig::default(),
+            cli_info: None,
         };
 
         let cmd = transport.build_command();
@@ -974,24 +614,13 @@ mod tests {
     #[test]
     fn test_build_command_with_max_buffer_size() {
         let options = ClaudeAgentOptions::builder()
-            .max_buffer_size(1024 * 1024)
+            .cli_path("/usr/bin/claude")
+            .max_buffer_size(64 * 1024)
             .build();
 
-        let transport = SubprocessCLITransport {
-            prompt: Some("test".to_string()),
-            options: options.clone(),
-            cli_path: PathBuf::from("/usr/bin/claude"),
-            cwd: None,
-            process: None,
-            stdin: None,
-            stdout: None,
-            ready: false,
-            max_buffer_size: options.max_buffer_size.unwrap_or(DEFAULT_MAX_BUFFER_SIZE),
-            is_streaming: false,
-            json_buffer: String::new(),
-        };
+        let transport = SubprocessCLITransport::new("test", options).unwrap();
 
-        assert_eq!(transport.max_buffer_size, 1024 * 1024);
+        assert_eq!(transport.framer.max_line_size(), 64 * 1024);
     }
 
     #[test]
@@ -1004,14 +633,15 @@ mod tests {
             prompt: Some("test".to_string()),
             options,
             cli_path: PathBuf::from("/usr/bin/claude"),
-            cwd: None,
             process: None,
             stdin: None,
             stdout: None,
             ready: false,
-            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
             is_streaming: false,
-            json_buffer: String::new(),
+            framer: LineFramer::new(DEFAULT_MAX_BUFFER_SIZE),
+            temp_files: Vec::new(),
+            initialize_config: InitializeConfig::default(),
+            cli_info: None,
         };
 
         let cmd = transport.build_command();
@@ -1030,14 +660,15 @@ mod tests {
             prompt: Some("test".to_string()),
             options,
             cli_path: PathBuf::from("/usr/bin/claude"),
-            cwd: None,
             process: None,
             stdin: None,
             stdout: None,
             ready: false,
-            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
             is_streaming: false,
-            json_buffer: String::new(),
+            framer: LineFramer::new(DEFAULT_MAX_BUFFER_SIZE),
+            temp_files: Vec::new(),
+            initialize_config: InitializeConfig::default(),
+            cli_info: None,
         };
 
         let cmd = transport.build_command();
@@ -1053,14 +684,15 @@ mod tests {
             prompt: Some("test".to_string()),
             options,
             cli_path: PathBuf::from("/usr/bin/claude"),
-            cwd: None,
             process: None,
             stdin: None,
             stdout: None,
             ready: false,
-            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
             is_streaming: false,
-            json_buffer: String::new(),
+            framer: LineFramer::new(DEFAULT_MAX_BUFFER_SIZE),
+            temp_files: Vec::new(),
+            initialize_config: InitializeConfig::default(),
+            cli_info: None,
         };
 
         let cmd = transport.build_command();
@@ -1078,14 +710,15 @@ mod tests {
             prompt: Some("test".to_string()),
             options,
             cli_path: PathBuf::from("/usr/bin/claude"),
-            cwd: None,
             process: None,
             stdin: None,
             stdout: None,
             ready: false,
-            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
             is_streaming: false,
-            json_buffer: String::new(),
+            framer: LineFramer::new(DEFAULT_MAX_BUFFER_SIZE),
+            temp_files: Vec::new(),
+            initialize_config: InitializeConfig::default(),
+            cli_info: None,
         };
 
         let cmd = transport.build_command();
@@ -1106,14 +739,15 @@ mod tests {
             prompt: Some("test".to_string()),
             options,
             cli_path: PathBuf::from("/usr/bin/claude"),
-            cwd: None,
             process: None,
             stdin: None,
             stdout: None,
             ready: false,
-            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
             is_streaming: false,
-            json_buffer: String::new(),
+            framer: LineFramer::new(DEFAULT_MAX_BUFFER_SIZE),
+            temp_files: Vec::new(),
+            initialize_config: InitializeConfig::default(),
+            cli_info: None,
         };
 
         let cmd = transport.build_command();
@@ -1133,14 +767,15 @@ mod tests {
             prompt: Some("test".to_string()),
             options,
             cli_path: PathBuf::from("/usr/bin/claude"),
-            cwd: None,
             process: None,
             stdin: None,
             stdout: None,
             ready: false,
-            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
             is_streaming: false,
-            json_buffer: String::new(),
+            framer: LineFramer::new(DEFAULT_MAX_BUFFER_SIZE),
+            temp_files: Vec::new(),
+            initialize_config: InitializeConfig::default(),
+            cli_info: None,
         };
 
         let cmd = transport.build_command();
@@ -1163,14 +798,15 @@ mod tests {
             prompt: Some("test".to_string()),
             options,
             cli_path: PathBuf::from("/usr/bin/claude"),
-            cwd: None,
             process: None,
             stdin: None,
             stdout: None,
             ready: false,
-            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
             is_streaming: false,
-            json_buffer: String::new(),
+            framer: LineFramer::new(DEFAULT_MAX_BUFFER_SIZE),
+            temp_files: Vec::new(),
+            initialize_config: InitializeConfig::default(),
+            cli_info: None,
         };
 
         let cmd = transport.build_command();
@@ -1192,14 +828,15 @@ mod tests {
             prompt: Some("test".to_string()),
             options,
             cli_path: PathBuf::from("/usr/bin/claude"),
-            cwd: None,
             process: None,
             stdin: None,
             stdout: None,
             ready: false,
-            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
             is_streaming: false,
-            json_buffer: String::new(),
+            framer: LineFramer::new(DEFAULT_MAX_BUFFER_SIZE),
+            temp_files: Vec::new(),
+            initialize_config: InitializeConfig::default(),
+            cli_info: None,
         };
 
         let cmd = transport.build_command();
@@ -1221,14 +858,15 @@ mod tests {
             prompt: Some("test".to_string()),
             options,
             cli_path: PathBuf::from("/usr/bin/claude"),
-            cwd: None,
             process: None,
             stdin: None,
             stdout: None,
             ready: false,
-            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
             is_streaming: false,
-            json_buffer: String::new(),
+            framer: LineFramer::new(DEFAULT_MAX_BUFFER_SIZE),
+            temp_files: Vec::new(),
+            initialize_config: InitializeConfig::default(),
+            cli_info: None,
         };
 
         let cmd = transport.build_command();
@@ -1245,14 +883,15 @@ mod tests {
             prompt: Some("test".to_string()),
             options,
             cli_path: PathBuf::from("/usr/bin/claude"),
-            cwd: None,
             process: None,
             stdin: None,
             stdout: None,
             ready: false,
-            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
             is_streaming: false,
-            json_buffer: String::new(),
+            framer: LineFramer::new(DEFAULT_MAX_BUFFER_SIZE),
+            temp_files: Vec::new(),
+            initialize_config: InitializeConfig::default(),
+            cli_info: None,
         };
 
         // User isn't directly added to command line in this implementation
@@ -1279,14 +918,15 @@ mod tests {
             prompt: Some("test".to_string()),
             options,
             cli_path: PathBuf::from("/usr/bin/claude"),
-            cwd: None,
             process: None,
             stdin: None,
             stdout: None,
             ready: false,
-            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
             is_streaming: false,
-            json_buffer: String::new(),
+            framer: LineFramer::new(DEFAULT_MAX_BUFFER_SIZE),
+            temp_files: Vec::new(),
+            initialize_config: InitializeConfig::default(),
+            cli_info: None,
         };
 
         let cmd = transport.build_command();
@@ -1314,14 +954,15 @@ mod tests {
             prompt: Some("test".to_string()),
             options,
             cli_path: PathBuf::from("/usr/bin/claude"),
-            cwd: None,
             process: None,
             stdin: None,
             stdout: None,
             ready: false,
-            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
             is_streaming: false,
-            json_buffer: String::new(),
+            framer: LineFramer::new(DEFAULT_MAX_BUFFER_SIZE),
+            temp_files: Vec::new(),
+            initialize_config: InitializeConfig::default(),
+            cli_info: None,
         };
 
         let cmd = transport.build_command();
@@ -1346,14 +987,15 @@ mod tests {
             prompt: Some("test".to_string()),
             options,
             cli_path: PathBuf::from("/usr/bin/claude"),
-            cwd: None,
             process: None,
             stdin: None,
             stdout: None,
             ready: false,
-            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
             is_streaming: false,
-            json_buffer: String::new(),
+            framer: LineFramer::new(DEFAULT_MAX_BUFFER_SIZE),
+            temp_files: Vec::new(),
+            initialize_config: InitializeConfig::default(),
+            cli_info: None,
         };
 
         let cmd = transport.build_command();
@@ -1363,23 +1005,13 @@ mod tests {
 
     #[test]
     fn test_default_max_buffer_size() {
-        let options = ClaudeAgentOptions::new();
+        let options = ClaudeAgentOptions::builder()
+            .cli_path("/usr/bin/claude")
+            .build();
 
-        let transport = SubprocessCLITransport {
-            prompt: Some("test".to_string()),
-            options,
-            cli_path: PathBuf::from("/usr/bin/claude"),
-            cwd: None,
-            process: None,
-            stdin: None,
-            stdout: None,
-            ready: false,
-            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
-            is_streaming: false,
-            json_buffer: String::new(),
-        };
+        let transport = SubprocessCLITransport::new("test", options).unwrap();
 
-        assert_eq!(transport.max_buffer_size, 1024 * 1024); // 1MB default
+        assert_eq!(transport.framer.max_line_size(), 1024 * 1024); // 1MB default
     }
 
     #[test]
@@ -1390,14 +1022,15 @@ mod tests {
             prompt: None,
             options,
             cli_path: PathBuf::from("/usr/bin/claude"),
-            cwd: None,
             process: None,
             stdin: None,
             stdout: None,
             ready: false,
-            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
             is_streaming: false,
-            json_buffer: String::new(),
+            framer: LineFramer::new(DEFAULT_MAX_BUFFER_SIZE),
+            temp_files: Vec::new(),
+            initialize_config: InitializeConfig::default(),
+            cli_info: None,
         };
 
         let cmd = transport.build_command();
@@ -1415,14 +1048,15 @@ mod tests {
             prompt: Some("test".to_string()),
             options,
             cli_path: PathBuf::from("/usr/bin/claude"),
-            cwd: None,
             process: None,
             stdin: None,
             stdout: None,
             ready: false,
-            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
             is_streaming: false,
-            json_buffer: String::new(),
+            framer: LineFramer::new(DEFAULT_MAX_BUFFER_SIZE),
+            temp_files: Vec::new(),
+            initialize_config: InitializeConfig::default(),
+            cli_info: None,
         };
 
         assert!(transport.build_settings_value().is_none());
@@ -1438,14 +1072,15 @@ mod tests {
             prompt: Some("test".to_string()),
             options,
             cli_path: PathBuf::from("/usr/bin/claude"),
-            cwd: None,
             process: None,
             stdin: None,
             stdout: None,
             ready: false,
-            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
             is_streaming: false,
-            json_buffer: String::new(),
+            framer: LineFramer::new(DEFAULT_MAX_BUFFER_SIZE),
+            temp_files: Vec::new(),
+            initialize_config: InitializeConfig::default(),
+            cli_info: None,
         };
 
         assert_eq!(
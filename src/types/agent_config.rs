@@ -0,0 +1,466 @@
+//! Serializable agent configuration loaded from files and the environment.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::command::CustomCommand;
+use super::config::{
+    AgentDefinition, ClaudeAgentOptionsBuilder, McpServers, SdkPluginConfig, SettingSource,
+    SystemPrompt, Tools,
+};
+use super::mcp::McpServerConfig;
+use super::permission::PermissionMode;
+use super::sandbox::SandboxSettings;
+use crate::error::{ClaudeSDKError, Result};
+
+/// Prefix for environment variables overriding [`AgentConfig`] fields.
+pub const AGENT_CONFIG_ENV_PREFIX: &str = "CLAUDE_AGENT_";
+
+/// Serializable subset of [`ClaudeAgentOptions`](super::ClaudeAgentOptions).
+///
+/// Covers everything that can be expressed as data, so agent behavior can be
+/// tuned from a JSON file, or a TOML file with the `toml` feature, without
+/// recompiling. Convert it into a [`ClaudeAgentOptionsBuilder`] to add
+/// callbacks and hooks.
+///
+/// ```
+/// use claude_agent_sdk::types::{AgentConfig, ClaudeAgentOptionsBuilder};
+///
+/// let config = AgentConfig::from_json_str(r#"{
+///     "model": "claude-sonnet-4-5",
+///     "permission_mode": "acceptEdits",
+///     "max_turns": 10,
+///     "allowed_tools": ["Read", "Grep"]
+/// }"#).unwrap();
+///
+/// let options = ClaudeAgentOptionsBuilder::from(config).build();
+/// assert_eq!(options.max_turns, Some(10));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AgentConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fallback_model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<SystemPrompt>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Tools>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub allowed_tools: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub disallowed_tools: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permission_mode: Option<PermissionMode>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub mcp_servers: HashMap<String, McpServerConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sandbox: Option<SandboxSettings>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub agents: HashMap<String, AgentDefinition>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub plugins: Vec<SdkPluginConfig>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub custom_commands: Vec<CustomCommand>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub setting_sources: Option<Vec<SettingSource>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_turns: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_budget_usd: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_thinking_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub add_dirs: Vec<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cli_path: Option<PathBuf>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+}
+
+impl AgentConfig {
+    /// Parse a configuration from TOML.
+    #[cfg(feature = "toml")]
+    pub fn from_toml_str(source: &str) -> Result<Self> {
+        toml::from_str(source)
+            .map_err(|e| ClaudeSDKError::InvalidConfig(format!("Invalid TOML config: {}", e)))
+    }
+
+    /// Parse a configuration from JSON.
+    pub fn from_json_str(source: &str) -> Result<Self> {
+        serde_json::from_str(source)
+            .map_err(|e| ClaudeSDKError::InvalidConfig(format!("Invalid JSON config: {}", e)))
+    }
+
+    /// Read a configuration file, choosing the format by extension
+    /// (`.toml` or `.json`). TOML files require the `toml` feature.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)?;
+
+        match path.extension().and_then(|ext| ext.to_str()) {
+            #[cfg(feature = "toml")]
+            Some("toml") => Self::from_toml_str(&source),
+            #[cfg(not(feature = "toml"))]
+            Some("toml") => Err(ClaudeSDKError::InvalidConfig(format!(
+                "Reading {} requires the `toml` feature",
+                path.display()
+            ))),
+            Some("json") => Self::from_json_str(&source),
+            _ => Err(ClaudeSDKError::InvalidConfig(format!(
+                "Unsupported config file extension: {} (expected .toml or .json)",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Read a configuration file and apply environment overrides.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_file(path)?.with_env_overrides()
+    }
+
+    /// Apply overrides from `CLAUDE_AGENT_*` environment variables.
+    ///
+    /// See [`AgentConfig::with_overrides`] for the supported variables.
+    pub fn with_env_overrides(self) -> Result<Self> {
+        self.with_overrides(std::env::vars())
+    }
+
+    /// Apply overrides from `CLAUDE_AGENT_*` variables in `vars`.
+    ///
+    /// Supported variables: `MODEL`, `FALLBACK_MODEL`, `SYSTEM_PROMPT`,
+    /// `PERMISSION_MODE`, `MAX_TURNS`, `MAX_BUDGET_USD`,
+    /// `MAX_THINKING_TOKENS`, `ALLOWED_TOOLS` and `DISALLOWED_TOOLS`
+    /// (comma-separated), `CWD` and `CLI_PATH`. Other variables with the
+    /// prefix are ignored.
+    pub fn with_overrides<K, V>(mut self, vars: impl IntoIterator<Item = (K, V)>) -> Result<Self>
+    where
+        K: AsRef<str>,
+        V: Into<String>,
+    {
+        for (key, value) in vars {
+            let Some(name) = key.as_ref().strip_prefix(AGENT_CONFIG_ENV_PREFIX) else {
+                continue;
+            };
+            let value = value.into();
+
+            match name {
+                "MODEL" => self.model = Some(value),
+                "FALLBACK_MODEL" => self.fallback_model = Some(value),
+                "SYSTEM_PROMPT" => self.system_prompt = Some(SystemPrompt::Text(value)),
+                "PERMISSION_MODE" => {
+                    self.permission_mode = Some(
+                        serde_json::from_value(serde_json::Value::String(value.clone()))
+                            .map_err(|_| invalid_env(name, &value))?,
+                    )
+                }
+                "MAX_TURNS" => {
+                    self.max_turns = Some(value.parse().map_err(|_| invalid_env(name, &value))?)
+                }
+                "MAX_BUDGET_USD" => {
+                    self.max_budget_usd =
+                        Some(value.parse().map_err(|_| invalid_env(name, &value))?)
+                }
+                "MAX_THINKING_TOKENS" => {
+                    self.max_thinking_tokens =
+                        Some(value.parse().map_err(|_| invalid_env(name, &value))?)
+                }
+                "ALLOWED_TOOLS" => self.allowed_tools = split_list(&value),
+                "DISALLOWED_TOOLS" => self.disallowed_tools = split_list(&value),
+                "CWD" => self.cwd = Some(PathBuf::from(value)),
+                "CLI_PATH" => self.cli_path = Some(PathBuf::from(value)),
+                _ => {}
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Convert into an options builder, to which callbacks can be added.
+    pub fn into_builder(self) -> ClaudeAgentOptionsBuilder {
+        let mut builder = ClaudeAgentOptionsBuilder::new()
+            .allowed_tools(self.allowed_tools)
+            .disallowed_tools(self.disallowed_tools)
+            .plugins(self.plugins)
+            .custom_commands(self.custom_commands)
+            .add_dirs(self.add_dirs)
+            .env(self.env);
+
+        if let Some(model) = self.model {
+            builder = builder.model(model);
+        }
+        if let Some(model) = self.fallback_model {
+            builder = builder.fallback_model(model);
+        }
+        if let Some(prompt) = self.system_prompt {
+            builder = builder.system_prompt(prompt);
+        }
+        if let Some(tools) = self.tools {
+            builder = builder.tools(tools);
+        }
+        if let Some(mode) = self.permission_mode {
+            builder = builder.permission_mode(mode);
+        }
+        if !self.mcp_servers.is_empty() {
+            builder = builder.mcp_servers(McpServers::Map(self.mcp_servers));
+        }
+        if let Some(sandbox) = self.sandbox {
+            builder = builder.sandbox(sandbox);
+        }
+        if !self.agents.is_empty() {
+            builder = builder.agents(self.agents);
+        }
+        if let Some(sources) = self.setting_sources {
+            builder = builder.setting_sources(sources);
+        }
+        if let Some(turns) = self.max_turns {
+            builder = builder.max_turns(turns);
+        }
+        if let Some(budget) = self.max_budget_usd {
+            builder = builder.max_budget_usd(budget);
+        }
+        if let Some(tokens) = self.max_thinking_tokens {
+            builder = builder.max_thinking_tokens(tokens);
+        }
+        if let Some(cwd) = self.cwd {
+            builder = builder.cwd(cwd);
+        }
+        if let Some(path) = self.cli_path {
+            builder = builder.cli_path(path);
+        }
+
+        builder
+    }
+}
+
+impl From<AgentConfig> for ClaudeAgentOptionsBuilder {
+    fn from(config: AgentConfig) -> Self {
+        config.into_builder()
+    }
+}
+
+fn invalid_env(name: &str, value: &str) -> ClaudeSDKError {
+    ClaudeSDKError::InvalidConfig(format!(
+        "Invalid value for {}{}: {:?}",
+        AGENT_CONFIG_ENV_PREFIX, name, value
+    ))
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[cfg(feature = "toml")]
+    const TOML_CONFIG: &str = r#"
+        model = "claude-sonnet-4-5"
+        permission_mode = "acceptEdits"
+        allowed_tools = ["Read", "Grep"]
+        max_turns = 5
+        max_budget_usd = 1.5
+
+        [sandbox]
+        enabled = true
+        excludedCommands = ["git"]
+
+        [mcp_servers.fs]
+        type = "stdio"
+        command = "mcp-fs"
+        args = ["--root", "/srv"]
+
+        [agents.reviewer]
+        description = "Reviews code"
+        prompt = "You review code."
+        tools = ["Read"]
+
+        [[plugins]]
+        type = "local"
+        path = "./plugins/lint"
+
+        [[custom_commands]]
+        name = "review"
+        description = "Review a file"
+        template = "Review $1 carefully."
+    "#;
+
+    const JSON_CONFIG: &str = r#"{
+        "model": "claude-sonnet-4-5",
+        "permission_mode": "acceptEdits",
+        "allowed_tools": ["Read", "Grep"],
+        "max_turns": 5,
+        "max_budget_usd": 1.5,
+        "sandbox": {"enabled": true, "excludedCommands": ["git"]},
+        "mcp_servers": {
+            "fs": {"type": "stdio", "command": "mcp-fs", "args": ["--root", "/srv"]}
+        },
+        "agents": {
+            "reviewer": {"description": "Reviews code", "prompt": "You review code.", "tools": ["Read"]}
+        },
+        "plugins": [{"type": "local", "path": "./plugins/lint"}],
+        "custom_commands": [
+            {"name": "review", "description": "Review a file", "template": "Review $1 carefully."}
+        ]
+    }"#;
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_from_toml_str() {
+        let config = AgentConfig::from_toml_str(TOML_CONFIG).unwrap();
+
+        assert_eq!(config.model, Some("claude-sonnet-4-5".to_string()));
+        assert_eq!(config.permission_mode, Some(PermissionMode::AcceptEdits));
+        assert_eq!(config.allowed_tools, vec!["Read", "Grep"]);
+        assert_eq!(config.max_budget_usd, Some(1.5));
+        assert_eq!(config.sandbox.unwrap().enabled, Some(true));
+        assert!(config.mcp_servers.contains_key("fs"));
+        assert_eq!(
+            config.agents["reviewer"].tools,
+            Some(vec!["Read".to_string()])
+        );
+        assert_eq!(
+            config.plugins,
+            vec![SdkPluginConfig::local("./plugins/lint")]
+        );
+    }
+
+    #[test]
+    fn test_from_json_str() {
+        let config = AgentConfig::from_json_str(
+            r#"{"model": "claude-opus-4", "tools": ["Bash"], "max_thinking_tokens": 2048}"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.model, Some("claude-opus-4".to_string()));
+        assert_eq!(config.tools, Some(Tools::List(vec!["Bash".to_string()])));
+        assert_eq!(config.max_thinking_tokens, Some(2048));
+    }
+
+    #[test]
+    fn test_unknown_field_rejected() {
+        let err = AgentConfig::from_json_str(r#"{"modle": "typo"}"#).unwrap_err();
+        assert!(matches!(err, ClaudeSDKError::InvalidConfig(_)));
+        assert!(err.to_string().contains("modle"));
+    }
+
+    #[test]
+    fn test_from_file() {
+        let mut file = tempfile::Builder::new().suffix(".json").tempfile().unwrap();
+        file.write_all(br#"{"max_turns": 3}"#).unwrap();
+
+        let config = AgentConfig::from_file(file.path()).unwrap();
+        assert_eq!(config.max_turns, Some(3));
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_from_toml_file() {
+        let mut file = tempfile::Builder::new().suffix(".toml").tempfile().unwrap();
+        file.write_all(TOML_CONFIG.as_bytes()).unwrap();
+
+        let config = AgentConfig::from_file(file.path()).unwrap();
+        assert_eq!(config.max_turns, Some(5));
+    }
+
+    #[cfg(not(feature = "toml"))]
+    #[test]
+    fn test_toml_file_requires_feature() {
+        let file = tempfile::Builder::new().suffix(".toml").tempfile().unwrap();
+        let err = AgentConfig::from_file(file.path()).unwrap_err();
+        assert!(matches!(err, ClaudeSDKError::InvalidConfig(_)));
+        assert!(err.to_string().contains("`toml` feature"));
+    }
+
+    #[test]
+    fn test_from_file_unsupported_extension() {
+        let file = tempfile::Builder::new().suffix(".yaml").tempfile().unwrap();
+        let err = AgentConfig::from_file(file.path()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Unsupported config file extension"));
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        let config = AgentConfig::from_json_str(JSON_CONFIG)
+            .unwrap()
+            .with_overrides([
+                ("CLAUDE_AGENT_MODEL", "claude-haiku-4-5"),
+                ("CLAUDE_AGENT_PERMISSION_MODE", "plan"),
+                ("CLAUDE_AGENT_MAX_TURNS", "20"),
+                ("CLAUDE_AGENT_ALLOWED_TOOLS", "Read, Write,,Edit"),
+                ("CLAUDE_AGENT_UNKNOWN", "ignored"),
+                ("OTHER_VAR", "ignored"),
+            ])
+            .unwrap();
+
+        assert_eq!(config.model, Some("claude-haiku-4-5".to_string()));
+        assert_eq!(config.permission_mode, Some(PermissionMode::Plan));
+        assert_eq!(config.max_turns, Some(20));
+        assert_eq!(config.allowed_tools, vec!["Read", "Write", "Edit"]);
+        assert_eq!(config.max_budget_usd, Some(1.5));
+    }
+
+    #[test]
+    fn test_env_override_invalid_value() {
+        let err = AgentConfig::default()
+            .with_overrides([("CLAUDE_AGENT_MAX_TURNS", "many")])
+            .unwrap_err();
+        assert!(err.to_string().contains("CLAUDE_AGENT_MAX_TURNS"));
+
+        let err = AgentConfig::default()
+            .with_overrides([("CLAUDE_AGENT_PERMISSION_MODE", "yolo")])
+            .unwrap_err();
+        assert!(err.to_string().contains("CLAUDE_AGENT_PERMISSION_MODE"));
+    }
+
+    #[test]
+    fn test_into_builder() {
+        let options = AgentConfig::from_json_str(JSON_CONFIG)
+            .unwrap()
+            .into_builder()
+            .build();
+
+        assert_eq!(options.model, Some("claude-sonnet-4-5".to_string()));
+        assert_eq!(options.permission_mode, Some(PermissionMode::AcceptEdits));
+        assert_eq!(options.allowed_tools, vec!["Read", "Grep"]);
+        assert_eq!(options.max_turns, Some(5));
+        assert!(
+            matches!(options.mcp_servers, Some(McpServers::Map(ref m)) if m.contains_key("fs"))
+        );
+        assert!(options.agents.unwrap().contains_key("reviewer"));
+        assert_eq!(options.plugins.len(), 1);
+        assert_eq!(
+            options.custom_commands[0].expand("main.rs"),
+            "Review main.rs carefully."
+        );
+        assert!(options.sandbox.is_some());
+    }
+
+    #[test]
+    fn test_round_trip_json() {
+        let config = AgentConfig::from_json_str(JSON_CONFIG).unwrap();
+        let serialized = serde_json::to_string(&config).unwrap();
+        assert_eq!(AgentConfig::from_json_str(&serialized).unwrap(), config);
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_round_trip_toml() {
+        let config = AgentConfig::from_toml_str(TOML_CONFIG).unwrap();
+        let serialized = toml::to_string(&config).unwrap();
+        assert_eq!(AgentConfig::from_toml_str(&serialized).unwrap(), config);
+    }
+}
@@ -0,0 +1,260 @@
+//! Claude Code CLI version types.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::ClaudeSDKError;
+
+/// Minimum Claude Code version supported by this SDK.
+pub const MINIMUM_CLAUDE_CODE_VERSION: CliVersion = CliVersion::new(2, 0, 0);
+
+/// A Claude Code version such as `2.0.14`.
+///
+/// Missing components compare as zero, so `2.0` equals `2.0.0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CliVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl CliVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Parse the output of `claude -v`, e.g. `2.0.14 (Claude Code)`.
+    pub fn parse(output: &str) -> Option<Self> {
+        let token = output.split_whitespace().next()?;
+        let token = token.trim_start_matches('v');
+        let core = token.split(['-', '+']).next()?;
+
+        let mut parts = core.split('.').map(|part| part.parse::<u32>().ok());
+        let major = parts.next()??;
+        let minor = parts.next().unwrap_or(Some(0))?;
+        let patch = parts.next().unwrap_or(Some(0))?;
+        Some(Self::new(major, minor, patch))
+    }
+}
+
+impl PartialOrd for CliVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CliVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch).cmp(&(other.major, other.minor, other.patch))
+    }
+}
+
+impl fmt::Display for CliVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl FromStr for CliVersion {
+    type Err = ClaudeSDKError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+            .ok_or_else(|| ClaudeSDKError::InvalidConfig(format!("Invalid CLI version: {}", s)))
+    }
+}
+
+/// What to do when the detected CLI version is outside the allowed range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VersionMismatchAction {
+    /// Log a warning and continue.
+    #[default]
+    Warn,
+    /// Fail to connect.
+    Error,
+}
+
+/// Allowed range of Claude Code versions.
+///
+/// The default requires [`MINIMUM_CLAUDE_CODE_VERSION`] and warns otherwise.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliVersionPolicy {
+    /// Lowest accepted version, inclusive.
+    pub min: Option<CliVersion>,
+    /// Highest accepted version, inclusive.
+    pub max: Option<CliVersion>,
+    /// Action taken for versions outside the range.
+    pub on_mismatch: VersionMismatchAction,
+}
+
+impl Default for CliVersionPolicy {
+    fn default() -> Self {
+        Self {
+            min: Some(MINIMUM_CLAUDE_CODE_VERSION),
+            max: None,
+            on_mismatch: VersionMismatchAction::Warn,
+        }
+    }
+}
+
+impl CliVersionPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accept any version.
+    pub fn any() -> Self {
+        Self {
+            min: None,
+            max: None,
+            on_mismatch: VersionMismatchAction::Warn,
+        }
+    }
+
+    pub fn with_min(mut self, min: CliVersion) -> Self {
+        self.min = Some(min);
+        self
+    }
+
+    pub fn with_max(mut self, max: CliVersion) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    pub fn with_action(mut self, action: VersionMismatchAction) -> Self {
+        self.on_mismatch = action;
+        self
+    }
+
+    /// Describe why `version` is outside the range, if it is.
+    pub fn violation(&self, version: CliVersion) -> Option<String> {
+        match (self.min, self.max) {
+            (Some(min), _) if version < min => Some(format!(
+                "Claude Code version {} is unsupported. Minimum required: {}",
+                version, min
+            )),
+            (_, Some(max)) if version > max => Some(format!(
+                "Claude Code version {} is unsupported. Maximum allowed: {}",
+                version, max
+            )),
+            _ => None,
+        }
+    }
+
+    /// Apply the policy to a detected version.
+    pub fn check(&self, version: CliVersion) -> crate::error::Result<()> {
+        match self.violation(version) {
+            None => Ok(()),
+            Some(message) => self.mismatch(message),
+        }
+    }
+
+    /// Apply the policy when the version could not be detected.
+    ///
+    /// A policy without bounds accepts any CLI; otherwise the unknown
+    /// version is treated as a mismatch.
+    pub fn check_undetected(&self) -> crate::error::Result<()> {
+        if self.min.is_none() && self.max.is_none() {
+            return Ok(());
+        }
+        self.mismatch("Could not detect the Claude Code version".to_string())
+    }
+
+    fn mismatch(&self, message: String) -> crate::error::Result<()> {
+        match self.on_mismatch {
+            VersionMismatchAction::Warn => {
+                tracing::warn!("{}", message);
+                Ok(())
+            }
+            VersionMismatchAction::Error => Err(ClaudeSDKError::CLIConnection(message)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(s: &str) -> CliVersion {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_version_compare() {
+        assert_eq!(v("2.0.0"), v("2.0.0"));
+        assert!(v("2.1.0") > v("2.0.0"));
+        assert!(v("1.9.0") < v("2.0.0"));
+        assert!(v("2.0.1") > v("2.0.0"));
+    }
+
+    #[test]
+    fn test_version_compare_edge_cases() {
+        assert_eq!(v("1.0.0"), v("1.0.0"));
+        assert_eq!(v("0.0.1"), v("0.0.1"));
+        assert_eq!(v("1.0"), v("1.0.0"));
+        assert_eq!(v("1"), v("1.0.0"));
+        assert!(v("2") > v("1.9.9"));
+        assert!(v("0.1.0") < v("1.0.0"));
+        assert!(v("10.0.0") > v("2.0.0"));
+    }
+
+    #[test]
+    fn test_parse_cli_output() {
+        assert_eq!(
+            CliVersion::parse("2.0.14 (Claude Code)\n"),
+            Some(CliVersion::new(2, 0, 14))
+        );
+        assert_eq!(
+            CliVersion::parse("v2.1.0-beta.1"),
+            Some(CliVersion::new(2, 1, 0))
+        );
+        assert_eq!(CliVersion::parse("claude"), None);
+        assert_eq!(CliVersion::parse(""), None);
+        assert!("x.y".parse::<CliVersion>().is_err());
+    }
+
+    #[test]
+    fn test_default_policy_warns_below_minimum() {
+        let policy = CliVersionPolicy::default();
+        assert!(policy.violation(v("1.9.9")).is_some());
+        assert!(policy.violation(v("2.0.0")).is_none());
+        assert!(policy.check(v("1.0.0")).is_ok());
+    }
+
+    #[test]
+    fn test_policy_error_outside_range() {
+        let policy = CliVersionPolicy::any()
+            .with_min(v("2.0.0"))
+            .with_max(v("2.5.0"))
+            .with_action(VersionMismatchAction::Error);
+
+        assert!(policy.check(v("2.5.0")).is_ok());
+        match policy.check(v("2.6.0")) {
+            Err(ClaudeSDKError::CLIConnection(message)) => {
+                assert!(message.contains("Maximum allowed: 2.5.0"))
+            }
+            other => panic!("expected error, got {:?}", other),
+        }
+        assert!(policy.check(v("1.0.0")).is_err());
+    }
+
+    #[test]
+    fn test_policy_undetected_version() {
+        assert!(CliVersionPolicy::default().check_undetected().is_ok());
+        assert!(CliVersionPolicy::any()
+            .with_action(VersionMismatchAction::Error)
+            .check_undetected()
+            .is_ok());
+        assert!(matches!(
+            CliVersionPolicy::default()
+                .with_action(VersionMismatchAction::Error)
+                .check_undetected(),
+            Err(ClaudeSDKError::CLIConnection(_))
+        ));
+    }
+}
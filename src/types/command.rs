@@ -0,0 +1,150 @@
+//! Slash commands offered by the CLI and custom commands defined in the SDK.
+
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// A slash command the CLI accepts, such as `compact` or a project command.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SlashCommand {
+    /// Name without the leading `/`.
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Hint for the arguments, such as `<file>`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub argument_hint: Option<String>,
+}
+
+impl SlashCommand {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            description: None,
+            argument_hint: None,
+        }
+    }
+
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn with_argument_hint(mut self, hint: impl Into<String>) -> Self {
+        self.argument_hint = Some(hint.into());
+        self
+    }
+}
+
+/// A command defined in the SDK that expands into a user message.
+///
+/// The template may reference `$ARGUMENTS` for the whole argument string and
+/// `$1`, `$2`, ... for whitespace-separated arguments. If it references
+/// neither, the arguments are appended after a blank line.
+///
+/// ```
+/// use claude_agent_sdk::types::CustomCommand;
+///
+/// let review = CustomCommand::new("review", "Review a file", "Review $1 for $2 issues.");
+/// assert_eq!(review.expand("src/lib.rs security"), "Review src/lib.rs for security issues.");
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomCommand {
+    /// Name without the leading `/`.
+    pub name: String,
+    pub description: String,
+    pub template: String,
+}
+
+impl CustomCommand {
+    pub fn new(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        template: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            template: template.into(),
+        }
+    }
+
+    /// The user message this command sends for `args`.
+    pub fn expand(&self, args: &str) -> String {
+        static PLACEHOLDER: OnceLock<Regex> = OnceLock::new();
+        let placeholder =
+            PLACEHOLDER.get_or_init(|| Regex::new(r"\$(ARGUMENTS|[1-9][0-9]*)").unwrap());
+
+        let args = args.trim();
+        if !placeholder.is_match(&self.template) {
+            return if args.is_empty() {
+                self.template.clone()
+            } else {
+                format!("{}\n\n{}", self.template, args)
+            };
+        }
+
+        let positional: Vec<&str> = args.split_whitespace().collect();
+        placeholder
+            .replace_all(&self.template, |caps: &Captures| match &caps[1] {
+                "ARGUMENTS" => args.to_string(),
+                index => index
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|i| positional.get(i - 1))
+                    .map(|arg| arg.to_string())
+                    .unwrap_or_default(),
+            })
+            .into_owned()
+    }
+
+    /// Whether `name` is usable as a command name.
+    pub(crate) fn is_valid_name(name: &str) -> bool {
+        !name.is_empty() && !name.starts_with('/') && !name.contains(char::is_whitespace)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_expand_arguments() {
+        let command = CustomCommand::new("fix", "Fix an issue", "Fix issue #$ARGUMENTS.");
+        assert_eq!(command.expand(" 123 "), "Fix issue #123.");
+    }
+
+    #[test]
+    fn test_expand_positional_arguments() {
+        let command = CustomCommand::new("mv", "Move", "Move $1 to $2, not $3.");
+        assert_eq!(command.expand("a.rs b.rs"), "Move a.rs to b.rs, not .");
+    }
+
+    #[test]
+    fn test_expand_without_placeholders_appends_arguments() {
+        let command = CustomCommand::new("tests", "Write tests", "Write tests.");
+        assert_eq!(command.expand(""), "Write tests.");
+        assert_eq!(
+            command.expand("for parser.rs"),
+            "Write tests.\n\nfor parser.rs"
+        );
+    }
+
+    #[test]
+    fn test_slash_command_deserializes_camel_case() {
+        let command: SlashCommand = serde_json::from_value(json!({
+            "name": "review",
+            "description": "Review a PR",
+            "argumentHint": "<pr>"
+        }))
+        .unwrap();
+        assert_eq!(
+            command,
+            SlashCommand::new("review")
+                .with_description("Review a PR")
+                .with_argument_hint("<pr>")
+        );
+    }
+}
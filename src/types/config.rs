@@ -4,14 +4,18 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
 use std::future::Future;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use std::sync::Arc;
 
+use super::cli::CliVersionPolicy;
+use super::command::CustomCommand;
 use super::hook::{HookEvent, HookMatcher};
 use super::mcp::McpServerConfig;
 use super::permission::{PermissionMode, PermissionResult, ToolPermissionContext};
 use super::sandbox::SandboxSettings;
+use super::typed_hook::TypedHook;
+use crate::error::ClaudeSDKError;
 
 /// Type alias for the tool permission callback function.
 ///
@@ -236,6 +240,12 @@ pub struct ClaudeAgentOptions {
     /// to determine whether the tool should be allowed or denied.
     pub can_use_tool: Option<CanUseToolFn>,
 
+    /// Timeout in seconds for each `can_use_tool` call (default: 300).
+    ///
+    /// A call that runs longer is abandoned and the CLI receives an error
+    /// for the permission request.
+    pub can_use_tool_timeout: Option<f64>,
+
     /// Hook configurations for various SDK events.
     ///
     /// Maps hook events to their matchers and callbacks.
@@ -277,6 +287,12 @@ pub struct ClaudeAgentOptions {
     /// Path to Claude CLI.
     pub cli_path: Option<PathBuf>,
 
+    /// Directory containing a bundled Claude CLI.
+    pub bundled_cli_dir: Option<PathBuf>,
+
+    /// Accepted Claude CLI versions.
+    pub cli_version_policy: CliVersionPolicy,
+
     /// Settings file path or JSON.
     pub settings: Option<String>,
 
@@ -313,6 +329,10 @@ pub struct ClaudeAgentOptions {
     /// Plugin configurations.
     pub plugins: Vec<SdkPluginConfig>,
 
+    /// Commands expanded by the SDK into user messages; see
+    /// `ClaudeSDKClient::send_command`.
+    pub custom_commands: Vec<CustomCommand>,
+
     /// Max tokens for thinking blocks.
     pub max_thinking_tokens: Option<u32>,
 
@@ -321,11 +341,19 @@ pub struct ClaudeAgentOptions {
 
     /// Enable file checkpointing.
     pub enable_file_checkpointing: bool,
+
+    /// Skip [`ClaudeAgentOptions::validate`] when creating a transport.
+    pub skip_validation: bool,
+
+    /// Sink receiving session, tool and control protocol metrics.
+    #[cfg(feature = "metrics")]
+    pub metrics_sink: Option<Arc<dyn crate::metrics::MetricsSink>>,
 }
 
 impl std::fmt::Debug for ClaudeAgentOptions {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("ClaudeAgentOptions")
+        let mut debug = f.debug_struct("ClaudeAgentOptions");
+        debug
             .field("tools", &self.tools)
             .field("allowed_tools", &self.allowed_tools)
             .field("disallowed_tools", &self.disallowed_tools)
@@ -333,6 +361,7 @@ impl std::fmt::Debug for ClaudeAgentOptions {
             .field("mcp_servers", &self.mcp_servers)
             .field("permission_mode", &self.permission_mode)
             .field("can_use_tool", &self.can_use_tool.is_some())
+            .field("can_use_tool_timeout", &self.can_use_tool_timeout)
             .field("hooks", &self.hooks)
             .field("stderr", &self.stderr.is_some())
             .field("continue_conversation", &self.continue_conversation)
@@ -348,6 +377,8 @@ impl std::fmt::Debug for ClaudeAgentOptions {
             )
             .field("cwd", &self.cwd)
             .field("cli_path", &self.cli_path)
+            .field("bundled_cli_dir", &self.bundled_cli_dir)
+            .field("cli_version_policy", &self.cli_version_policy)
             .field("settings", &self.settings)
             .field("add_dirs", &self.add_dirs)
             .field("env", &self.env)
@@ -360,10 +391,14 @@ impl std::fmt::Debug for ClaudeAgentOptions {
             .field("setting_sources", &self.setting_sources)
             .field("sandbox", &self.sandbox)
             .field("plugins", &self.plugins)
+            .field("custom_commands", &self.custom_commands)
             .field("max_thinking_tokens", &self.max_thinking_tokens)
             .field("output_format", &self.output_format)
             .field("enable_file_checkpointing", &self.enable_file_checkpointing)
-            .finish()
+            .field("skip_validation", &self.skip_validation);
+        #[cfg(feature = "metrics")]
+        debug.field("metrics_sink", &self.metrics_sink.is_some());
+        debug.finish()
     }
 }
 
@@ -377,6 +412,7 @@ impl Clone for ClaudeAgentOptions {
             mcp_servers: self.mcp_servers.clone(),
             permission_mode: self.permission_mode,
             can_use_tool: self.can_use_tool.clone(),
+            can_use_tool_timeout: self.can_use_tool_timeout,
             hooks: self.hooks.clone(),
             stderr: self.stderr.clone(),
             continue_conversation: self.continue_conversation,
@@ -389,6 +425,8 @@ impl Clone for ClaudeAgentOptions {
             permission_prompt_tool_name: self.permission_prompt_tool_name.clone(),
             cwd: self.cwd.clone(),
             cli_path: self.cli_path.clone(),
+            bundled_cli_dir: self.bundled_cli_dir.clone(),
+            cli_version_policy: self.cli_version_policy.clone(),
             settings: self.settings.clone(),
             add_dirs: self.add_dirs.clone(),
             env: self.env.clone(),
@@ -401,9 +439,13 @@ impl Clone for ClaudeAgentOptions {
             setting_sources: self.setting_sources.clone(),
             sandbox: self.sandbox.clone(),
             plugins: self.plugins.clone(),
+            custom_commands: self.custom_commands.clone(),
             max_thinking_tokens: self.max_thinking_tokens,
             output_format: self.output_format.clone(),
             enable_file_checkpointing: self.enable_file_checkpointing,
+            skip_validation: self.skip_validation,
+            #[cfg(feature = "metrics")]
+            metrics_sink: self.metrics_sink.clone(),
         }
     }
 }
@@ -419,6 +461,7 @@ impl Default for ClaudeAgentOptions {
             mcp_servers: None,
             permission_mode: None,
             can_use_tool: None,
+            can_use_tool_timeout: None,
             hooks: HashMap::new(),
             stderr: None,
             continue_conversation: false,
@@ -431,6 +474,8 @@ impl Default for ClaudeAgentOptions {
             permission_prompt_tool_name: None,
             cwd: None,
             cli_path: None,
+            bundled_cli_dir: None,
+            cli_version_policy: CliVersionPolicy::default(),
             settings: None,
             add_dirs: Vec::new(),
             env: HashMap::new(),
@@ -443,9 +488,13 @@ impl Default for ClaudeAgentOptions {
             setting_sources: None,
             sandbox: None,
             plugins: Vec::new(),
+            custom_commands: Vec::new(),
             max_thinking_tokens: None,
             output_format: None,
             enable_file_checkpointing: false,
+            skip_validation: false,
+            #[cfg(feature = "metrics")]
+            metrics_sink: None,
         }
     }
 }
@@ -460,6 +509,120 @@ impl ClaudeAgentOptions {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Check the options for misconfigurations the CLI would reject or
+    /// silently ignore.
+    ///
+    /// Returns one [`ClaudeSDKError::InvalidConfig`] per problem found; an
+    /// empty list means the options are valid.
+    pub fn validate(&self) -> Vec<ClaudeSDKError> {
+        let mut problems = Vec::new();
+
+        if let Some(ref format) = self.output_format {
+            if format.get("type").and_then(Value::as_str) != Some("json_schema") {
+                problems.push(
+                    "output_format must have \"type\": \"json_schema\"; other formats are ignored"
+                        .to_string(),
+                );
+            } else if format.get("schema").is_none() {
+                problems
+                    .push("output_format of type json_schema is missing \"schema\"".to_string());
+            }
+        }
+
+        if let Some(budget) = self.max_budget_usd {
+            if budget.is_nan() || budget < 0.0 {
+                problems.push(format!(
+                    "max_budget_usd must be a non-negative number, got {}",
+                    budget
+                ));
+            }
+        }
+
+        if self.can_use_tool.is_some() && self.permission_prompt_tool_name.is_some() {
+            problems.push(
+                "can_use_tool cannot be used together with permission_prompt_tool_name".to_string(),
+            );
+        }
+
+        if let Some(timeout) = self.can_use_tool_timeout {
+            if !timeout.is_finite() || timeout < 0.0 {
+                problems.push(format!(
+                    "can_use_tool timeout must be a non-negative number of seconds, got {}",
+                    timeout
+                ));
+            }
+        }
+
+        if let Some(ref cwd) = self.cwd {
+            if !cwd.is_dir() {
+                problems.push(format!(
+                    "cwd does not exist or is not a directory: {}",
+                    cwd.display()
+                ));
+            }
+        }
+
+        for dir in &self.add_dirs {
+            if !self.resolve(dir).is_dir() {
+                problems.push(format!(
+                    "add_dirs entry does not exist or is not a directory: {}",
+                    dir.display()
+                ));
+            }
+        }
+
+        for plugin in &self.plugins {
+            if plugin.plugin_type != "local" {
+                problems.push(format!(
+                    "Unsupported plugin type \"{}\" for {}",
+                    plugin.plugin_type, plugin.path
+                ));
+            } else if !self.resolve(Path::new(&plugin.path)).exists() {
+                problems.push(format!("Plugin path does not exist: {}", plugin.path));
+            }
+        }
+
+        for (event, matchers) in &self.hooks {
+            for matcher in matchers {
+                if let Some(timeout) = matcher.timeout {
+                    if !timeout.is_finite() || timeout < 0.0 {
+                        problems.push(format!(
+                            "{} hook timeout must be a non-negative number of seconds, got {}",
+                            event, timeout
+                        ));
+                    }
+                }
+            }
+        }
+
+        for (i, command) in self.custom_commands.iter().enumerate() {
+            if !CustomCommand::is_valid_name(&command.name) {
+                problems.push(format!(
+                    "Custom command name must be non-empty, without whitespace or a leading '/': {:?}",
+                    command.name
+                ));
+            } else if self.custom_commands[..i]
+                .iter()
+                .any(|other| other.name == command.name)
+            {
+                problems.push(format!("Duplicate custom command: /{}", command.name));
+            }
+        }
+
+        problems
+            .into_iter()
+            .map(ClaudeSDKError::InvalidConfig)
+            .collect()
+    }
+
+    /// Resolve a path the way the CLI will, relative to `cwd` when set.
+    fn resolve(&self, path: &Path) -> PathBuf {
+        match self.cwd {
+            Some(ref cwd) => cwd.join(path),
+            None => path.to_path_buf(),
+        }
+    }
 }
 
 /// Builder for ClaudeAgentOptions.
@@ -537,6 +700,12 @@ impl ClaudeAgentOptionsBuilder {
         self
     }
 
+    /// Set the timeout in seconds for each can_use_tool call.
+    pub fn can_use_tool_timeout(mut self, timeout: f64) -> Self {
+        self.options.can_use_tool_timeout = Some(timeout);
+        self
+    }
+
     /// Set hook configurations for SDK events.
     ///
     /// Maps hook events to their matchers and callbacks.
@@ -554,6 +723,12 @@ impl ClaudeAgentOptionsBuilder {
         self
     }
 
+    /// Add a typed hook under the event its callback handles.
+    pub fn hook(self, hook: TypedHook) -> Self {
+        let (event, matcher) = hook.into_parts();
+        self.add_hook(event, matcher)
+    }
+
     /// Set the stderr callback.
     ///
     /// When set, stderr output from the Claude CLI process will be
@@ -613,6 +788,18 @@ impl ClaudeAgentOptionsBuilder {
         self
     }
 
+    /// Look for a bundled `claude` executable in this directory.
+    pub fn bundled_cli_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.options.bundled_cli_dir = Some(dir.into());
+        self
+    }
+
+    /// Set the accepted CLI version range and what happens outside it.
+    pub fn cli_version_policy(mut self, policy: CliVersionPolicy) -> Self {
+        self.options.cli_version_policy = policy;
+        self
+    }
+
     pub fn settings(mut self, settings: impl Into<String>) -> Self {
         self.options.settings = Some(settings.into());
         self
@@ -673,6 +860,11 @@ impl ClaudeAgentOptionsBuilder {
         self
     }
 
+    pub fn custom_commands(mut self, commands: Vec<CustomCommand>) -> Self {
+        self.options.custom_commands = commands;
+        self
+    }
+
     pub fn max_thinking_tokens(mut self, tokens: u32) -> Self {
         self.options.max_thinking_tokens = Some(tokens);
         self
@@ -683,16 +875,45 @@ impl ClaudeAgentOptionsBuilder {
         self
     }
 
+    /// Request structured output matching the JSON schema of `T`.
+    #[cfg(feature = "schemars")]
+    pub fn output_type<T: schemars::JsonSchema>(mut self) -> Self {
+        self.options.output_format = Some(json_schema_format::<T>());
+        self
+    }
+
     pub fn enable_file_checkpointing(mut self, enable: bool) -> Self {
         self.options.enable_file_checkpointing = enable;
         self
     }
 
+    /// Skip validation of the options when creating a transport.
+    pub fn skip_validation(mut self, skip: bool) -> Self {
+        self.options.skip_validation = skip;
+        self
+    }
+
+    /// Record metrics into `sink`.
+    #[cfg(feature = "metrics")]
+    pub fn metrics_sink(mut self, sink: Arc<dyn crate::metrics::MetricsSink>) -> Self {
+        self.options.metrics_sink = Some(sink);
+        self
+    }
+
     pub fn build(self) -> ClaudeAgentOptions {
         self.options
     }
 }
 
+/// Build an `output_format` value requesting JSON matching the schema of `T`.
+#[cfg(feature = "schemars")]
+pub fn json_schema_format<T: schemars::JsonSchema>() -> Value {
+    serde_json::json!({
+        "type": "json_schema",
+        "schema": schemars::schema_for!(T),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -893,6 +1114,144 @@ mod tests {
         assert_eq!(options.max_thinking_tokens, Some(1000));
     }
 
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn test_builder_output_type() {
+        #[derive(schemars::JsonSchema)]
+        #[allow(dead_code)]
+        struct Person {
+            name: String,
+            age: u32,
+        }
+
+        let options = ClaudeAgentOptions::builder()
+            .output_type::<Person>()
+            .build();
+        let format = options.output_format.unwrap();
+        assert_eq!(format["type"], "json_schema");
+        assert_eq!(format["schema"]["type"], "object");
+        assert_eq!(format["schema"]["properties"]["age"]["type"], "integer");
+    }
+
+    #[test]
+    fn test_validate_default_options() {
+        assert!(ClaudeAgentOptions::default().validate().is_empty());
+    }
+
+    #[test]
+    fn test_validate_reports_all_problems() {
+        let can_use_tool: CanUseToolFn =
+            Arc::new(|_tool_name, _input, _context| Box::pin(async { PermissionResult::allow() }));
+        let options = ClaudeAgentOptions::builder()
+            .output_format(serde_json::json!({"type": "json", "schema": {}}))
+            .max_budget_usd(-1.0)
+            .can_use_tool(can_use_tool)
+            .permission_prompt_tool_name("MyTool")
+            .cwd("/nonexistent/cwd")
+            .add_dirs(vec![PathBuf::from("/nonexistent/dir")])
+            .plugins(vec![SdkPluginConfig::local("/nonexistent/plugin")])
+            .build();
+
+        let problems: Vec<String> = options
+            .validate()
+            .into_iter()
+            .map(|err| {
+                assert!(matches!(err, ClaudeSDKError::InvalidConfig(_)));
+                err.to_string()
+            })
+            .collect();
+
+        assert_eq!(problems.len(), 6);
+        assert!(problems[0].contains("json_schema"));
+        assert!(problems[1].contains("max_budget_usd"));
+        assert!(problems[2].contains("permission_prompt_tool_name"));
+        assert!(problems[3].contains("/nonexistent/cwd"));
+        assert!(problems[4].contains("/nonexistent/dir"));
+        assert!(problems[5].contains("/nonexistent/plugin"));
+    }
+
+    #[test]
+    fn test_validate_json_schema_without_schema() {
+        let options = ClaudeAgentOptions::builder()
+            .output_format(serde_json::json!({"type": "json_schema"}))
+            .build();
+        let problems = options.validate();
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].to_string().contains("missing \"schema\""));
+    }
+
+    #[test]
+    fn test_validate_existing_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = ClaudeAgentOptions::builder()
+            .cwd(dir.path())
+            .add_dirs(vec![dir.path().to_path_buf()])
+            .plugins(vec![SdkPluginConfig::local(dir.path().to_string_lossy())])
+            .max_budget_usd(0.0)
+            .build();
+        assert!(options.validate().is_empty());
+    }
+
+    #[test]
+    fn test_validate_resolves_relative_paths_against_cwd() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("extra")).unwrap();
+        std::fs::create_dir(dir.path().join("plugin")).unwrap();
+        let options = ClaudeAgentOptions::builder()
+            .cwd(dir.path())
+            .add_dirs(vec![PathBuf::from("extra")])
+            .plugins(vec![SdkPluginConfig::local("plugin")])
+            .build();
+        assert!(options.validate().is_empty());
+
+        let options = ClaudeAgentOptions::builder()
+            .cwd(dir.path())
+            .add_dirs(vec![PathBuf::from("missing")])
+            .build();
+        let problems = options.validate();
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].to_string().contains("missing"));
+    }
+
+    #[test]
+    fn test_validate_hook_timeouts() {
+        let options = ClaudeAgentOptions::builder()
+            .add_hook(HookEvent::Stop, HookMatcher::new().with_timeout(-1.0))
+            .add_hook(HookEvent::Stop, HookMatcher::new().with_timeout(f64::NAN))
+            .add_hook(HookEvent::Stop, HookMatcher::new().with_timeout(30.0))
+            .build();
+        let problems = options.validate();
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].to_string().contains("Stop hook timeout"));
+    }
+
+    #[test]
+    fn test_validate_can_use_tool_timeout() {
+        let options = ClaudeAgentOptions::builder()
+            .can_use_tool_timeout(f64::INFINITY)
+            .build();
+        let problems = options.validate();
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].to_string().contains("can_use_tool timeout"));
+    }
+
+    #[test]
+    fn test_validate_custom_commands() {
+        let options = ClaudeAgentOptions::builder()
+            .custom_commands(vec![
+                CustomCommand::new("review", "Review", "Review $1"),
+                CustomCommand::new("review", "Again", "Review again"),
+                CustomCommand::new("/fix", "Fix", "Fix $ARGUMENTS"),
+            ])
+            .build();
+        let problems = options.validate();
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0]
+            .to_string()
+            .contains("Duplicate custom command: /review"));
+        assert!(problems[1].to_string().contains("\"/fix\""));
+    }
+
     #[test]
     fn test_builder_output_format() {
         let format = serde_json::json!({"type": "json"});
@@ -144,6 +144,12 @@ pub enum SDKControlRequestVariant {
     Initialize {
         #[serde(skip_serializing_if = "Option::is_none")]
         hooks: Option<HashMap<String, Value>>,
+        /// Agent definitions too large to pass as `--agents`.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        agents: Option<Value>,
+        /// Output schema too large to pass as `--json-schema`.
+        #[serde(rename = "jsonSchema", skip_serializing_if = "Option::is_none")]
+        json_schema: Option<Value>,
     },
     SetPermissionMode {
         mode: String,
@@ -196,7 +202,14 @@ impl SDKControlRequest {
         request_id: impl Into<String>,
         hooks: Option<HashMap<String, Value>>,
     ) -> Self {
-        Self::new(request_id, SDKControlRequestVariant::Initialize { hooks })
+        Self::new(
+            request_id,
+            SDKControlRequestVariant::Initialize {
+                hooks,
+                agents: None,
+                json_schema: None,
+            },
+        )
     }
 
     /// Create a set permission mode request.
@@ -1,11 +1,13 @@
 //! Hook types for Claude SDK.
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
 
+use super::permission::PermissionUpdate;
+
 /// Supported hook event types.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum HookEvent {
@@ -16,6 +18,11 @@ pub enum HookEvent {
     Stop,
     SubagentStop,
     PreCompact,
+    Notification,
+    SessionStart,
+    SessionEnd,
+    SubagentStart,
+    PermissionRequest,
 }
 
 impl HookEvent {
@@ -28,6 +35,11 @@ impl HookEvent {
             Self::Stop => "Stop",
             Self::SubagentStop => "SubagentStop",
             Self::PreCompact => "PreCompact",
+            Self::Notification => "Notification",
+            Self::SessionStart => "SessionStart",
+            Self::SessionEnd => "SessionEnd",
+            Self::SubagentStart => "SubagentStart",
+            Self::PermissionRequest => "PermissionRequest",
         }
     }
 }
@@ -129,8 +141,127 @@ pub struct PreCompactHookInput {
     pub custom_instructions: Option<String>,
 }
 
-/// Union type for all hook inputs.
+/// Input data for Notification hook events.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NotificationHookInput {
+    #[serde(flatten)]
+    pub base: BaseHookInput,
+    pub hook_event_name: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notification_type: Option<String>,
+}
+
+/// Define an enum of CLI strings that keeps unknown values in `Other`.
+macro_rules! open_string_enum {
+    ($(#[$meta:meta])* $name:ident { $($variant:ident => $value:literal),* $(,)? }) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub enum $name {
+            $($variant,)*
+            /// A value this SDK does not know, as the CLI sent it.
+            Other(String),
+        }
+
+        impl $name {
+            /// The value as the CLI spells it.
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $(Self::$variant => $value,)*
+                    Self::Other(value) => value,
+                }
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(value: String) -> Self {
+                match value.as_str() {
+                    $($value => Self::$variant,)*
+                    _ => Self::Other(value),
+                }
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+                String::deserialize(deserializer).map(Self::from)
+            }
+        }
+    };
+}
+
+open_string_enum! {
+    /// Source that started a session.
+    SessionStartSource {
+        Startup => "startup",
+        Resume => "resume",
+        Clear => "clear",
+        Compact => "compact",
+    }
+}
+
+/// Input data for SessionStart hook events.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionStartHookInput {
+    #[serde(flatten)]
+    pub base: BaseHookInput,
+    pub hook_event_name: String,
+    pub source: SessionStartSource,
+}
+
+open_string_enum! {
+    /// Reason a session ended.
+    SessionEndReason {
+        Clear => "clear",
+        Logout => "logout",
+        PromptInputExit => "prompt_input_exit",
+    }
+}
+
+/// Input data for SessionEnd hook events.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionEndHookInput {
+    #[serde(flatten)]
+    pub base: BaseHookInput,
+    pub hook_event_name: String,
+    pub reason: SessionEndReason,
+}
+
+/// Input data for SubagentStart hook events.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubagentStartHookInput {
+    #[serde(flatten)]
+    pub base: BaseHookInput,
+    pub hook_event_name: String,
+    pub agent_id: String,
+    pub agent_type: String,
+}
+
+/// Input data for PermissionRequest hook events.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PermissionRequestHookInput {
+    #[serde(flatten)]
+    pub base: BaseHookInput,
+    pub hook_event_name: String,
+    pub tool_name: String,
+    pub tool_input: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permission_suggestions: Option<Vec<Value>>,
+}
+
+/// Union type for all hook inputs.
+///
+/// Several events share the same shape (e.g. Stop and SubagentStop), so
+/// deserialization dispatches on `hook_event_name` rather than on structure.
+#[derive(Debug, Clone, PartialEq, Serialize)]
 #[serde(untagged)]
 pub enum HookInput {
     PreToolUse(PreToolUseHookInput),
@@ -140,6 +271,47 @@ pub enum HookInput {
     Stop(StopHookInput),
     SubagentStop(SubagentStopHookInput),
     PreCompact(PreCompactHookInput),
+    Notification(NotificationHookInput),
+    SessionStart(SessionStartHookInput),
+    SessionEnd(SessionEndHookInput),
+    SubagentStart(SubagentStartHookInput),
+    PermissionRequest(PermissionRequestHookInput),
+}
+
+impl<'de> Deserialize<'de> for HookInput {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let value = Value::deserialize(deserializer)?;
+        let event_name = value
+            .get("hook_event_name")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| D::Error::missing_field("hook_event_name"))?
+            .to_string();
+
+        let input = match event_name.as_str() {
+            "PreToolUse" => serde_json::from_value(value).map(Self::PreToolUse),
+            "PostToolUse" => serde_json::from_value(value).map(Self::PostToolUse),
+            "PostToolUseFailure" => serde_json::from_value(value).map(Self::PostToolUseFailure),
+            "UserPromptSubmit" => serde_json::from_value(value).map(Self::UserPromptSubmit),
+            "Stop" => serde_json::from_value(value).map(Self::Stop),
+            "SubagentStop" => serde_json::from_value(value).map(Self::SubagentStop),
+            "PreCompact" => serde_json::from_value(value).map(Self::PreCompact),
+            "Notification" => serde_json::from_value(value).map(Self::Notification),
+            "SessionStart" => serde_json::from_value(value).map(Self::SessionStart),
+            "SessionEnd" => serde_json::from_value(value).map(Self::SessionEnd),
+            "SubagentStart" => serde_json::from_value(value).map(Self::SubagentStart),
+            "PermissionRequest" => serde_json::from_value(value).map(Self::PermissionRequest),
+            other => {
+                return Err(D::Error::custom(format!(
+                    "unknown hook event name: {}",
+                    other
+                )))
+            }
+        };
+
+        input.map_err(D::Error::custom)
+    }
 }
 
 impl HookInput {
@@ -153,6 +325,29 @@ impl HookInput {
             Self::Stop(input) => &input.hook_event_name,
             Self::SubagentStop(input) => &input.hook_event_name,
             Self::PreCompact(input) => &input.hook_event_name,
+            Self::Notification(input) => &input.hook_event_name,
+            Self::SessionStart(input) => &input.hook_event_name,
+            Self::SessionEnd(input) => &input.hook_event_name,
+            Self::SubagentStart(input) => &input.hook_event_name,
+            Self::PermissionRequest(input) => &input.hook_event_name,
+        }
+    }
+
+    /// Get the hook event this input belongs to.
+    pub fn event(&self) -> HookEvent {
+        match self {
+            Self::PreToolUse(_) => HookEvent::PreToolUse,
+            Self::PostToolUse(_) => HookEvent::PostToolUse,
+            Self::PostToolUseFailure(_) => HookEvent::PostToolUseFailure,
+            Self::UserPromptSubmit(_) => HookEvent::UserPromptSubmit,
+            Self::Stop(_) => HookEvent::Stop,
+            Self::SubagentStop(_) => HookEvent::SubagentStop,
+            Self::PreCompact(_) => HookEvent::PreCompact,
+            Self::Notification(_) => HookEvent::Notification,
+            Self::SessionStart(_) => HookEvent::SessionStart,
+            Self::SessionEnd(_) => HookEvent::SessionEnd,
+            Self::SubagentStart(_) => HookEvent::SubagentStart,
+            Self::PermissionRequest(_) => HookEvent::PermissionRequest,
         }
     }
 
@@ -166,10 +361,42 @@ impl HookInput {
             Self::Stop(input) => &input.base,
             Self::SubagentStop(input) => &input.base,
             Self::PreCompact(input) => &input.base,
+            Self::Notification(input) => &input.base,
+            Self::SessionStart(input) => &input.base,
+            Self::SessionEnd(input) => &input.base,
+            Self::SubagentStart(input) => &input.base,
+            Self::PermissionRequest(input) => &input.base,
+        }
+    }
+
+    /// Get the value matchers are evaluated against for this event.
+    ///
+    /// Tool events match on the tool name; SessionStart, SessionEnd,
+    /// PreCompact, Notification and SubagentStart match on their source,
+    /// reason, trigger, notification type and agent type respectively.
+    /// Other events have no match value and run every matcher.
+    pub fn match_value(&self) -> Option<String> {
+        match self {
+            Self::PreToolUse(input) => Some(input.tool_name.clone()),
+            Self::PostToolUse(input) => Some(input.tool_name.clone()),
+            Self::PostToolUseFailure(input) => Some(input.tool_name.clone()),
+            Self::PermissionRequest(input) => Some(input.tool_name.clone()),
+            Self::PreCompact(input) => enum_str(&input.trigger),
+            Self::SessionStart(input) => Some(input.source.as_str().to_string()),
+            Self::SessionEnd(input) => Some(input.reason.as_str().to_string()),
+            Self::Notification(input) => input.notification_type.clone(),
+            Self::SubagentStart(input) => Some(input.agent_type.clone()),
+            Self::UserPromptSubmit(_) | Self::Stop(_) | Self::SubagentStop(_) => None,
         }
     }
 }
 
+fn enum_str<T: Serialize>(value: &T) -> Option<String> {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|v| v.as_str().map(String::from))
+}
+
 /// Permission decision for PreToolUse hooks.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -255,14 +482,179 @@ impl UserPromptSubmitHookSpecificOutput {
     }
 }
 
-/// Union type for hook-specific outputs.
+/// Hook-specific output for SessionStart events.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionStartHookSpecificOutput {
+    pub hook_event_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub additional_context: Option<String>,
+}
+
+impl SessionStartHookSpecificOutput {
+    pub fn new() -> Self {
+        Self {
+            hook_event_name: "SessionStart".to_string(),
+            additional_context: None,
+        }
+    }
+}
+
+/// Hook-specific output for Notification events.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationHookSpecificOutput {
+    pub hook_event_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub additional_context: Option<String>,
+}
+
+impl NotificationHookSpecificOutput {
+    pub fn new() -> Self {
+        Self {
+            hook_event_name: "Notification".to_string(),
+            additional_context: None,
+        }
+    }
+}
+
+/// Hook-specific output for SubagentStart events.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubagentStartHookSpecificOutput {
+    pub hook_event_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub additional_context: Option<String>,
+}
+
+impl SubagentStartHookSpecificOutput {
+    pub fn new() -> Self {
+        Self {
+            hook_event_name: "SubagentStart".to_string(),
+            additional_context: None,
+        }
+    }
+}
+
+/// Verdict sent back to the CLI for a PermissionRequest hook.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "behavior", rename_all = "lowercase")]
+pub enum PermissionRequestVerdict {
+    Allow {
+        #[serde(
+            rename = "updatedInput",
+            skip_serializing_if = "Option::is_none",
+            default
+        )]
+        updated_input: Option<Value>,
+        #[serde(
+            rename = "updatedPermissions",
+            skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_permission_updates",
+            default
+        )]
+        updated_permissions: Option<Vec<PermissionUpdate>>,
+    },
+    Deny {
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        message: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        interrupt: Option<bool>,
+    },
+}
+
+impl PermissionRequestVerdict {
+    /// Allow the request with the original input.
+    pub fn allow() -> Self {
+        Self::Allow {
+            updated_input: None,
+            updated_permissions: None,
+        }
+    }
+
+    /// Deny the request with a message for Claude.
+    pub fn deny(message: impl Into<String>) -> Self {
+        Self::Deny {
+            message: Some(message.into()),
+            interrupt: None,
+        }
+    }
+}
+
+fn serialize_permission_updates<S: Serializer>(
+    updates: &Option<Vec<PermissionUpdate>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let dicts: Option<Vec<_>> = updates
+        .as_ref()
+        .map(|updates| updates.iter().map(|u| u.to_dict()).collect());
+    dicts.serialize(serializer)
+}
+
+/// Hook-specific output for PermissionRequest events.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionRequestHookSpecificOutput {
+    pub hook_event_name: String,
+    pub decision: PermissionRequestVerdict,
+}
+
+impl PermissionRequestHookSpecificOutput {
+    pub fn new(decision: PermissionRequestVerdict) -> Self {
+        Self {
+            hook_event_name: "PermissionRequest".to_string(),
+            decision,
+        }
+    }
+}
+
+/// Union type for hook-specific outputs.
+///
+/// Deserialization dispatches on `hookEventName`, since several variants
+/// share the same shape.
+#[derive(Debug, Clone, PartialEq, Serialize)]
 #[serde(untagged)]
 pub enum HookSpecificOutput {
     PreToolUse(PreToolUseHookSpecificOutput),
     PostToolUse(PostToolUseHookSpecificOutput),
     PostToolUseFailure(PostToolUseFailureHookSpecificOutput),
     UserPromptSubmit(UserPromptSubmitHookSpecificOutput),
+    SessionStart(SessionStartHookSpecificOutput),
+    Notification(NotificationHookSpecificOutput),
+    SubagentStart(SubagentStartHookSpecificOutput),
+    PermissionRequest(PermissionRequestHookSpecificOutput),
+}
+
+impl<'de> Deserialize<'de> for HookSpecificOutput {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let value = Value::deserialize(deserializer)?;
+        let event_name = value
+            .get("hookEventName")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| D::Error::missing_field("hookEventName"))?
+            .to_string();
+
+        let output = match event_name.as_str() {
+            "PreToolUse" => serde_json::from_value(value).map(Self::PreToolUse),
+            "PostToolUse" => serde_json::from_value(value).map(Self::PostToolUse),
+            "PostToolUseFailure" => serde_json::from_value(value).map(Self::PostToolUseFailure),
+            "UserPromptSubmit" => serde_json::from_value(value).map(Self::UserPromptSubmit),
+            "SessionStart" => serde_json::from_value(value).map(Self::SessionStart),
+            "Notification" => serde_json::from_value(value).map(Self::Notification),
+            "SubagentStart" => serde_json::from_value(value).map(Self::SubagentStart),
+            "PermissionRequest" => serde_json::from_value(value).map(Self::PermissionRequest),
+            other => {
+                return Err(D::Error::custom(format!(
+                    "unknown hook event name: {}",
+                    other
+                )))
+            }
+        };
+
+        output.map_err(D::Error::custom)
+    }
 }
 
 /// Hook JSON output for synchronous hooks.
@@ -347,12 +739,19 @@ impl HookJSONOutput {
 pub struct HookContext {
     /// Reserved for future abort signal support.
     pub signal: Option<()>,
+    /// ID of the tool use that triggered the hook, if any.
+    pub tool_use_id: Option<String>,
 }
 
 impl HookContext {
     pub fn new() -> Self {
         Self::default()
     }
+
+    pub fn with_tool_use_id(mut self, tool_use_id: Option<String>) -> Self {
+        self.tool_use_id = tool_use_id;
+        self
+    }
 }
 
 /// Type alias for hook callback function.
@@ -366,7 +765,28 @@ pub type HookCallbackFn = Arc<
         + Sync,
 >;
 
+/// Type alias for the callback receiving late results of async hooks.
+pub type AsyncHookResultFn =
+    Arc<dyn Fn(HookEvent, crate::error::Result<HookJSONOutput>) + Send + Sync>;
+
 /// Hook matcher configuration.
+///
+/// All fields are public, so a matcher can be built with a struct literal
+/// as well as with the builder methods:
+///
+/// ```
+/// use claude_agent_sdk::types::HookMatcher;
+///
+/// let matcher = HookMatcher {
+///     matcher: Some("Write|Edit".to_string()),
+///     hooks: Vec::new(),
+///     timeout: Some(30.0),
+///     is_async: false,
+///     async_timeout: None,
+///     on_async_result: None,
+/// };
+/// assert!(matcher.matches(Some("Edit")));
+/// ```
 #[derive(Clone)]
 pub struct HookMatcher {
     /// Pattern to match tool names (e.g., "Bash" or "Write|MultiEdit|Edit").
@@ -375,6 +795,12 @@ pub struct HookMatcher {
     pub hooks: Vec<HookCallbackFn>,
     /// Timeout in seconds for all hooks in this matcher (default: 60).
     pub timeout: Option<f64>,
+    /// Run hooks in the background, replying to the CLI immediately.
+    pub is_async: bool,
+    /// Timeout in milliseconds the CLI applies to async hooks.
+    pub async_timeout: Option<u64>,
+    /// Receives the late result of async hooks once they complete.
+    pub on_async_result: Option<AsyncHookResultFn>,
 }
 
 impl std::fmt::Debug for HookMatcher {
@@ -383,6 +809,9 @@ impl std::fmt::Debug for HookMatcher {
             .field("matcher", &self.matcher)
             .field("hooks_count", &self.hooks.len())
             .field("timeout", &self.timeout)
+            .field("is_async", &self.is_async)
+            .field("async_timeout", &self.async_timeout)
+            .field("has_on_async_result", &self.on_async_result.is_some())
             .finish()
     }
 }
@@ -399,6 +828,9 @@ impl HookMatcher {
             matcher: None,
             hooks: Vec::new(),
             timeout: None,
+            is_async: false,
+            async_timeout: None,
+            on_async_result: None,
         }
     }
 
@@ -416,6 +848,79 @@ impl HookMatcher {
         self.timeout = Some(timeout);
         self
     }
+
+    /// Run hooks fire-and-forget: the SDK replies `{"async": true}` at once
+    /// and runs the callbacks in the background.
+    ///
+    /// `async_timeout` is in milliseconds and bounds the background run;
+    /// without it the matcher's `timeout` applies.
+    pub fn with_async(mut self, async_timeout: Option<u64>) -> Self {
+        self.is_async = true;
+        self.async_timeout = async_timeout;
+        self
+    }
+
+    /// Set a callback receiving the result of async hooks once they finish.
+    pub fn with_async_result_handler(mut self, handler: AsyncHookResultFn) -> Self {
+        self.on_async_result = Some(handler);
+        self
+    }
+
+    /// Check whether this matcher applies to a value, using the CLI's rules.
+    ///
+    /// A missing, empty or `*` matcher matches everything. A matcher made of
+    /// only word characters and `|` is an exact match against one of the
+    /// alternatives. Anything else is treated as an unanchored regex; an
+    /// invalid regex matches nothing.
+    ///
+    /// Regex matchers are compiled on each call; [`HookRegistry`] compiles
+    /// them once when it is built.
+    ///
+    /// [`HookRegistry`]: crate::hooks::HookRegistry
+    pub fn matches(&self, value: Option<&str>) -> bool {
+        self.matches_compiled(value, self.compile().as_ref())
+    }
+
+    /// Compile the matcher if it is a regex rather than a wildcard or names.
+    pub(crate) fn compile(&self) -> Option<regex::Regex> {
+        match self.matcher.as_deref() {
+            Some(pattern) if !is_wildcard(pattern) && !is_name_list(pattern) => {
+                regex::Regex::new(pattern).ok()
+            }
+            _ => None,
+        }
+    }
+
+    /// Like [`matches`](Self::matches), with the regex from [`compile`](Self::compile).
+    pub(crate) fn matches_compiled(
+        &self,
+        value: Option<&str>,
+        regex: Option<&regex::Regex>,
+    ) -> bool {
+        let pattern = match self.matcher.as_deref() {
+            None => return true,
+            Some(pattern) if is_wildcard(pattern) => return true,
+            Some(pattern) => pattern,
+        };
+        let Some(value) = value else {
+            return false;
+        };
+
+        if is_name_list(pattern) {
+            return pattern.split('|').any(|alt| alt.trim() == value);
+        }
+        regex.is_some_and(|re| re.is_match(value))
+    }
+}
+
+fn is_wildcard(pattern: &str) -> bool {
+    pattern.is_empty() || pattern == "*"
+}
+
+fn is_name_list(pattern: &str) -> bool {
+    pattern
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '|')
 }
 
 #[cfg(test)]
@@ -473,6 +978,11 @@ mod tests {
             (HookEvent::Stop, "Stop"),
             (HookEvent::SubagentStop, "SubagentStop"),
             (HookEvent::PreCompact, "PreCompact"),
+            (HookEvent::Notification, "Notification"),
+            (HookEvent::SessionStart, "SessionStart"),
+            (HookEvent::SessionEnd, "SessionEnd"),
+            (HookEvent::SubagentStart, "SubagentStart"),
+            (HookEvent::PermissionRequest, "PermissionRequest"),
         ];
 
         for (event, expected_str) in events {
@@ -499,6 +1009,7 @@ mod tests {
     fn test_hook_context_new() {
         let context = HookContext::new();
         assert!(context.signal.is_none());
+        assert!(context.tool_use_id.is_none());
     }
 
     #[test]
@@ -735,4 +1246,215 @@ mod tests {
         let json = serde_json::to_string(&post_tool).unwrap();
         assert!(json.contains("PostToolUse"));
     }
+
+    #[test]
+    fn test_hook_specific_output_round_trips_every_variant() {
+        fn with_context<T>(mut output: T, set: fn(&mut T) -> &mut Option<String>) -> T {
+            *set(&mut output) = Some("context".to_string());
+            output
+        }
+
+        let mut pre_tool = PreToolUseHookSpecificOutput::new();
+        pre_tool.permission_decision = Some(HookPermissionDecision::Deny);
+        pre_tool.permission_decision_reason = Some("no".to_string());
+        pre_tool.updated_input = Some(serde_json::json!({"command": "ls"}));
+
+        let outputs = vec![
+            HookSpecificOutput::PreToolUse(pre_tool),
+            HookSpecificOutput::PostToolUse(with_context(
+                PostToolUseHookSpecificOutput::new(),
+                |o| &mut o.additional_context,
+            )),
+            HookSpecificOutput::PostToolUseFailure(with_context(
+                PostToolUseFailureHookSpecificOutput::new(),
+                |o| &mut o.additional_context,
+            )),
+            HookSpecificOutput::UserPromptSubmit(with_context(
+                UserPromptSubmitHookSpecificOutput::new(),
+                |o| &mut o.additional_context,
+            )),
+            HookSpecificOutput::SessionStart(with_context(
+                SessionStartHookSpecificOutput::new(),
+                |o| &mut o.additional_context,
+            )),
+            HookSpecificOutput::Notification(with_context(
+                NotificationHookSpecificOutput::new(),
+                |o| &mut o.additional_context,
+            )),
+            HookSpecificOutput::SubagentStart(with_context(
+                SubagentStartHookSpecificOutput::new(),
+                |o| &mut o.additional_context,
+            )),
+            HookSpecificOutput::PermissionRequest(PermissionRequestHookSpecificOutput::new(
+                PermissionRequestVerdict::Allow {
+                    updated_input: Some(serde_json::json!({"command": "ls"})),
+                    updated_permissions: None,
+                },
+            )),
+            HookSpecificOutput::PermissionRequest(PermissionRequestHookSpecificOutput::new(
+                PermissionRequestVerdict::deny("no"),
+            )),
+        ];
+
+        for output in outputs {
+            let json = serde_json::to_value(&output).unwrap();
+            let parsed: HookSpecificOutput = serde_json::from_value(json).unwrap();
+            assert_eq!(parsed, output);
+        }
+    }
+
+    #[test]
+    fn test_hook_specific_output_rejects_unknown_event() {
+        let result = serde_json::from_value::<HookSpecificOutput>(serde_json::json!({
+            "hookEventName": "Bogus",
+            "additionalContext": "context"
+        }));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_hook_input_dispatches_on_event_name() {
+        let input: HookInput = serde_json::from_value(serde_json::json!({
+            "session_id": "session-123",
+            "transcript_path": "/path",
+            "cwd": "/home",
+            "hook_event_name": "SubagentStop",
+            "stop_hook_active": false
+        }))
+        .unwrap();
+        assert!(matches!(input, HookInput::SubagentStop(_)));
+
+        let input: HookInput = serde_json::from_value(serde_json::json!({
+            "session_id": "session-123",
+            "transcript_path": "/path",
+            "cwd": "/home",
+            "hook_event_name": "PermissionRequest",
+            "tool_name": "Bash",
+            "tool_input": {"command": "ls"}
+        }))
+        .unwrap();
+        assert!(matches!(input, HookInput::PermissionRequest(_)));
+    }
+
+    #[test]
+    fn test_hook_input_unknown_event_name() {
+        let result = serde_json::from_value::<HookInput>(serde_json::json!({
+            "session_id": "session-123",
+            "transcript_path": "/path",
+            "cwd": "/home",
+            "hook_event_name": "Bogus"
+        }));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_session_start_hook_input() {
+        let input: HookInput = serde_json::from_value(serde_json::json!({
+            "session_id": "session-123",
+            "transcript_path": "/path",
+            "cwd": "/home",
+            "hook_event_name": "SessionStart",
+            "source": "resume"
+        }))
+        .unwrap();
+        match input {
+            HookInput::SessionStart(input) => {
+                assert_eq!(input.source, SessionStartSource::Resume)
+            }
+            _ => panic!("Expected SessionStart input"),
+        }
+    }
+
+    #[test]
+    fn test_session_start_unknown_source() {
+        let input: HookInput = serde_json::from_value(serde_json::json!({
+            "session_id": "session-123",
+            "transcript_path": "/path",
+            "cwd": "/home",
+            "hook_event_name": "SessionStart",
+            "source": "something_new"
+        }))
+        .unwrap();
+        match input {
+            HookInput::SessionStart(input) => assert_eq!(
+                input.source,
+                SessionStartSource::Other("something_new".to_string())
+            ),
+            _ => panic!("Expected SessionStart input"),
+        }
+    }
+
+    #[test]
+    fn test_session_end_reason_serde() {
+        let reason: SessionEndReason = serde_json::from_str("\"prompt_input_exit\"").unwrap();
+        assert_eq!(reason, SessionEndReason::PromptInputExit);
+
+        let reason: SessionEndReason = serde_json::from_str("\"something_new\"").unwrap();
+        assert_eq!(reason, SessionEndReason::Other("something_new".to_string()));
+        assert_eq!(serde_json::to_string(&reason).unwrap(), "\"something_new\"");
+    }
+
+    #[test]
+    fn test_notification_and_subagent_start_hook_input() {
+        let input: HookInput = serde_json::from_value(serde_json::json!({
+            "session_id": "session-123",
+            "transcript_path": "/path",
+            "cwd": "/home",
+            "hook_event_name": "Notification",
+            "message": "Claude needs your permission",
+            "notification_type": "permission_prompt"
+        }))
+        .unwrap();
+        assert_eq!(input.hook_event_name(), "Notification");
+
+        let input: HookInput = serde_json::from_value(serde_json::json!({
+            "session_id": "session-123",
+            "transcript_path": "/path",
+            "cwd": "/home",
+            "hook_event_name": "SubagentStart",
+            "agent_id": "agent-1",
+            "agent_type": "code-reviewer"
+        }))
+        .unwrap();
+        match input {
+            HookInput::SubagentStart(input) => assert_eq!(input.agent_type, "code-reviewer"),
+            _ => panic!("Expected SubagentStart input"),
+        }
+    }
+
+    #[test]
+    fn test_session_start_hook_specific_output() {
+        let mut output = SessionStartHookSpecificOutput::new();
+        output.additional_context = Some("Project uses pnpm".to_string());
+        let json = serde_json::to_value(HookSpecificOutput::SessionStart(output)).unwrap();
+        assert_eq!(json["hookEventName"], "SessionStart");
+        assert_eq!(json["additionalContext"], "Project uses pnpm");
+    }
+
+    #[test]
+    fn test_permission_request_hook_specific_output() {
+        use crate::types::{PermissionBehavior, PermissionRuleValue};
+
+        let output = PermissionRequestHookSpecificOutput::new(PermissionRequestVerdict::Allow {
+            updated_input: Some(serde_json::json!({"command": "ls -la"})),
+            updated_permissions: Some(vec![PermissionUpdate::add_rules(
+                vec![PermissionRuleValue::new("Bash")],
+                PermissionBehavior::Allow,
+            )]),
+        });
+        let json = serde_json::to_value(&output).unwrap();
+        assert_eq!(json["hookEventName"], "PermissionRequest");
+        assert_eq!(json["decision"]["behavior"], "allow");
+        assert_eq!(json["decision"]["updatedInput"]["command"], "ls -la");
+        assert_eq!(
+            json["decision"]["updatedPermissions"][0]["rules"][0]["toolName"],
+            "Bash"
+        );
+
+        let deny = serde_json::to_value(PermissionRequestVerdict::deny("no")).unwrap();
+        assert_eq!(
+            deny,
+            serde_json::json!({"behavior": "deny", "message": "no"})
+        );
+    }
 }
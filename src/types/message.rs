@@ -1,10 +1,13 @@
 //! Message types for Claude SDK.
 
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
 
+use super::command::SlashCommand;
 use super::content::ContentBlock;
+use crate::error::ClaudeSDKError;
 
 /// Assistant message error types.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -145,6 +148,21 @@ impl SystemMessage {
             data,
         }
     }
+
+    /// Slash commands listed by an `init` message.
+    pub fn slash_commands(&self) -> Option<Vec<SlashCommand>> {
+        if self.subtype != "init" {
+            return None;
+        }
+        let names = self.data.get("slash_commands")?.as_array()?;
+        Some(
+            names
+                .iter()
+                .filter_map(Value::as_str)
+                .map(SlashCommand::new)
+                .collect(),
+        )
+    }
 }
 
 /// Result message with cost and usage information.
@@ -208,6 +226,35 @@ impl ResultMessage {
         self.structured_output = Some(output);
         self
     }
+
+    /// Deserialize the structured output into `T`.
+    ///
+    /// Fails with [`ClaudeSDKError::StructuredOutput`] if the run produced no
+    /// structured output or it does not match `T`.
+    ///
+    /// [`ClaudeSDKError::StructuredOutput`]: crate::error::ClaudeSDKError::StructuredOutput
+    pub fn structured_output_as<T: DeserializeOwned>(&self) -> crate::error::Result<T> {
+        let output = self.structured_output.as_ref().ok_or_else(|| {
+            let reason = if self.is_error {
+                format!("run ended with error ({})", self.subtype)
+            } else {
+                "result has no structured_output; was output_format set?".to_string()
+            };
+            ClaudeSDKError::StructuredOutput {
+                message: reason,
+                output: None,
+            }
+        })?;
+
+        serde_json::from_value(output.clone()).map_err(|e| ClaudeSDKError::StructuredOutput {
+            message: format!(
+                "output does not match {}: {}",
+                std::any::type_name::<T>(),
+                e
+            ),
+            output: Some(output.clone()),
+        })
+    }
 }
 
 /// Stream event for partial message updates during streaming.
@@ -237,7 +284,11 @@ impl StreamEvent {
 }
 
 /// Message enum representing all possible message types.
-#[derive(Debug, Clone, PartialEq)]
+///
+/// Serializes as the inner message with a `type` tag (`"user"`,
+/// `"assistant"`, `"system"`, `"result"` or `"stream_event"`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum Message {
     User(UserMessage),
     Assistant(AssistantMessage),
@@ -311,6 +362,16 @@ impl Message {
             _ => None,
         }
     }
+
+    /// Id of the `Task` tool use whose subagent produced this message.
+    pub fn parent_tool_use_id(&self) -> Option<&str> {
+        match self {
+            Self::User(msg) => msg.parent_tool_use_id.as_deref(),
+            Self::Assistant(msg) => msg.parent_tool_use_id.as_deref(),
+            Self::StreamEvent(event) => event.parent_tool_use_id.as_deref(),
+            Self::System(_) | Self::Result(_) => None,
+        }
+    }
 }
 
 impl From<UserMessage> for Message {
@@ -482,6 +543,21 @@ mod tests {
         assert_eq!(msg.data.len(), 2);
     }
 
+    #[test]
+    fn test_system_message_slash_commands() {
+        let mut data = HashMap::new();
+        data.insert("slash_commands".to_string(), json!(["compact", "review"]));
+        let init = SystemMessage::new("init", data.clone());
+        assert_eq!(
+            init.slash_commands(),
+            Some(vec![
+                SlashCommand::new("compact"),
+                SlashCommand::new("review")
+            ])
+        );
+        assert_eq!(SystemMessage::new("status", data).slash_commands(), None);
+    }
+
     #[test]
     fn test_result_message_with_usage() {
         let usage = json!({
@@ -501,6 +577,32 @@ mod tests {
         assert_eq!(msg.structured_output, Some(output));
     }
 
+    #[test]
+    fn test_result_message_structured_output_as() {
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct Extracted {
+            name: String,
+        }
+
+        let msg = ResultMessage::new("success", 1000, 800, false, 3, "session-123")
+            .with_structured_output(json!({"name": "Ada"}));
+        assert_eq!(
+            msg.structured_output_as::<Extracted>().unwrap(),
+            Extracted {
+                name: "Ada".to_string()
+            }
+        );
+
+        let msg = ResultMessage::new("success", 1000, 800, false, 3, "session-123")
+            .with_structured_output(json!({"title": "Ada"}));
+        let err = msg.structured_output_as::<Extracted>().unwrap_err();
+        assert!(err.to_string().contains("missing field `name`"));
+
+        let msg = ResultMessage::new("error_max_turns", 1000, 800, true, 3, "session-123");
+        let err = msg.structured_output_as::<Extracted>().unwrap_err();
+        assert!(err.to_string().contains("error_max_turns"));
+    }
+
     #[test]
     fn test_stream_event_with_parent_tool_use_id() {
         let event =
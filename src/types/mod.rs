@@ -1,5 +1,8 @@
 //! Type definitions for Claude Agent SDK.
 
+mod agent_config;
+mod cli;
+mod command;
 mod config;
 mod content;
 mod control;
@@ -8,8 +11,12 @@ mod mcp;
 mod message;
 mod permission;
 mod sandbox;
+mod typed_hook;
 
 // Re-export all types
+pub use agent_config::*;
+pub use cli::*;
+pub use command::*;
 pub use config::*;
 pub use content::*;
 pub use control::*;
@@ -18,3 +25,4 @@ pub use mcp::*;
 pub use message::*;
 pub use permission::*;
 pub use sandbox::*;
+pub use typed_hook::*;
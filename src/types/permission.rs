@@ -58,8 +58,9 @@ pub enum PermissionBehavior {
 /// Permission rule value.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PermissionRuleValue {
+    #[serde(alias = "toolName")]
     pub tool_name: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(alias = "ruleContent", skip_serializing_if = "Option::is_none")]
     pub rule_content: Option<String>,
 }
 
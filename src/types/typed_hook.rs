@@ -0,0 +1,1022 @@
+//! Typed hook registration for Claude SDK.
+//!
+//! These constructors wrap a strongly typed closure into a [`HookCallbackFn`],
+//! so callbacks receive the input struct for their event and return a
+//! decision type that can only express outputs valid for that event. The
+//! result is a [`TypedHook`], which carries its event with it.
+//!
+//! The constructors live on [`TypedHook`] rather than [`HookMatcher`]
+//! because a `HookMatcher` does not know its event; it is filed under one
+//! in the hooks map. Events matched against a tool name take the matcher as
+//! their first argument. The rest match all inputs by default and can be
+//! narrowed with [`TypedHook::with_matcher`], e.g. a SessionStart hook for
+//! `"resume"` only.
+
+use serde_json::Value;
+use std::future::Future;
+use std::sync::Arc;
+
+use super::hook::{
+    AsyncHookResultFn, HookCallbackFn, HookContext, HookEvent, HookInput, HookJSONOutput,
+    HookMatcher, HookPermissionDecision, HookSpecificOutput, NotificationHookInput,
+    NotificationHookSpecificOutput, PermissionRequestHookInput,
+    PermissionRequestHookSpecificOutput, PermissionRequestVerdict, PostToolUseFailureHookInput,
+    PostToolUseFailureHookSpecificOutput, PostToolUseHookInput, PostToolUseHookSpecificOutput,
+    PreCompactHookInput, PreToolUseHookInput, PreToolUseHookSpecificOutput, SessionEndHookInput,
+    SessionStartHookInput, SessionStartHookSpecificOutput, StopHookInput, SubagentStartHookInput,
+    SubagentStartHookSpecificOutput, SubagentStopHookInput, UserPromptSubmitHookInput,
+    UserPromptSubmitHookSpecificOutput,
+};
+use super::permission::PermissionUpdate;
+
+/// Output fields shared by every hook event.
+#[derive(Debug, Clone, PartialEq, Default)]
+struct CommonHookOutput {
+    should_continue: Option<bool>,
+    stop_reason: Option<String>,
+    suppress_output: Option<bool>,
+    system_message: Option<String>,
+}
+
+impl CommonHookOutput {
+    fn into_output(self) -> HookJSONOutput {
+        HookJSONOutput {
+            should_continue: self.should_continue,
+            stop_reason: self.stop_reason,
+            suppress_output: self.suppress_output,
+            system_message: self.system_message,
+            ..Default::default()
+        }
+    }
+}
+
+/// Implements the builder methods for fields shared by every hook event.
+macro_rules! impl_common_output {
+    ($ty:ident) => {
+        impl $ty {
+            /// Stop the agent after this hook, with a reason shown to the user.
+            pub fn stop(mut self, reason: impl Into<String>) -> Self {
+                self.common.should_continue = Some(false);
+                self.common.stop_reason = Some(reason.into());
+                self
+            }
+
+            /// Show a warning message to the user.
+            pub fn with_system_message(mut self, message: impl Into<String>) -> Self {
+                self.common.system_message = Some(message.into());
+                self
+            }
+
+            /// Hide the hook's stdout from transcript mode.
+            pub fn suppress_output(mut self) -> Self {
+                self.common.suppress_output = Some(true);
+                self
+            }
+        }
+    };
+}
+
+/// Decision for events that only support the common output fields
+/// (PreCompact and SessionEnd).
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct HookDecision {
+    common: CommonHookOutput,
+}
+
+impl HookDecision {
+    /// Let the CLI proceed without changes.
+    pub fn passthrough() -> Self {
+        Self::default()
+    }
+}
+
+impl_common_output!(HookDecision);
+
+impl From<HookDecision> for HookJSONOutput {
+    fn from(decision: HookDecision) -> Self {
+        decision.common.into_output()
+    }
+}
+
+/// Decision for PreToolUse hooks.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PreToolUseDecision {
+    permission_decision: Option<HookPermissionDecision>,
+    reason: Option<String>,
+    updated_input: Option<Value>,
+    common: CommonHookOutput,
+}
+
+impl PreToolUseDecision {
+    /// Let the normal permission flow decide.
+    pub fn passthrough() -> Self {
+        Self::default()
+    }
+
+    /// Allow the tool call, bypassing the permission prompt.
+    pub fn allow() -> Self {
+        Self {
+            permission_decision: Some(HookPermissionDecision::Allow),
+            ..Default::default()
+        }
+    }
+
+    /// Deny the tool call, with a reason shown to Claude.
+    pub fn deny(reason: impl Into<String>) -> Self {
+        Self {
+            permission_decision: Some(HookPermissionDecision::Deny),
+            reason: Some(reason.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Ask the user to confirm the tool call.
+    pub fn ask(reason: impl Into<String>) -> Self {
+        Self {
+            permission_decision: Some(HookPermissionDecision::Ask),
+            reason: Some(reason.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Set the reason for the permission decision.
+    pub fn with_reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+
+    /// Replace the tool input before it runs.
+    pub fn with_updated_input(mut self, input: Value) -> Self {
+        self.updated_input = Some(input);
+        self
+    }
+}
+
+impl_common_output!(PreToolUseDecision);
+
+impl From<PreToolUseDecision> for HookJSONOutput {
+    fn from(decision: PreToolUseDecision) -> Self {
+        let mut output = decision.common.into_output();
+        if decision.permission_decision.is_some()
+            || decision.reason.is_some()
+            || decision.updated_input.is_some()
+        {
+            output.hook_specific_output = Some(HookSpecificOutput::PreToolUse(
+                PreToolUseHookSpecificOutput {
+                    permission_decision: decision.permission_decision,
+                    permission_decision_reason: decision.reason,
+                    updated_input: decision.updated_input,
+                    ..PreToolUseHookSpecificOutput::new()
+                },
+            ));
+        }
+        output
+    }
+}
+
+/// Decision for PostToolUse hooks.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PostToolUseDecision {
+    block_reason: Option<String>,
+    additional_context: Option<String>,
+    common: CommonHookOutput,
+}
+
+impl PostToolUseDecision {
+    /// Accept the tool result without changes.
+    pub fn passthrough() -> Self {
+        Self::default()
+    }
+
+    /// Flag the tool result to Claude with the given feedback.
+    pub fn block(reason: impl Into<String>) -> Self {
+        Self {
+            block_reason: Some(reason.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Add context for Claude alongside the tool result.
+    pub fn with_additional_context(mut self, context: impl Into<String>) -> Self {
+        self.additional_context = Some(context.into());
+        self
+    }
+}
+
+impl_common_output!(PostToolUseDecision);
+
+impl From<PostToolUseDecision> for HookJSONOutput {
+    fn from(decision: PostToolUseDecision) -> Self {
+        let mut output = decision.common.into_output();
+        if let Some(reason) = decision.block_reason {
+            output.decision = Some("block".to_string());
+            output.reason = Some(reason);
+        }
+        if decision.additional_context.is_some() {
+            output.hook_specific_output = Some(HookSpecificOutput::PostToolUse(
+                PostToolUseHookSpecificOutput {
+                    additional_context: decision.additional_context,
+                    ..PostToolUseHookSpecificOutput::new()
+                },
+            ));
+        }
+        output
+    }
+}
+
+/// Decision for PostToolUseFailure hooks.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PostToolUseFailureDecision {
+    additional_context: Option<String>,
+    common: CommonHookOutput,
+}
+
+impl PostToolUseFailureDecision {
+    /// Report the failure without changes.
+    pub fn passthrough() -> Self {
+        Self::default()
+    }
+
+    /// Add context for Claude alongside the failure.
+    pub fn with_additional_context(mut self, context: impl Into<String>) -> Self {
+        self.additional_context = Some(context.into());
+        self
+    }
+}
+
+impl_common_output!(PostToolUseFailureDecision);
+
+impl From<PostToolUseFailureDecision> for HookJSONOutput {
+    fn from(decision: PostToolUseFailureDecision) -> Self {
+        let mut output = decision.common.into_output();
+        if decision.additional_context.is_some() {
+            output.hook_specific_output = Some(HookSpecificOutput::PostToolUseFailure(
+                PostToolUseFailureHookSpecificOutput {
+                    additional_context: decision.additional_context,
+                    ..PostToolUseFailureHookSpecificOutput::new()
+                },
+            ));
+        }
+        output
+    }
+}
+
+/// Decision for UserPromptSubmit hooks.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct UserPromptSubmitDecision {
+    block_reason: Option<String>,
+    additional_context: Option<String>,
+    common: CommonHookOutput,
+}
+
+impl UserPromptSubmitDecision {
+    /// Submit the prompt without changes.
+    pub fn passthrough() -> Self {
+        Self::default()
+    }
+
+    /// Block the prompt from being processed.
+    pub fn block(reason: impl Into<String>) -> Self {
+        Self {
+            block_reason: Some(reason.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Add context for Claude alongside the prompt.
+    pub fn with_additional_context(mut self, context: impl Into<String>) -> Self {
+        self.additional_context = Some(context.into());
+        self
+    }
+}
+
+impl_common_output!(UserPromptSubmitDecision);
+
+impl From<UserPromptSubmitDecision> for HookJSONOutput {
+    fn from(decision: UserPromptSubmitDecision) -> Self {
+        let mut output = decision.common.into_output();
+        if let Some(reason) = decision.block_reason {
+            output.decision = Some("block".to_string());
+            output.reason = Some(reason);
+        }
+        if decision.additional_context.is_some() {
+            output.hook_specific_output = Some(HookSpecificOutput::UserPromptSubmit(
+                UserPromptSubmitHookSpecificOutput {
+                    additional_context: decision.additional_context,
+                    ..UserPromptSubmitHookSpecificOutput::new()
+                },
+            ));
+        }
+        output
+    }
+}
+
+/// Decision for Stop and SubagentStop hooks.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StopDecision {
+    block_reason: Option<String>,
+    common: CommonHookOutput,
+}
+
+impl StopDecision {
+    /// Let the agent stop.
+    pub fn passthrough() -> Self {
+        Self::default()
+    }
+
+    /// Prevent the agent from stopping, telling Claude how to proceed.
+    pub fn block(reason: impl Into<String>) -> Self {
+        Self {
+            block_reason: Some(reason.into()),
+            ..Default::default()
+        }
+    }
+}
+
+impl_common_output!(StopDecision);
+
+impl From<StopDecision> for HookJSONOutput {
+    fn from(decision: StopDecision) -> Self {
+        let mut output = decision.common.into_output();
+        if let Some(reason) = decision.block_reason {
+            output.decision = Some("block".to_string());
+            output.reason = Some(reason);
+        }
+        output
+    }
+}
+
+/// Decision for events whose only specific output is additional context
+/// (SessionStart, Notification and SubagentStart).
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ContextDecision {
+    additional_context: Option<String>,
+    common: CommonHookOutput,
+}
+
+impl ContextDecision {
+    /// Proceed without adding context.
+    pub fn passthrough() -> Self {
+        Self::default()
+    }
+
+    /// Add context for Claude.
+    pub fn additional_context(context: impl Into<String>) -> Self {
+        Self {
+            additional_context: Some(context.into()),
+            ..Default::default()
+        }
+    }
+
+    fn into_output(self, event: HookEvent) -> HookJSONOutput {
+        let mut output = self.common.into_output();
+        if self.additional_context.is_some() {
+            output.hook_specific_output = match event {
+                HookEvent::SessionStart => Some(HookSpecificOutput::SessionStart(
+                    SessionStartHookSpecificOutput {
+                        additional_context: self.additional_context,
+                        ..SessionStartHookSpecificOutput::new()
+                    },
+                )),
+                HookEvent::Notification => Some(HookSpecificOutput::Notification(
+                    NotificationHookSpecificOutput {
+                        additional_context: self.additional_context,
+                        ..NotificationHookSpecificOutput::new()
+                    },
+                )),
+                HookEvent::SubagentStart => Some(HookSpecificOutput::SubagentStart(
+                    SubagentStartHookSpecificOutput {
+                        additional_context: self.additional_context,
+                        ..SubagentStartHookSpecificOutput::new()
+                    },
+                )),
+                _ => None,
+            };
+        }
+        output
+    }
+}
+
+impl_common_output!(ContextDecision);
+
+/// Decision for PermissionRequest hooks.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PermissionRequestDecision {
+    verdict: Option<PermissionRequestVerdict>,
+    common: CommonHookOutput,
+}
+
+impl PermissionRequestDecision {
+    /// Fall back to the normal permission prompt.
+    pub fn passthrough() -> Self {
+        Self::default()
+    }
+
+    /// Grant the permission request.
+    pub fn allow() -> Self {
+        Self {
+            verdict: Some(PermissionRequestVerdict::allow()),
+            ..Default::default()
+        }
+    }
+
+    /// Grant the permission request with modified tool input.
+    pub fn allow_with_input(input: Value) -> Self {
+        Self {
+            verdict: Some(PermissionRequestVerdict::Allow {
+                updated_input: Some(input),
+                updated_permissions: None,
+            }),
+            ..Default::default()
+        }
+    }
+
+    /// Grant the permission request and apply permission updates.
+    pub fn allow_with_permissions(permissions: Vec<PermissionUpdate>) -> Self {
+        Self {
+            verdict: Some(PermissionRequestVerdict::Allow {
+                updated_input: None,
+                updated_permissions: Some(permissions),
+            }),
+            ..Default::default()
+        }
+    }
+
+    /// Deny the permission request with a message for Claude.
+    pub fn deny(message: impl Into<String>) -> Self {
+        Self {
+            verdict: Some(PermissionRequestVerdict::deny(message)),
+            ..Default::default()
+        }
+    }
+
+    /// Deny the permission request and interrupt the agent.
+    pub fn deny_and_interrupt(message: impl Into<String>) -> Self {
+        Self {
+            verdict: Some(PermissionRequestVerdict::Deny {
+                message: Some(message.into()),
+                interrupt: Some(true),
+            }),
+            ..Default::default()
+        }
+    }
+}
+
+impl_common_output!(PermissionRequestDecision);
+
+impl From<PermissionRequestDecision> for HookJSONOutput {
+    fn from(decision: PermissionRequestDecision) -> Self {
+        let mut output = decision.common.into_output();
+        if let Some(verdict) = decision.verdict {
+            output.hook_specific_output = Some(HookSpecificOutput::PermissionRequest(
+                PermissionRequestHookSpecificOutput::new(verdict),
+            ));
+        }
+        output
+    }
+}
+
+/// Wrap a typed closure into an untyped hook callback.
+///
+/// `extract` pulls the event's input out of [`HookInput`]; should an input for
+/// another event arrive anyway, the callback is skipped and an empty output
+/// is returned.
+fn typed_callback<I, O, F, Fut>(
+    event: HookEvent,
+    extract: fn(HookInput) -> Option<I>,
+    convert: fn(O) -> HookJSONOutput,
+    callback: F,
+) -> HookCallbackFn
+where
+    I: Send + 'static,
+    O: 'static,
+    F: Fn(I, HookContext) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = O> + Send + 'static,
+{
+    let callback = Arc::new(callback);
+    Arc::new(move |input, _tool_use_id, context| {
+        let callback = callback.clone();
+        Box::pin(async move {
+            let received = input.hook_event_name().to_string();
+            match extract(input) {
+                Some(input) => convert(callback(input, context).await),
+                None => {
+                    tracing::warn!(
+                        "Typed {} hook received a {} input; skipping",
+                        event,
+                        received
+                    );
+                    HookJSONOutput::default()
+                }
+            }
+        })
+    })
+}
+
+/// A hook matcher bound to the event its typed callback handles.
+///
+/// Built by the typed constructors below and registered with
+/// [`ClaudeAgentOptionsBuilder::hook`](super::ClaudeAgentOptionsBuilder::hook),
+/// so a callback can never be filed under the wrong event.
+#[derive(Debug, Clone)]
+pub struct TypedHook {
+    event: HookEvent,
+    matcher: HookMatcher,
+}
+
+impl TypedHook {
+    /// Get the event this hook is registered under.
+    pub fn event(&self) -> HookEvent {
+        self.event
+    }
+
+    /// Only run for inputs this pattern matches; see [`HookMatcher::with_matcher`].
+    pub fn with_matcher(mut self, matcher: impl Into<String>) -> Self {
+        self.matcher = self.matcher.with_matcher(matcher);
+        self
+    }
+
+    /// Set the timeout in seconds for this hook.
+    pub fn with_timeout(mut self, timeout: f64) -> Self {
+        self.matcher = self.matcher.with_timeout(timeout);
+        self
+    }
+
+    /// Run this hook in the background; see [`HookMatcher::with_async`].
+    pub fn with_async(mut self, async_timeout: Option<u64>) -> Self {
+        self.matcher = self.matcher.with_async(async_timeout);
+        self
+    }
+
+    /// Set the handler for background results; see
+    /// [`HookMatcher::with_async_result_handler`].
+    pub fn with_async_result_handler(mut self, handler: AsyncHookResultFn) -> Self {
+        self.matcher = self.matcher.with_async_result_handler(handler);
+        self
+    }
+
+    /// Split into the event and the matcher to register under it.
+    pub(crate) fn into_parts(self) -> (HookEvent, HookMatcher) {
+        (self.event, self.matcher)
+    }
+
+    fn bind<I, O, F, Fut>(
+        matcher: HookMatcher,
+        event: HookEvent,
+        extract: fn(HookInput) -> Option<I>,
+        convert: fn(O) -> HookJSONOutput,
+        callback: F,
+    ) -> Self
+    where
+        I: Send + 'static,
+        O: 'static,
+        F: Fn(I, HookContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = O> + Send + 'static,
+    {
+        Self {
+            event,
+            matcher: matcher.with_hook(typed_callback(event, extract, convert, callback)),
+        }
+    }
+}
+
+impl TypedHook {
+    /// Create a typed PreToolUse callback.
+    ///
+    /// The hook is registered under [`HookEvent::PreToolUse`].
+    ///
+    /// ```
+    /// use claude_agent_sdk::types::{ClaudeAgentOptions, PreToolUseDecision, TypedHook};
+    ///
+    /// let no_rm = TypedHook::pre_tool_use("Bash", |input, _ctx| async move {
+    ///     let command = input.tool_input["command"].as_str().unwrap_or_default();
+    ///     if command.contains("rm -rf") {
+    ///         PreToolUseDecision::deny("no rm")
+    ///     } else {
+    ///         PreToolUseDecision::passthrough()
+    ///     }
+    /// });
+    /// let options = ClaudeAgentOptions::builder().hook(no_rm).build();
+    /// ```
+    pub fn pre_tool_use<F, Fut>(matcher: impl Into<String>, callback: F) -> Self
+    where
+        F: Fn(PreToolUseHookInput, HookContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = PreToolUseDecision> + Send + 'static,
+    {
+        Self::bind(
+            HookMatcher::new().with_matcher(matcher),
+            HookEvent::PreToolUse,
+            |input| match input {
+                HookInput::PreToolUse(input) => Some(input),
+                _ => None,
+            },
+            HookJSONOutput::from,
+            callback,
+        )
+    }
+
+    /// Create a typed PostToolUse callback.
+    ///
+    /// The hook is registered under [`HookEvent::PostToolUse`].
+    pub fn post_tool_use<F, Fut>(matcher: impl Into<String>, callback: F) -> Self
+    where
+        F: Fn(PostToolUseHookInput, HookContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = PostToolUseDecision> + Send + 'static,
+    {
+        Self::bind(
+            HookMatcher::new().with_matcher(matcher),
+            HookEvent::PostToolUse,
+            |input| match input {
+                HookInput::PostToolUse(input) => Some(input),
+                _ => None,
+            },
+            HookJSONOutput::from,
+            callback,
+        )
+    }
+
+    /// Create a typed PostToolUseFailure callback.
+    ///
+    /// The hook is registered under [`HookEvent::PostToolUseFailure`].
+    pub fn post_tool_use_failure<F, Fut>(matcher: impl Into<String>, callback: F) -> Self
+    where
+        F: Fn(PostToolUseFailureHookInput, HookContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = PostToolUseFailureDecision> + Send + 'static,
+    {
+        Self::bind(
+            HookMatcher::new().with_matcher(matcher),
+            HookEvent::PostToolUseFailure,
+            |input| match input {
+                HookInput::PostToolUseFailure(input) => Some(input),
+                _ => None,
+            },
+            HookJSONOutput::from,
+            callback,
+        )
+    }
+
+    /// Create a typed PermissionRequest callback.
+    ///
+    /// The hook is registered under [`HookEvent::PermissionRequest`].
+    pub fn permission_request<F, Fut>(matcher: impl Into<String>, callback: F) -> Self
+    where
+        F: Fn(PermissionRequestHookInput, HookContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = PermissionRequestDecision> + Send + 'static,
+    {
+        Self::bind(
+            HookMatcher::new().with_matcher(matcher),
+            HookEvent::PermissionRequest,
+            |input| match input {
+                HookInput::PermissionRequest(input) => Some(input),
+                _ => None,
+            },
+            HookJSONOutput::from,
+            callback,
+        )
+    }
+
+    /// Create a typed UserPromptSubmit callback.
+    ///
+    /// The hook is registered under [`HookEvent::UserPromptSubmit`].
+    pub fn user_prompt_submit<F, Fut>(callback: F) -> Self
+    where
+        F: Fn(UserPromptSubmitHookInput, HookContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = UserPromptSubmitDecision> + Send + 'static,
+    {
+        Self::bind(
+            HookMatcher::new(),
+            HookEvent::UserPromptSubmit,
+            |input| match input {
+                HookInput::UserPromptSubmit(input) => Some(input),
+                _ => None,
+            },
+            HookJSONOutput::from,
+            callback,
+        )
+    }
+
+    /// Create a typed Stop callback.
+    ///
+    /// The hook is registered under [`HookEvent::Stop`].
+    pub fn stop<F, Fut>(callback: F) -> Self
+    where
+        F: Fn(StopHookInput, HookContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = StopDecision> + Send + 'static,
+    {
+        Self::bind(
+            HookMatcher::new(),
+            HookEvent::Stop,
+            |input| match input {
+                HookInput::Stop(input) => Some(input),
+                _ => None,
+            },
+            HookJSONOutput::from,
+            callback,
+        )
+    }
+
+    /// Create a typed SubagentStop callback.
+    ///
+    /// The hook is registered under [`HookEvent::SubagentStop`].
+    pub fn subagent_stop<F, Fut>(callback: F) -> Self
+    where
+        F: Fn(SubagentStopHookInput, HookContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = StopDecision> + Send + 'static,
+    {
+        Self::bind(
+            HookMatcher::new(),
+            HookEvent::SubagentStop,
+            |input| match input {
+                HookInput::SubagentStop(input) => Some(input),
+                _ => None,
+            },
+            HookJSONOutput::from,
+            callback,
+        )
+    }
+
+    /// Create a typed PreCompact callback.
+    ///
+    /// The hook is registered under [`HookEvent::PreCompact`].
+    pub fn pre_compact<F, Fut>(callback: F) -> Self
+    where
+        F: Fn(PreCompactHookInput, HookContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = HookDecision> + Send + 'static,
+    {
+        Self::bind(
+            HookMatcher::new(),
+            HookEvent::PreCompact,
+            |input| match input {
+                HookInput::PreCompact(input) => Some(input),
+                _ => None,
+            },
+            HookJSONOutput::from,
+            callback,
+        )
+    }
+
+    /// Create a typed SessionStart callback.
+    ///
+    /// The hook is registered under [`HookEvent::SessionStart`].
+    pub fn session_start<F, Fut>(callback: F) -> Self
+    where
+        F: Fn(SessionStartHookInput, HookContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ContextDecision> + Send + 'static,
+    {
+        Self::bind(
+            HookMatcher::new(),
+            HookEvent::SessionStart,
+            |input| match input {
+                HookInput::SessionStart(input) => Some(input),
+                _ => None,
+            },
+            |decision: ContextDecision| decision.into_output(HookEvent::SessionStart),
+            callback,
+        )
+    }
+
+    /// Create a typed SessionEnd callback.
+    ///
+    /// The hook is registered under [`HookEvent::SessionEnd`].
+    pub fn session_end<F, Fut>(callback: F) -> Self
+    where
+        F: Fn(SessionEndHookInput, HookContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = HookDecision> + Send + 'static,
+    {
+        Self::bind(
+            HookMatcher::new(),
+            HookEvent::SessionEnd,
+            |input| match input {
+                HookInput::SessionEnd(input) => Some(input),
+                _ => None,
+            },
+            HookJSONOutput::from,
+            callback,
+        )
+    }
+
+    /// Create a typed Notification callback.
+    ///
+    /// The hook is registered under [`HookEvent::Notification`].
+    pub fn notification<F, Fut>(callback: F) -> Self
+    where
+        F: Fn(NotificationHookInput, HookContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ContextDecision> + Send + 'static,
+    {
+        Self::bind(
+            HookMatcher::new(),
+            HookEvent::Notification,
+            |input| match input {
+                HookInput::Notification(input) => Some(input),
+                _ => None,
+            },
+            |decision: ContextDecision| decision.into_output(HookEvent::Notification),
+            callback,
+        )
+    }
+
+    /// Create a typed SubagentStart callback.
+    ///
+    /// The hook is registered under [`HookEvent::SubagentStart`].
+    pub fn subagent_start<F, Fut>(callback: F) -> Self
+    where
+        F: Fn(SubagentStartHookInput, HookContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ContextDecision> + Send + 'static,
+    {
+        Self::bind(
+            HookMatcher::new(),
+            HookEvent::SubagentStart,
+            |input| match input {
+                HookInput::SubagentStart(input) => Some(input),
+                _ => None,
+            },
+            |decision: ContextDecision| decision.into_output(HookEvent::SubagentStart),
+            callback,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::BaseHookInput;
+    use serde_json::json;
+
+    fn base() -> BaseHookInput {
+        BaseHookInput {
+            session_id: "session-123".to_string(),
+            transcript_path: "/path".to_string(),
+            cwd: "/home".to_string(),
+            permission_mode: None,
+        }
+    }
+
+    fn pre_tool_use_input(command: &str) -> HookInput {
+        HookInput::PreToolUse(PreToolUseHookInput {
+            base: base(),
+            hook_event_name: "PreToolUse".to_string(),
+            tool_name: "Bash".to_string(),
+            tool_input: json!({"command": command}),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_pre_tool_use_typed_hook() {
+        let hook = TypedHook::pre_tool_use("Bash", |input, _ctx| async move {
+            if input.tool_input["command"]
+                .as_str()
+                .unwrap_or_default()
+                .starts_with("rm")
+            {
+                PreToolUseDecision::deny("no rm")
+            } else {
+                PreToolUseDecision::passthrough()
+            }
+        });
+        assert_eq!(hook.matcher.matcher, Some("Bash".to_string()));
+        assert_eq!(hook.matcher.hooks.len(), 1);
+
+        let callback = hook.matcher.hooks[0].clone();
+        assert_eq!(hook.event(), HookEvent::PreToolUse);
+        let output = callback(pre_tool_use_input("rm -rf /"), None, HookContext::new()).await;
+        let json = serde_json::to_value(&output).unwrap();
+        assert_eq!(json["hookSpecificOutput"]["hookEventName"], "PreToolUse");
+        assert_eq!(json["hookSpecificOutput"]["permissionDecision"], "deny");
+        assert_eq!(
+            json["hookSpecificOutput"]["permissionDecisionReason"],
+            "no rm"
+        );
+
+        let output = callback(pre_tool_use_input("ls"), None, HookContext::new()).await;
+        assert_eq!(output, HookJSONOutput::default());
+    }
+
+    #[test]
+    fn test_with_matcher_narrows_event_hooks() {
+        let hook =
+            TypedHook::session_start(|_input, _ctx| async move { ContextDecision::passthrough() });
+        assert_eq!(hook.matcher.matcher, None);
+
+        let hook = hook.with_matcher("resume");
+        assert_eq!(hook.event(), HookEvent::SessionStart);
+        assert_eq!(hook.matcher.matcher, Some("resume".to_string()));
+        assert_eq!(hook.matcher.hooks.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_typed_hook_receives_context() {
+        let hook = TypedHook::pre_tool_use("Bash", |_input, ctx| async move {
+            PreToolUseDecision::allow().with_reason(ctx.tool_use_id.unwrap_or_default())
+        });
+
+        let context = HookContext::new().with_tool_use_id(Some("tool-1".to_string()));
+        let output = hook.matcher.hooks[0](pre_tool_use_input("ls"), None, context).await;
+        match output.hook_specific_output {
+            Some(HookSpecificOutput::PreToolUse(specific)) => {
+                assert_eq!(
+                    specific.permission_decision,
+                    Some(HookPermissionDecision::Allow)
+                );
+                assert_eq!(
+                    specific.permission_decision_reason,
+                    Some("tool-1".to_string())
+                );
+            }
+            other => panic!("Expected PreToolUse output, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_typed_hook_skips_mismatched_input() {
+        let hook = TypedHook::stop(|_input, _ctx| async move { StopDecision::block("keep going") });
+
+        let output =
+            hook.matcher.hooks[0](pre_tool_use_input("ls"), None, HookContext::new()).await;
+        assert_eq!(output, HookJSONOutput::default());
+    }
+
+    #[tokio::test]
+    async fn test_stop_typed_hook_block() {
+        let hook = TypedHook::stop(|_input, _ctx| async move { StopDecision::block("keep going") });
+        let input = HookInput::Stop(StopHookInput {
+            base: base(),
+            hook_event_name: "Stop".to_string(),
+            stop_hook_active: false,
+        });
+
+        let output = hook.matcher.hooks[0](input, None, HookContext::new()).await;
+        assert_eq!(output.decision, Some("block".to_string()));
+        assert_eq!(output.reason, Some("keep going".to_string()));
+        assert!(output.hook_specific_output.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_session_start_typed_hook() {
+        let hook = TypedHook::session_start(|_input, _ctx| async move {
+            ContextDecision::additional_context("Use pnpm, not npm")
+        });
+        let input: HookInput = serde_json::from_value(json!({
+            "session_id": "session-123",
+            "transcript_path": "/path",
+            "cwd": "/home",
+            "hook_event_name": "SessionStart",
+            "source": "startup"
+        }))
+        .unwrap();
+
+        let output = hook.matcher.hooks[0](input, None, HookContext::new()).await;
+        let json = serde_json::to_value(&output).unwrap();
+        assert_eq!(json["hookSpecificOutput"]["hookEventName"], "SessionStart");
+        assert_eq!(
+            json["hookSpecificOutput"]["additionalContext"],
+            "Use pnpm, not npm"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_permission_request_typed_hook() {
+        let hook = TypedHook::permission_request("Bash", |_input, _ctx| async move {
+            PermissionRequestDecision::deny_and_interrupt("not today")
+        });
+        let input: HookInput = serde_json::from_value(json!({
+            "session_id": "session-123",
+            "transcript_path": "/path",
+            "cwd": "/home",
+            "hook_event_name": "PermissionRequest",
+            "tool_name": "Bash",
+            "tool_input": {"command": "ls"}
+        }))
+        .unwrap();
+
+        let output = hook.matcher.hooks[0](input, None, HookContext::new()).await;
+        let json = serde_json::to_value(&output).unwrap();
+        assert_eq!(json["hookSpecificOutput"]["decision"]["behavior"], "deny");
+        assert_eq!(json["hookSpecificOutput"]["decision"]["interrupt"], true);
+    }
+
+    #[test]
+    fn test_common_output_fields() {
+        let output: HookJSONOutput = PostToolUseDecision::block("bad output")
+            .with_additional_context("see logs")
+            .with_system_message("heads up")
+            .stop("done")
+            .suppress_output()
+            .into();
+
+        assert_eq!(output.should_continue, Some(false));
+        assert_eq!(output.stop_reason, Some("done".to_string()));
+        assert_eq!(output.suppress_output, Some(true));
+        assert_eq!(output.system_message, Some("heads up".to_string()));
+        assert_eq!(output.decision, Some("block".to_string()));
+        assert!(matches!(
+            output.hook_specific_output,
+            Some(HookSpecificOutput::PostToolUse(_))
+        ));
+    }
+
+    #[test]
+    fn test_hook_decision_passthrough_is_empty() {
+        let output: HookJSONOutput = HookDecision::passthrough().into();
+        assert_eq!(output, HookJSONOutput::default());
+    }
+}